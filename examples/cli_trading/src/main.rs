@@ -635,6 +635,7 @@ async fn handle_buy_pumpfun(
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
         use_exact_sol_amount: None,
+        referral: None,
     };
     match client.buy(buy_params).await {
         Ok((_, signature, _)) => {
@@ -690,6 +691,7 @@ async fn handle_buy_pumpswap(
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
         use_exact_sol_amount: None,
+        referral: None,
     };
     match client.buy(buy_params).await {
         Ok((_, signature, _)) => {
@@ -744,6 +746,7 @@ async fn handle_buy_bonk(
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
         use_exact_sol_amount: None,
+        referral: None,
     };
     match client.buy(buy_params).await {
         Ok((_, signature, _)) => {
@@ -802,6 +805,7 @@ async fn handle_buy_raydium_v4(
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
         use_exact_sol_amount: None,
+        referral: None,
     };
     match client.buy(buy_params).await {
         Ok((_, signature, _)) => {
@@ -860,6 +864,7 @@ async fn handle_buy_raydium_cpmm(
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
         use_exact_sol_amount: None,
+        referral: None,
     };
     match client.buy(buy_params).await {
         Ok((_, signature, _)) => {
@@ -1028,6 +1033,7 @@ async fn handle_sell_pumpfun(
         fixed_output_token_amount: None,
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
+        referral: None,
     };
 
     match client.sell(sell_params).await {
@@ -1086,6 +1092,7 @@ async fn handle_sell_pumpswap(
         fixed_output_token_amount: None,
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
+        referral: None,
     };
     match client.sell(sell_params).await {
         Ok((_, signature, _)) => {
@@ -1143,6 +1150,7 @@ async fn handle_sell_bonk(
         fixed_output_token_amount: None,
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
+        referral: None,
     };
     match client.sell(sell_params).await {
         Ok((_, signature, _)) => {
@@ -1203,6 +1211,7 @@ async fn handle_sell_raydium_v4(
         fixed_output_token_amount: None,
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
+        referral: None,
     };
     match client.sell(sell_params).await {
         Ok((_, signature, _)) => {
@@ -1263,6 +1272,7 @@ async fn handle_sell_raydium_cpmm(
         fixed_output_token_amount: None,
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
+        referral: None,
     };
     match client.sell(sell_params).await {
         Ok((_, signature, _)) => {
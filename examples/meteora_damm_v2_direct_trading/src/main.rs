@@ -44,6 +44,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         gas_fee_strategy: gas_fee_strategy.clone(),
         simulate: false,
         use_exact_sol_amount: None,
+        referral: None,
     };
     client.buy(buy_params).await?;
 
@@ -77,6 +78,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         fixed_output_token_amount: Some(1),
         gas_fee_strategy: gas_fee_strategy,
         simulate: false,
+        referral: None,
     };
     client.sell(sell_params).await?;
 
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::common::SolanaRpcClient;
+
+/// TTL of `account_cache()` entries until changed with `set_default_ttl`
+pub const DEFAULT_ACCOUNT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+static ACCOUNT_CACHE: Lazy<AccountCache> =
+    Lazy::new(|| AccountCache::new(DEFAULT_ACCOUNT_CACHE_TTL));
+
+/// Process-wide cache the trade builders read static accounts through: the Pump.fun global
+/// account and the mints whose owner program picks the token program. Pre-warm it with `insert`
+/// or drop stale entries with `invalidate` / `clear`.
+pub fn account_cache() -> &'static AccountCache {
+    &ACCOUNT_CACHE
+}
+
+/// Accounts that rarely change, each kept until its own expiry.
+///
+/// Only for static data such as configs and mint owners; never cache pools or bonding curves,
+/// their reserves move with every trade.
+#[derive(Debug)]
+pub struct AccountCache {
+    entries: RwLock<HashMap<Pubkey, (Account, Instant)>>,
+    default_ttl: RwLock<Duration>,
+}
+
+impl AccountCache {
+    pub fn new(default_ttl: Duration) -> Self {
+        Self { entries: RwLock::default(), default_ttl: RwLock::new(default_ttl) }
+    }
+
+    pub fn default_ttl(&self) -> Duration {
+        *self.default_ttl.read()
+    }
+
+    /// TTL of entries inserted from now on, existing entries keep their expiry
+    pub fn set_default_ttl(&self, ttl: Duration) {
+        *self.default_ttl.write() = ttl;
+    }
+
+    /// The cached account, `None` if it was never cached or has expired
+    pub fn get(&self, pubkey: &Pubkey) -> Option<Account> {
+        let entries = self.entries.read();
+        let (account, expires_at) = entries.get(pubkey)?;
+        (Instant::now() < *expires_at).then(|| account.clone())
+    }
+
+    pub fn insert(&self, pubkey: Pubkey, account: Account) {
+        self.insert_with_ttl(pubkey, account, self.default_ttl());
+    }
+
+    pub fn insert_with_ttl(&self, pubkey: Pubkey, account: Account, ttl: Duration) {
+        let now = Instant::now();
+        let mut entries = self.entries.write();
+        entries.retain(|_, (_, expires_at)| now < *expires_at);
+        entries.insert(pubkey, (account, now + ttl));
+    }
+
+    pub fn invalidate(&self, pubkey: &Pubkey) {
+        self.entries.write().remove(pubkey);
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().clear();
+    }
+
+    /// The cached account, or fetch it at the client's commitment and cache it for the default TTL
+    pub async fn get_or_fetch(&self, rpc: &SolanaRpcClient, pubkey: &Pubkey) -> Result<Account> {
+        if let Some(account) = self.get(pubkey) {
+            return Ok(account);
+        }
+        let account = rpc
+            .get_account_with_commitment(pubkey, rpc.commitment())
+            .await?
+            .value
+            .ok_or_else(|| anyhow!("Account {} not found", pubkey))?;
+        self.insert(*pubkey, account.clone());
+        Ok(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_expire_per_key() {
+        let cache = AccountCache::new(Duration::from_secs(60));
+        let (config, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        cache.insert(config, Account { lamports: 1, ..Account::default() });
+        cache.insert_with_ttl(mint, Account::default(), Duration::from_millis(20));
+        assert_eq!(cache.get(&config).map(|account| account.lamports), Some(1));
+        assert!(cache.get(&mint).is_some());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.get(&mint).is_none());
+        assert!(cache.get(&config).is_some());
+
+        cache.invalidate(&config);
+        assert!(cache.get(&config).is_none());
+    }
+}
@@ -1,10 +1,24 @@
+use crate::common::rpc_provider::RpcProvider;
 use crate::common::SolanaRpcClient;
 use anyhow::Result;
+use solana_address_lookup_table_interface::instruction;
 use solana_address_lookup_table_interface::state::AddressLookupTable;
-use solana_sdk::{message::AddressLookupTableAccount, pubkey::Pubkey};
+use solana_commitment_config::CommitmentConfig;
+use solana_hash::Hash;
+use solana_sdk::{
+    instruction::Instruction,
+    message::{v0, AddressLookupTableAccount, VersionedMessage},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+
+/// Addresses added per extend transaction, keeps each one well under the packet size limit
+pub const EXTEND_ADDRESSES_PER_TRANSACTION: usize = 30;
 
 pub async fn fetch_address_lookup_table_account(
-    rpc: &SolanaRpcClient,
+    rpc: &dyn RpcProvider,
     lookup_table_address: &Pubkey,
 ) -> Result<AddressLookupTableAccount, anyhow::Error> {
     let account = rpc.get_account(lookup_table_address).await?;
@@ -15,3 +29,122 @@ pub async fn fetch_address_lookup_table_account(
     };
     Ok(address_lookup_table_account)
 }
+
+/// Create an empty lookup table owned and paid for by `authority`, returning its address and the
+/// creation signature
+pub async fn create_lookup_table(
+    rpc: &SolanaRpcClient,
+    authority: &Keypair,
+) -> Result<(Pubkey, Signature), anyhow::Error> {
+    // The table address is derived from a slot that must still be in the SlotHashes sysvar
+    let recent_slot = rpc.get_slot_with_commitment(CommitmentConfig::finalized()).await?;
+    let (create_instruction, table) =
+        instruction::create_lookup_table(authority.pubkey(), authority.pubkey(), recent_slot);
+    let signature = send_signed(rpc, authority, create_instruction).await?;
+    Ok((table, signature))
+}
+
+/// Append `addresses` to `table`, `EXTEND_ADDRESSES_PER_TRANSACTION` per transaction.
+///
+/// New entries can be referenced from the slot after the extension lands.
+pub async fn extend_lookup_table(
+    rpc: &SolanaRpcClient,
+    table: &Pubkey,
+    authority: &Keypair,
+    addresses: &[Pubkey],
+) -> Result<Vec<Signature>, anyhow::Error> {
+    let mut signatures = Vec::new();
+    for chunk in addresses.chunks(EXTEND_ADDRESSES_PER_TRANSACTION) {
+        let extend_instruction = instruction::extend_lookup_table(
+            *table,
+            authority.pubkey(),
+            Some(authority.pubkey()),
+            chunk.to_vec(),
+        );
+        signatures.push(send_signed(rpc, authority, extend_instruction).await?);
+    }
+    Ok(signatures)
+}
+
+/// Put the accounts a strategy reuses into a new lookup table and compile `instructions` into a V0
+/// message referencing it.
+///
+/// Keep the returned table account and pass it to later builds; creating a table per trade wastes
+/// rent. The message can only be submitted from the slot after the table was extended.
+pub async fn compile_with_lookup_table(
+    rpc: &SolanaRpcClient,
+    authority: &Keypair,
+    reused_accounts: &[Pubkey],
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    recent_blockhash: Hash,
+) -> Result<(AddressLookupTableAccount, VersionedMessage), anyhow::Error> {
+    let (table, _) = create_lookup_table(rpc, authority).await?;
+    extend_lookup_table(rpc, &table, authority, reused_accounts).await?;
+    let table_account = AddressLookupTableAccount { key: table, addresses: reused_accounts.to_vec() };
+    let message = v0::Message::try_compile(
+        payer,
+        instructions,
+        std::slice::from_ref(&table_account),
+        recent_blockhash,
+    )?;
+    Ok((table_account, VersionedMessage::V0(message)))
+}
+
+async fn send_signed(
+    rpc: &SolanaRpcClient,
+    authority: &Keypair,
+    instruction: Instruction,
+) -> Result<Signature, anyhow::Error> {
+    let recent_blockhash = rpc.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        recent_blockhash,
+    );
+    Ok(rpc.send_and_confirm_transaction(&transaction).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swqos::common::{validate_size, MAX_TRANSACTION_SIZE};
+    use solana_sdk::instruction::AccountMeta;
+    use solana_sdk::transaction::VersionedTransaction;
+
+    #[test]
+    fn test_extend_chunk_fits_in_one_transaction() {
+        let authority = Keypair::new();
+        let addresses: Vec<Pubkey> =
+            (0..EXTEND_ADDRESSES_PER_TRANSACTION).map(|_| Pubkey::new_unique()).collect();
+        let extend_instruction = instruction::extend_lookup_table(
+            Pubkey::new_unique(),
+            authority.pubkey(),
+            Some(authority.pubkey()),
+            addresses,
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[extend_instruction],
+            Some(&authority.pubkey()),
+            &[&authority],
+            Hash::default(),
+        );
+        let size = validate_size(&VersionedTransaction::from(transaction)).unwrap();
+        assert!(size <= MAX_TRANSACTION_SIZE);
+    }
+
+    #[test]
+    fn test_lookup_table_shrinks_message() {
+        let payer = Pubkey::new_unique();
+        let accounts: Vec<Pubkey> = (0..20).map(|_| Pubkey::new_unique()).collect();
+        let metas = accounts.iter().map(|account| AccountMeta::new_readonly(*account, false)).collect();
+        let instructions = [Instruction::new_with_bytes(Pubkey::new_unique(), &[1], metas)];
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: accounts };
+
+        let message = v0::Message::try_compile(&payer, &instructions, &[table], Hash::default()).unwrap();
+        // Payer and program stay static, the reused accounts are loaded from the table
+        assert_eq!(message.account_keys.len(), 2);
+        assert_eq!(message.address_table_lookups[0].readonly_indexes.len(), 20);
+    }
+}
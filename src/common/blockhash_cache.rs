@@ -0,0 +1,86 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use anyhow::Result;
+use parking_lot::RwLock;
+use solana_hash::Hash;
+use tokio::task::JoinHandle;
+
+use crate::common::SolanaRpcClient;
+
+/// Latest blockhash kept fresh by a background task, so submissions skip the
+/// `getLatestBlockhash` round-trip
+#[derive(Debug, Default)]
+pub struct BlockhashCache {
+    latest: RwLock<Option<(Hash, u64)>>,
+}
+
+impl BlockhashCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch the first blockhash, then refresh it every `refresh_interval` in the background.
+    /// The refresh task stops once the last reference to the cache is dropped.
+    pub async fn start(rpc: Arc<SolanaRpcClient>, refresh_interval: Duration) -> Result<Arc<Self>> {
+        let cache = Arc::new(Self::new());
+        cache.refresh(&rpc).await?;
+        cache.spawn_refresh(rpc, refresh_interval);
+        Ok(cache)
+    }
+
+    /// Most recent `(blockhash, last_valid_block_height)`, `None` until the first refresh
+    pub fn get(&self) -> Option<(Hash, u64)> {
+        *self.latest.read()
+    }
+
+    pub fn set(&self, blockhash: Hash, last_valid_block_height: u64) {
+        *self.latest.write() = Some((blockhash, last_valid_block_height));
+    }
+
+    /// Fetch the latest blockhash at the client's commitment and store it
+    pub async fn refresh(&self, rpc: &SolanaRpcClient) -> Result<(Hash, u64)> {
+        let (blockhash, last_valid_block_height) =
+            rpc.get_latest_blockhash_with_commitment(rpc.commitment()).await?;
+        self.set(blockhash, last_valid_block_height);
+        Ok((blockhash, last_valid_block_height))
+    }
+
+    /// Refresh every `refresh_interval` until the cache is dropped; failed refreshes keep the
+    /// previous value
+    pub fn spawn_refresh(self: &Arc<Self>, rpc: Arc<SolanaRpcClient>, refresh_interval: Duration) -> JoinHandle<()> {
+        let cache: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(refresh_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let Some(cache) = cache.upgrade() else {
+                    break;
+                };
+                if let Err(e) = cache.refresh(&rpc).await {
+                    log::warn!(" [blockhash] refresh failed, keeping cached value: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_refresh_task_stops_when_cache_is_dropped() {
+        let rpc = Arc::new(SolanaRpcClient::new("http://127.0.0.1:1".to_string()));
+        let cache = Arc::new(BlockhashCache::new());
+        assert_eq!(cache.get(), None);
+        let blockhash = Hash::new_unique();
+        cache.set(blockhash, 100);
+        assert_eq!(cache.get(), Some((blockhash, 100)));
+
+        let task = cache.spawn_refresh(rpc, Duration::from_millis(10));
+        drop(cache);
+        tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use solana_sdk::signature::Keypair;
+
+/// Where `load_keypair` reads a wallet from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeypairSource {
+    /// A keypair file, either the 64-byte JSON array written by `solana-keygen` or a base58 string
+    FilePath(PathBuf),
+    /// The base58 encoding of the 64 keypair bytes, as exported by most wallets
+    Base58String(String),
+    /// The 64 keypair bytes as a JSON array, e.g. `[12,34,...]`
+    ByteArrayJson(String),
+    /// An environment variable holding either a JSON array or a base58 string
+    EnvVar(String),
+}
+
+/// Parse a keypair from `source`, failing with a description of what is malformed.
+///
+/// Error messages never include the secret itself.
+pub fn load_keypair(source: KeypairSource) -> Result<Keypair> {
+    match source {
+        KeypairSource::FilePath(path) => {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read keypair file {}", path.display()))?;
+            parse_keypair(&contents).with_context(|| format!("Invalid keypair file {}", path.display()))
+        }
+        KeypairSource::Base58String(encoded) => keypair_from_base58(encoded.trim()),
+        KeypairSource::ByteArrayJson(json) => keypair_from_json(&json),
+        KeypairSource::EnvVar(name) => {
+            let value = std::env::var(&name)
+                .with_context(|| format!("Environment variable {} is not set", name))?;
+            parse_keypair(&value).with_context(|| format!("Invalid keypair in environment variable {}", name))
+        }
+    }
+}
+
+/// JSON byte array if it looks like one, base58 otherwise
+fn parse_keypair(value: &str) -> Result<Keypair> {
+    let value = value.trim();
+    if value.starts_with('[') {
+        keypair_from_json(value)
+    } else {
+        keypair_from_base58(value)
+    }
+}
+
+fn keypair_from_base58(encoded: &str) -> Result<Keypair> {
+    let bytes = bs58::decode(encoded).into_vec().map_err(|e| anyhow!("Keypair is not valid base58: {}", e))?;
+    keypair_from_bytes(&bytes)
+}
+
+fn keypair_from_json(json: &str) -> Result<Keypair> {
+    let bytes: Vec<u8> = serde_json::from_str(json.trim())
+        .map_err(|e| anyhow!("Keypair is not a JSON array of bytes: {}", e))?;
+    keypair_from_bytes(&bytes)
+}
+
+fn keypair_from_bytes(bytes: &[u8]) -> Result<Keypair> {
+    if bytes.len() != 64 {
+        return Err(anyhow!("Keypair must be 64 bytes, got {}", bytes.len()));
+    }
+    // Rejects a public half that does not match the secret half
+    Keypair::try_from(bytes).map_err(|_| anyhow!("Keypair bytes are not a valid ed25519 keypair"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::Signer;
+
+    #[test]
+    fn test_load_keypair_formats() {
+        let keypair = Keypair::new();
+        let json = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+
+        let from_base58 = load_keypair(KeypairSource::Base58String(keypair.to_base58_string())).unwrap();
+        assert_eq!(from_base58.pubkey(), keypair.pubkey());
+        let from_json = load_keypair(KeypairSource::ByteArrayJson(json.clone())).unwrap();
+        assert_eq!(from_json.pubkey(), keypair.pubkey());
+
+        let path = std::env::temp_dir().join(format!("sol-trade-sdk-{}.json", keypair.pubkey()));
+        std::fs::write(&path, &json).unwrap();
+        let from_file = load_keypair(KeypairSource::FilePath(path.clone()));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(from_file.unwrap().pubkey(), keypair.pubkey());
+
+        assert!(load_keypair(KeypairSource::ByteArrayJson("[1,2,3]".to_string())).is_err());
+        assert!(load_keypair(KeypairSource::Base58String("not-base58!".to_string())).is_err());
+        let mut mismatched = keypair.to_bytes();
+        mismatched[63] ^= 1;
+        assert!(load_keypair(KeypairSource::Base58String(bs58::encode(mismatched).into_string())).is_err());
+    }
+}
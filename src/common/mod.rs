@@ -1,14 +1,20 @@
+pub mod account_cache;
+pub mod blockhash_cache;
 pub mod bonding_curve;
 pub mod fast_fn;
 pub mod fast_timing;
 pub mod gas_fee_strategy;
 pub mod global;
+pub mod keypair;
 pub mod nonce_cache;
+pub mod priority_fee;
+pub mod rpc_provider;
 pub mod seed;
 pub mod spl_associated_token_account;
 pub mod spl_token;
 pub mod spl_token_2022;
 pub mod subscription_handle;
+pub mod token_program;
 pub mod types;
 pub mod address_lookup;
 
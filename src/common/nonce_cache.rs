@@ -39,3 +39,25 @@ pub async fn fetch_nonce_info(
     }
     None
 }
+
+/// Read the nonce currently stored in `nonce_account`, to sign durable transactions against
+///
+/// Unlike `fetch_nonce_info`, failures are returned instead of logged.
+pub async fn fetch_nonce(
+    rpc: &SolanaRpcClient,
+    nonce_account: &Pubkey,
+) -> Result<DurableNonceInfo, anyhow::Error> {
+    let account = rpc.get_account(nonce_account).await?;
+    let versions: Versions = account
+        .state()
+        .map_err(|e| anyhow::anyhow!("{} is not a nonce account: {}", nonce_account, e))?;
+    match versions.state() {
+        State::Initialized(data) => Ok(DurableNonceInfo {
+            nonce_account: Some(*nonce_account),
+            current_nonce: Some(*data.durable_nonce.as_hash()),
+        }),
+        State::Uninitialized => {
+            Err(anyhow::anyhow!("Nonce account {} is not initialized", nonce_account))
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::common::SolanaRpcClient;
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+/// Estimate a compute unit price (micro-lamports) from `getRecentPrioritizationFees`.
+///
+/// `accounts` should be the writable accounts of the transaction, the node then reports the fees
+/// paid by recent transactions that locked them. `percentile` is 0..=100, e.g. 75 to outbid most
+/// recent traders. Returns 0 if the node has no recent samples.
+pub async fn estimate_priority_fee(
+    rpc: &SolanaRpcClient,
+    accounts: &[Pubkey],
+    percentile: u8,
+) -> Result<u64> {
+    if percentile > 100 {
+        return Err(anyhow::anyhow!("percentile must be between 0 and 100, got {}", percentile));
+    }
+    let mut fees: Vec<u64> = rpc
+        .get_recent_prioritization_fees(accounts)
+        .await?
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    Ok(percentile_fee(&mut fees, percentile))
+}
+
+/// Nearest-rank percentile of `fees`, 0 if empty
+fn percentile_fee(fees: &mut [u64], percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    let rank = (percentile as usize * fees.len()).div_ceil(100);
+    fees[rank.saturating_sub(1).min(fees.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_fee() {
+        let mut fees = vec![50, 10, 40, 20, 30];
+        assert_eq!(percentile_fee(&mut fees, 0), 10);
+        assert_eq!(percentile_fee(&mut fees, 50), 30);
+        assert_eq!(percentile_fee(&mut fees, 75), 40);
+        assert_eq!(percentile_fee(&mut fees, 100), 50);
+        assert_eq!(percentile_fee(&mut [], 90), 0);
+    }
+}
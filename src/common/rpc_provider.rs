@@ -0,0 +1,400 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_client::rpc_client::SerializableTransaction;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig};
+use solana_client::rpc_response::{Response, RpcResponseContext, RpcResult, RpcSimulateTransactionResult};
+use solana_commitment_config::CommitmentConfig;
+use solana_hash::Hash;
+use solana_sdk::clock::MAX_PROCESSING_AGE;
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_sdk::{account::Account, pubkey::Pubkey, signature::Signature, transaction::VersionedTransaction};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, TransactionStatus};
+
+use crate::common::SolanaRpcClient;
+
+/// The RPC methods the SDK calls to submit and confirm transactions.
+///
+/// Implemented for `SolanaRpcClient`; `MockRpc` scripts the responses so send, confirmation and
+/// retry logic can be tested without a network.
+#[async_trait::async_trait]
+pub trait RpcProvider: Send + Sync {
+    async fn send_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature>;
+
+    async fn get_signature_statuses(&self, signatures: &[Signature]) -> RpcResult<Vec<Option<TransactionStatus>>>;
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash>;
+
+    /// Latest blockhash and the last block height it can land in
+    async fn get_latest_blockhash_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<(Hash, u64)>;
+
+    async fn get_block_height_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<u64>;
+
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account>;
+
+    async fn get_transaction_with_config(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta>;
+
+    async fn get_slot_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<u64>;
+
+    async fn is_blockhash_valid(&self, blockhash: &Hash, commitment: CommitmentConfig) -> ClientResult<bool>;
+
+    async fn simulate_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> RpcResult<RpcSimulateTransactionResult>;
+
+    async fn get_health(&self) -> ClientResult<()>;
+
+    /// Endpoint the requests go to, used to derive the websocket url
+    fn url(&self) -> String;
+}
+
+#[async_trait::async_trait]
+impl RpcProvider for SolanaRpcClient {
+    async fn send_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        SolanaRpcClient::send_transaction_with_config(self, transaction, config).await
+    }
+
+    async fn get_signature_statuses(&self, signatures: &[Signature]) -> RpcResult<Vec<Option<TransactionStatus>>> {
+        SolanaRpcClient::get_signature_statuses(self, signatures).await
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        SolanaRpcClient::get_latest_blockhash(self).await
+    }
+
+    async fn get_latest_blockhash_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<(Hash, u64)> {
+        SolanaRpcClient::get_latest_blockhash_with_commitment(self, commitment).await
+    }
+
+    async fn get_block_height_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<u64> {
+        SolanaRpcClient::get_block_height_with_commitment(self, commitment).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+        SolanaRpcClient::get_account(self, pubkey).await
+    }
+
+    async fn get_transaction_with_config(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        SolanaRpcClient::get_transaction_with_config(self, signature, config).await
+    }
+
+    async fn get_slot_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<u64> {
+        SolanaRpcClient::get_slot_with_commitment(self, commitment).await
+    }
+
+    async fn is_blockhash_valid(&self, blockhash: &Hash, commitment: CommitmentConfig) -> ClientResult<bool> {
+        SolanaRpcClient::is_blockhash_valid(self, blockhash, commitment).await
+    }
+
+    async fn simulate_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> RpcResult<RpcSimulateTransactionResult> {
+        SolanaRpcClient::simulate_transaction_with_config(self, transaction, config).await
+    }
+
+    async fn get_health(&self) -> ClientResult<()> {
+        SolanaRpcClient::get_health(self).await
+    }
+
+    fn url(&self) -> String {
+        SolanaRpcClient::url(self)
+    }
+}
+
+/// Lets `&Arc<SolanaRpcClient>` and `&Arc<dyn RpcProvider>` be passed where `&dyn RpcProvider` is
+/// expected
+#[async_trait::async_trait]
+impl<T: RpcProvider + ?Sized> RpcProvider for Arc<T> {
+    async fn send_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        (**self).send_transaction_with_config(transaction, config).await
+    }
+
+    async fn get_signature_statuses(&self, signatures: &[Signature]) -> RpcResult<Vec<Option<TransactionStatus>>> {
+        (**self).get_signature_statuses(signatures).await
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        (**self).get_latest_blockhash().await
+    }
+
+    async fn get_latest_blockhash_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<(Hash, u64)> {
+        (**self).get_latest_blockhash_with_commitment(commitment).await
+    }
+
+    async fn get_block_height_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<u64> {
+        (**self).get_block_height_with_commitment(commitment).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+        (**self).get_account(pubkey).await
+    }
+
+    async fn get_transaction_with_config(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        (**self).get_transaction_with_config(signature, config).await
+    }
+
+    async fn get_slot_with_commitment(&self, commitment: CommitmentConfig) -> ClientResult<u64> {
+        (**self).get_slot_with_commitment(commitment).await
+    }
+
+    async fn is_blockhash_valid(&self, blockhash: &Hash, commitment: CommitmentConfig) -> ClientResult<bool> {
+        (**self).is_blockhash_valid(blockhash, commitment).await
+    }
+
+    async fn simulate_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> RpcResult<RpcSimulateTransactionResult> {
+        (**self).simulate_transaction_with_config(transaction, config).await
+    }
+
+    async fn get_health(&self) -> ClientResult<()> {
+        (**self).get_health().await
+    }
+
+    fn url(&self) -> String {
+        (**self).url()
+    }
+}
+
+/// `RpcProvider` answering from scripted responses, for tests
+///
+/// Unscripted sends succeed with the transaction's signature, unknown signatures have no status
+/// and unknown accounts are not found. Every submitted transaction is recorded in `sent`, its
+/// send config in `sent_configs`.
+#[derive(Default)]
+pub struct MockRpc {
+    send_results: Mutex<VecDeque<ClientResult<Signature>>>,
+    statuses: Mutex<HashMap<Signature, VecDeque<Option<TransactionStatus>>>>,
+    accounts: Mutex<HashMap<Pubkey, Account>>,
+    latest_blockhash: Mutex<Hash>,
+    slot: Mutex<u64>,
+    block_height: Mutex<u64>,
+    blockhash_valid: Mutex<Option<bool>>,
+    simulation: Mutex<Option<RpcSimulateTransactionResult>>,
+    sent: Mutex<Vec<VersionedTransaction>>,
+    sent_configs: Mutex<Vec<RpcSendTransactionConfig>>,
+}
+
+impl MockRpc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the outcome of the next `send_transaction_with_config`
+    pub fn push_send_result(&self, result: ClientResult<Signature>) {
+        self.send_results.lock().push_back(result);
+    }
+
+    /// Queue a status for `signature`; each poll takes the next one and the last one sticks
+    pub fn push_signature_status(&self, signature: Signature, status: Option<TransactionStatus>) {
+        self.statuses.lock().entry(signature).or_default().push_back(status);
+    }
+
+    pub fn set_account(&self, pubkey: Pubkey, account: Account) {
+        self.accounts.lock().insert(pubkey, account);
+    }
+
+    pub fn set_latest_blockhash(&self, blockhash: Hash) {
+        *self.latest_blockhash.lock() = blockhash;
+    }
+
+    pub fn set_slot(&self, slot: u64) {
+        *self.slot.lock() = slot;
+    }
+
+    /// The latest blockhash stays valid for `MAX_PROCESSING_AGE` blocks past this height
+    pub fn set_block_height(&self, block_height: u64) {
+        *self.block_height.lock() = block_height;
+    }
+
+    /// Answer of `is_blockhash_valid`, `true` by default
+    pub fn set_blockhash_valid(&self, valid: bool) {
+        *self.blockhash_valid.lock() = Some(valid);
+    }
+
+    /// Answer of every `simulate_transaction_with_config`
+    pub fn set_simulation(&self, result: RpcSimulateTransactionResult) {
+        *self.simulation.lock() = Some(result);
+    }
+
+    /// Transactions submitted so far, in order
+    pub fn sent(&self) -> Vec<VersionedTransaction> {
+        self.sent.lock().clone()
+    }
+
+    /// Send configs of the transactions in `sent`, in the same order
+    pub fn sent_configs(&self) -> Vec<RpcSendTransactionConfig> {
+        self.sent_configs.lock().clone()
+    }
+
+    fn context(&self) -> RpcResponseContext {
+        RpcResponseContext::new(*self.slot.lock())
+    }
+}
+
+fn unscripted(method: &str) -> ClientError {
+    ClientError::from(ClientErrorKind::Custom(format!("MockRpc: {} is not scripted", method)))
+}
+
+#[async_trait::async_trait]
+impl RpcProvider for MockRpc {
+    async fn send_transaction_with_config(
+        &self,
+        transaction: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        self.sent.lock().push(transaction.clone());
+        self.sent_configs.lock().push(config);
+        match self.send_results.lock().pop_front() {
+            Some(result) => result,
+            None => Ok(*transaction.get_signature()),
+        }
+    }
+
+    async fn get_signature_statuses(&self, signatures: &[Signature]) -> RpcResult<Vec<Option<TransactionStatus>>> {
+        let mut statuses = self.statuses.lock();
+        let value = signatures
+            .iter()
+            .map(|signature| {
+                let queue = statuses.get_mut(signature)?;
+                if queue.len() > 1 {
+                    queue.pop_front().flatten()
+                } else {
+                    queue.front().cloned().flatten()
+                }
+            })
+            .collect();
+        Ok(Response { context: self.context(), value })
+    }
+
+    async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        Ok(*self.latest_blockhash.lock())
+    }
+
+    async fn get_latest_blockhash_with_commitment(&self, _: CommitmentConfig) -> ClientResult<(Hash, u64)> {
+        Ok((*self.latest_blockhash.lock(), *self.block_height.lock() + MAX_PROCESSING_AGE as u64))
+    }
+
+    async fn get_block_height_with_commitment(&self, _: CommitmentConfig) -> ClientResult<u64> {
+        Ok(*self.block_height.lock())
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
+        self.accounts
+            .lock()
+            .get(pubkey)
+            .cloned()
+            .ok_or_else(|| ClientError::from(ClientErrorKind::Custom(format!("AccountNotFound: pubkey={}", pubkey))))
+    }
+
+    async fn get_transaction_with_config(
+        &self,
+        _: &Signature,
+        _: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransactionWithStatusMeta> {
+        Err(unscripted("getTransaction"))
+    }
+
+    async fn get_slot_with_commitment(&self, _: CommitmentConfig) -> ClientResult<u64> {
+        Ok(*self.slot.lock())
+    }
+
+    async fn is_blockhash_valid(&self, _: &Hash, _: CommitmentConfig) -> ClientResult<bool> {
+        Ok(self.blockhash_valid.lock().unwrap_or(true))
+    }
+
+    async fn simulate_transaction_with_config(
+        &self,
+        _: &VersionedTransaction,
+        _: RpcSimulateTransactionConfig,
+    ) -> RpcResult<RpcSimulateTransactionResult> {
+        match self.simulation.lock().clone() {
+            Some(value) => Ok(Response { context: self.context(), value }),
+            None => Err(unscripted("simulateTransaction")),
+        }
+    }
+
+    async fn get_health(&self) -> ClientResult<()> {
+        Ok(())
+    }
+
+    fn url(&self) -> String {
+        "http://mock".to_string()
+    }
+}
+
+/// Serve one JSON-RPC `body` over HTTP on a local port, for code that needs a real endpoint
+/// rather than `MockRpc`, e.g. relays with their own HTTP client.
+///
+/// Returns the endpoint url and a handle yielding the raw request once it was answered.
+#[cfg(test)]
+pub(crate) async fn serve_json_rpc_once(body: String) -> (String, tokio::task::JoinHandle<String>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            let Some(headers_end) = text.find("\r\n\r\n") else {
+                assert!(read > 0, "connection closed before the request headers");
+                continue;
+            };
+            let content_length = text[..headers_end]
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length").then(|| value.trim().to_string())
+                })
+                .map_or(0, |length| length.parse::<usize>().unwrap());
+            if request.len() >= headers_end + 4 + content_length || read == 0 {
+                break;
+            }
+        }
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8(request).unwrap()
+    });
+    (url, server)
+}
@@ -34,11 +34,24 @@ pub fn start_rent_updater(client: Arc<SolanaRpcClient>) {
     });
 }
 
+/// SPL token 账户的字节数，创建 ATA 时按此大小计算租金
+pub(crate) const TOKEN_ACCOUNT_SIZE: usize = 165;
+
+/// 查询 `data_len` 字节账户免租所需的最低 lamports（`getMinimumBalanceForRentExemption`）
+///
+/// 创建账户时使用此值而不是写死的租金，租金参数变化后依然正确
+pub async fn rent_exempt_lamports(
+    client: &SolanaRpcClient,
+    data_len: usize,
+) -> Result<u64, anyhow::Error> {
+    Ok(client.get_minimum_balance_for_rent_exemption(data_len).await?)
+}
+
 async fn fetch_rent_for_token_account(
     client: &SolanaRpcClient,
     _is_2022_token: bool,
 ) -> Result<u64, anyhow::Error> {
-    Ok(client.get_minimum_balance_for_rent_exemption(165).await?)
+    rent_exempt_lamports(client, TOKEN_ACCOUNT_SIZE).await
 }
 
 pub fn create_associated_token_account_use_seed(
@@ -1,8 +1,11 @@
+use anyhow::Result;
 use solana_sdk::{
     message::{AccountMeta, Instruction},
     pubkey::Pubkey,
 };
 
+use crate::common::token_program::detect_token_program;
+use crate::common::SolanaRpcClient;
 use crate::constants::{ASSOCIATED_TOKEN_PROGRAM_ID, SYSTEM_PROGRAM};
 
 /// Get the associated token address with a specified token program ID.
@@ -69,3 +72,23 @@ pub fn create_associated_token_account_idempotent(
         data: vec![instruction],
     }
 }
+
+/// Get the associated token account of `owner` for `mint`, with a create instruction only when
+/// the account does not exist yet.
+///
+/// The token program (legacy Token or Token-2022) is detected from the mint's owner. The create
+/// instruction is the idempotent variant, paid for by `payer`.
+pub async fn ensure_ata(
+    rpc: &SolanaRpcClient,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<(Pubkey, Option<Instruction>)> {
+    let token_program = detect_token_program(rpc, mint).await?.id();
+    let ata = get_associated_token_address_with_program_id(owner, mint, &token_program);
+    let exists = rpc.get_account_with_commitment(&ata, rpc.commitment()).await?.value.is_some();
+    let create = (!exists).then(|| {
+        create_associated_token_account_idempotent(payer, owner, mint, &token_program)
+    });
+    Ok((ata, create))
+}
@@ -1,3 +1,8 @@
+use std::time::Duration;
+
+use futures::future::{join_all, BoxFuture};
+use parking_lot::Mutex;
+use solana_client::nonblocking::pubsub_client::{PubsubClient, PubsubClientError};
 use tokio::task::JoinHandle;
 
 pub struct SubscriptionHandle {
@@ -11,3 +16,68 @@ impl SubscriptionHandle {
         self.task.abort();
     }
 }
+
+/// How long `PubsubGuard` waits for the server to acknowledge unsubscribes before closing anyway
+const UNSUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Unsubscribe function returned with every `PubsubClient` subscription
+pub type UnsubscribeFn = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+/// A `PubsubClient` connection that cleans up after itself.
+///
+/// `close` unsubscribes every tracked subscription and closes the socket. Dropping the guard
+/// without closing it, e.g. when a confirmation future is cancelled or times out, does the same on
+/// a background task, so a long-running bot leaks neither subscriptions nor file descriptors.
+/// Subscription streams borrow the client and must be dropped before the guard.
+pub struct PubsubGuard {
+    client: Option<PubsubClient>,
+    unsubscribes: Mutex<Vec<UnsubscribeFn>>,
+}
+
+impl PubsubGuard {
+    pub async fn connect(ws_url: &str) -> Result<Self, PubsubClientError> {
+        let client = PubsubClient::new(ws_url).await?;
+        Ok(Self { client: Some(client), unsubscribes: Mutex::default() })
+    }
+
+    pub fn client(&self) -> &PubsubClient {
+        // Only taken by `close` and `drop`, which consume the guard
+        self.client.as_ref().expect("pubsub client already closed")
+    }
+
+    /// Unsubscribe with `unsubscribe` when the guard is closed or dropped
+    pub fn track(&self, unsubscribe: UnsubscribeFn) {
+        self.unsubscribes.lock().push(unsubscribe);
+    }
+
+    /// Unsubscribe everything and close the connection
+    pub async fn close(mut self) {
+        if let Some(client) = self.client.take() {
+            shutdown(client, std::mem::take(self.unsubscribes.get_mut())).await;
+        }
+    }
+}
+
+impl Drop for PubsubGuard {
+    fn drop(&mut self) {
+        let Some(client) = self.client.take() else {
+            return;
+        };
+        let unsubscribes = std::mem::take(self.unsubscribes.get_mut());
+        // Without a runtime the connection task is gone already; dropping the client is all that
+        // is left to do
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(shutdown(client, unsubscribes));
+        }
+    }
+}
+
+async fn shutdown(client: PubsubClient, unsubscribes: Vec<UnsubscribeFn>) {
+    let unsubscribed = join_all(unsubscribes.into_iter().map(|unsubscribe| unsubscribe()));
+    if tokio::time::timeout(UNSUBSCRIBE_TIMEOUT, unsubscribed).await.is_err() {
+        log::debug!(" [pubsub] unsubscribe not acknowledged, closing anyway");
+    }
+    if let Err(e) = client.shutdown().await {
+        log::debug!(" [pubsub] closing connection failed: {}", e);
+    }
+}
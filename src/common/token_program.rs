@@ -0,0 +1,183 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::common::account_cache::account_cache;
+use crate::common::SolanaRpcClient;
+use crate::constants::{TOKEN_PROGRAM, TOKEN_PROGRAM_2022};
+
+/// Size of the base SPL mint layout, shared by both token programs
+const MINT_LEN: usize = 82;
+/// Token-2022 pads the base layout to the account size before the account type byte
+const ACCOUNT_TYPE_OFFSET: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const EXTENSION_TYPE_TRANSFER_FEE_CONFIG: u16 = 1;
+/// Two optional authorities and the withheld amount precede the two fee schedules
+const TRANSFER_FEE_CONFIG_FEES_OFFSET: usize = 72;
+const TRANSFER_FEE_LEN: usize = 18;
+
+/// Token program owning a mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenProgram {
+    /// Legacy SPL Token program
+    Token,
+    /// Token-2022 (token extensions) program
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn id(&self) -> Pubkey {
+        match self {
+            TokenProgram::Token => TOKEN_PROGRAM,
+            TokenProgram::Token2022 => TOKEN_PROGRAM_2022,
+        }
+    }
+
+    /// `None` when `program_id` is not a token program
+    pub fn from_program_id(program_id: &Pubkey) -> Option<Self> {
+        if *program_id == TOKEN_PROGRAM {
+            Some(TokenProgram::Token)
+        } else if *program_id == TOKEN_PROGRAM_2022 {
+            Some(TokenProgram::Token2022)
+        } else {
+            None
+        }
+    }
+}
+
+/// Token-2022 transfer fee in effect for an epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFee {
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    /// Fee withheld from a transfer of `amount`, rounded up and capped at `maximum_fee`
+    pub fn calculate_fee(&self, amount: u64) -> u64 {
+        if self.transfer_fee_basis_points == 0 || amount == 0 {
+            return 0;
+        }
+        let fee = (amount as u128 * self.transfer_fee_basis_points as u128).div_ceil(10_000);
+        (fee as u64).min(self.maximum_fee)
+    }
+
+    /// Amount that arrives at the destination after the fee is withheld
+    pub fn amount_after_fee(&self, amount: u64) -> u64 {
+        amount.saturating_sub(self.calculate_fee(amount))
+    }
+
+    fn from_bytes(data: &[u8]) -> Self {
+        Self {
+            epoch: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+            maximum_fee: u64::from_le_bytes(data[8..16].try_into().unwrap()),
+            transfer_fee_basis_points: u16::from_le_bytes(data[16..18].try_into().unwrap()),
+        }
+    }
+}
+
+/// Mint details that affect how trades are built
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MintInfo {
+    pub token_program: TokenProgram,
+    pub decimals: u8,
+    /// Transfer fee for the current epoch, set only for Token-2022 mints with a transfer fee
+    pub transfer_fee: Option<TransferFee>,
+}
+
+/// Detect the token program owning `mint`, read through `account_cache()`
+pub async fn detect_token_program(rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<TokenProgram> {
+    let owner = account_cache().get_or_fetch(rpc, mint).await?.owner;
+    TokenProgram::from_program_id(&owner)
+        .ok_or_else(|| anyhow!("{} is not a token mint, owned by {}", mint, owner))
+}
+
+/// Fetch the token program, decimals and current transfer fee of `mint`
+pub async fn detect_mint(rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<MintInfo> {
+    let account = rpc
+        .get_account_with_commitment(mint, rpc.commitment())
+        .await?
+        .value
+        .ok_or_else(|| anyhow!("Mint {} not found", mint))?;
+    let token_program = TokenProgram::from_program_id(&account.owner)
+        .ok_or_else(|| anyhow!("{} is not a token mint, owned by {}", mint, account.owner))?;
+    if account.data.len() < MINT_LEN {
+        return Err(anyhow!("Mint {} account data is too short", mint));
+    }
+    let decimals = account.data[44];
+
+    let transfer_fee = match (token_program, transfer_fee_schedule(&account.data)) {
+        (TokenProgram::Token2022, Some((older, newer))) => {
+            let epoch = rpc.get_epoch_info().await?.epoch;
+            Some(if epoch >= newer.epoch { newer } else { older })
+        }
+        _ => None,
+    };
+    Ok(MintInfo { token_program, decimals, transfer_fee })
+}
+
+/// Older and newer fee schedules of the Token-2022 transfer fee extension, if present
+fn transfer_fee_schedule(mint_data: &[u8]) -> Option<(TransferFee, TransferFee)> {
+    if mint_data.get(ACCOUNT_TYPE_OFFSET) != Some(&ACCOUNT_TYPE_MINT) {
+        return None;
+    }
+    let mut offset = ACCOUNT_TYPE_OFFSET + 1;
+    while offset + 4 <= mint_data.len() {
+        let extension_type = u16::from_le_bytes([mint_data[offset], mint_data[offset + 1]]);
+        let length = u16::from_le_bytes([mint_data[offset + 2], mint_data[offset + 3]]) as usize;
+        let value = mint_data.get(offset + 4..offset + 4 + length)?;
+        if extension_type == EXTENSION_TYPE_TRANSFER_FEE_CONFIG {
+            let fees = value.get(
+                TRANSFER_FEE_CONFIG_FEES_OFFSET..TRANSFER_FEE_CONFIG_FEES_OFFSET + 2 * TRANSFER_FEE_LEN,
+            )?;
+            return Some((
+                TransferFee::from_bytes(&fees[..TRANSFER_FEE_LEN]),
+                TransferFee::from_bytes(&fees[TRANSFER_FEE_LEN..]),
+            ));
+        }
+        offset += 4 + length;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee_bytes(fee: &TransferFee) -> Vec<u8> {
+        let mut bytes = fee.epoch.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&fee.maximum_fee.to_le_bytes());
+        bytes.extend_from_slice(&fee.transfer_fee_basis_points.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_transfer_fee_schedule_is_parsed_from_mint_extensions() {
+        let older = TransferFee { epoch: 0, maximum_fee: u64::MAX, transfer_fee_basis_points: 100 };
+        let newer = TransferFee { epoch: 600, maximum_fee: 5_000, transfer_fee_basis_points: 250 };
+
+        let mut data = vec![0u8; ACCOUNT_TYPE_OFFSET];
+        data.push(ACCOUNT_TYPE_MINT);
+        // An unrelated extension comes first
+        data.extend_from_slice(&3u16.to_le_bytes());
+        data.extend_from_slice(&32u16.to_le_bytes());
+        data.extend_from_slice(&[7u8; 32]);
+        let mut config = vec![0u8; TRANSFER_FEE_CONFIG_FEES_OFFSET];
+        config.extend(fee_bytes(&older));
+        config.extend(fee_bytes(&newer));
+        data.extend_from_slice(&EXTENSION_TYPE_TRANSFER_FEE_CONFIG.to_le_bytes());
+        data.extend_from_slice(&(config.len() as u16).to_le_bytes());
+        data.extend(config);
+
+        assert_eq!(transfer_fee_schedule(&data), Some((older, newer)));
+        assert_eq!(transfer_fee_schedule(&data[..MINT_LEN]), None);
+    }
+
+    #[test]
+    fn test_transfer_fee_rounds_up_and_caps() {
+        let fee = TransferFee { epoch: 0, maximum_fee: 5_000, transfer_fee_basis_points: 250 };
+        assert_eq!(fee.calculate_fee(1_001), 26);
+        assert_eq!(fee.calculate_fee(1_000_000), 5_000);
+        assert_eq!(fee.amount_after_fee(1_001), 975);
+    }
+}
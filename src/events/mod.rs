@@ -0,0 +1,5 @@
+//! Decoders for program events emitted in transaction logs
+
+pub mod pumpfun;
+
+pub use pumpfun::{parse_pumpfun_trade, parse_pumpfun_trades, TradeEvent};
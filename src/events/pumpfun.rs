@@ -0,0 +1,129 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::BorshDeserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::instruction::utils::pumpfun::{accounts::PUMPFUN, TRADE_EVENT_DISCRIMINATOR};
+
+/// A Pump.fun bonding curve buy or sell, decoded from the program's Anchor `TradeEvent`
+///
+/// Reserves are the curve's state after the trade. Only the leading fields of the event are
+/// decoded, so later additions to the event do not break parsing.
+#[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize)]
+pub struct TradeEvent {
+    pub mint: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: Pubkey,
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub real_token_reserves: u64,
+}
+
+impl TradeEvent {
+    /// Fill price in SOL per whole token
+    pub fn price_in_sol(&self) -> f64 {
+        crate::utils::price::pumpfun::price_token_in_sol(self.sol_amount, self.token_amount)
+    }
+}
+
+/// First Pump.fun trade in `logs`, see `parse_pumpfun_trades`
+pub fn parse_pumpfun_trade(logs: &[String]) -> Option<TradeEvent> {
+    parse_pumpfun_trades(logs).into_iter().next()
+}
+
+/// Every Pump.fun trade in the log messages of one transaction, in execution order
+///
+/// Only `Program data:` lines logged while the Pump.fun program is executing are considered, so
+/// events of other programs with a colliding layout are ignored.
+pub fn parse_pumpfun_trades(logs: &[String]) -> Vec<TradeEvent> {
+    let pumpfun = PUMPFUN.to_string();
+    let mut invocations: Vec<&str> = Vec::new();
+    let mut trades = Vec::new();
+    for log in logs {
+        let Some(rest) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if invocations.last() == Some(&pumpfun.as_str()) {
+                trades.extend(decode_trade_event(data));
+            }
+        } else if let Some((program, status)) = rest.split_once(' ') {
+            if status.starts_with("invoke [") {
+                invocations.push(program);
+            } else if status == "success" || status.starts_with("failed") {
+                invocations.pop();
+            }
+        }
+    }
+    trades
+}
+
+fn decode_trade_event(data: &str) -> Option<TradeEvent> {
+    let bytes = STANDARD.decode(data).ok()?;
+    let payload = bytes.strip_prefix(&TRADE_EVENT_DISCRIMINATOR)?;
+    TradeEvent::deserialize(&mut &payload[..]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade_log(event: &TradeEvent) -> String {
+        let mut bytes = TRADE_EVENT_DISCRIMINATOR.to_vec();
+        bytes.extend_from_slice(event.mint.as_ref());
+        bytes.extend_from_slice(&event.sol_amount.to_le_bytes());
+        bytes.extend_from_slice(&event.token_amount.to_le_bytes());
+        bytes.push(event.is_buy as u8);
+        bytes.extend_from_slice(event.user.as_ref());
+        bytes.extend_from_slice(&event.timestamp.to_le_bytes());
+        for reserve in [
+            event.virtual_sol_reserves,
+            event.virtual_token_reserves,
+            event.real_sol_reserves,
+            event.real_token_reserves,
+        ] {
+            bytes.extend_from_slice(&reserve.to_le_bytes());
+        }
+        // Trailing fields of newer program versions
+        bytes.extend_from_slice(&[0u8; 48]);
+        format!("Program data: {}", STANDARD.encode(bytes))
+    }
+
+    #[test]
+    fn test_parse_pumpfun_trades_only_inside_pumpfun() {
+        let buy = TradeEvent {
+            mint: Pubkey::new_unique(),
+            sol_amount: 1_000_000_000,
+            token_amount: 35_000_000_000_000,
+            is_buy: true,
+            user: Pubkey::new_unique(),
+            timestamp: 1_700_000_000,
+            virtual_sol_reserves: 31_000_000_000,
+            virtual_token_reserves: 1_038_000_000_000_000,
+            real_sol_reserves: 1_000_000_000,
+            real_token_reserves: 758_100_000_000_000,
+        };
+        let sell = TradeEvent { is_buy: false, sol_amount: 500_000_000, ..buy.clone() };
+        let other = Pubkey::new_unique().to_string();
+        let logs = vec![
+            format!("Program {} invoke [1]", other),
+            format!("Program {} invoke [2]", PUMPFUN),
+            trade_log(&buy),
+            format!("Program {} success", PUMPFUN),
+            // Same bytes logged by another program are not a Pump.fun trade
+            trade_log(&buy),
+            format!("Program {} invoke [2]", PUMPFUN),
+            "Program log: Instruction: Sell".to_string(),
+            trade_log(&sell),
+            format!("Program {} success", PUMPFUN),
+            format!("Program {} success", other),
+        ];
+
+        assert_eq!(parse_pumpfun_trades(&logs), vec![buy.clone(), sell]);
+        assert_eq!(parse_pumpfun_trade(&logs), Some(buy));
+        assert_eq!(parse_pumpfun_trade(&logs[..2]), None);
+    }
+}
@@ -0,0 +1,95 @@
+//! Yellowstone gRPC subscriptions
+//!
+//! Every subscription runs on a background task that reconnects with exponential backoff when
+//! the stream fails, so it survives node restarts. Dropping the returned stream stops the task.
+
+pub mod pumpfun;
+
+pub use pumpfun::{subscribe_pumpfun_creates, CreateEvent};
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures::{SinkExt, Stream, StreamExt};
+use tokio::sync::mpsc::UnboundedSender;
+use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient};
+use yellowstone_grpc_proto::prelude::{
+    subscribe_update::UpdateOneof, SubscribeRequest, SubscribeRequestPing, SubscribeUpdate,
+};
+
+const RECONNECT_BACKOFF_INITIAL: Duration = Duration::from_millis(500);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run `request` against `endpoint` and yield what `handle` extracts from every update
+pub(crate) fn subscribe<T, F>(
+    endpoint: String,
+    x_token: Option<String>,
+    request: SubscribeRequest,
+    mut handle: F,
+) -> impl Stream<Item = T>
+where
+    T: Send + 'static,
+    F: FnMut(SubscribeUpdate) -> Vec<T> + Send + 'static,
+{
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut backoff = RECONNECT_BACKOFF_INITIAL;
+        loop {
+            let result =
+                run_subscription(&endpoint, &x_token, &request, &tx, &mut handle, &mut backoff)
+                    .await;
+            if tx.is_closed() {
+                return;
+            }
+            if let Err(e) = result {
+                log::warn!(" [grpc] {} subscription failed, reconnecting in {:?}: {}", endpoint, backoff, e);
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+        }
+    });
+    futures::stream::poll_fn(move |cx| rx.poll_recv(cx))
+}
+
+/// One connection's lifetime. Returns once the receiver is gone, errors when the stream breaks
+async fn run_subscription<T, F>(
+    endpoint: &str,
+    x_token: &Option<String>,
+    request: &SubscribeRequest,
+    tx: &UnboundedSender<T>,
+    handle: &mut F,
+    backoff: &mut Duration,
+) -> Result<()>
+where
+    F: FnMut(SubscribeUpdate) -> Vec<T>,
+{
+    let mut builder = GeyserGrpcClient::build_from_shared(endpoint.to_string())?
+        .x_token(x_token.clone())?
+        .connect_timeout(CONNECT_TIMEOUT);
+    if endpoint.starts_with("https") {
+        builder = builder.tls_config(ClientTlsConfig::new().with_native_roots())?;
+    }
+    let mut client = builder.connect().await?;
+    let (mut sink, mut stream) = client.subscribe_with_request(Some(request.clone())).await?;
+
+    while let Some(update) = stream.next().await {
+        let update = update?;
+        *backoff = RECONNECT_BACKOFF_INITIAL;
+        if let Some(UpdateOneof::Ping(_)) = update.update_oneof {
+            // Answer server pings so load balancers keep the idle stream open
+            sink.send(SubscribeRequest {
+                ping: Some(SubscribeRequestPing { id: 1 }),
+                ..Default::default()
+            })
+            .await?;
+            continue;
+        }
+        for item in handle(update) {
+            if tx.send(item).is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Err(anyhow!("stream closed by the server"))
+}
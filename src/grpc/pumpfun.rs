@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use futures::Stream;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use yellowstone_grpc_proto::prelude::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterTransactions, SubscribeUpdate,
+};
+
+use crate::instruction::utils::pumpfun::{
+    accounts::PUMPFUN, CREATE_DISCRIMINATOR, CREATE_V2_DISCRIMINATOR,
+};
+
+/// A Pump.fun token launch, from a `create` or `create_v2` instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateEvent {
+    pub signature: Signature,
+    pub slot: u64,
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub bonding_curve: Pubkey,
+    /// Unix seconds at which the node produced the update
+    pub timestamp: i64,
+}
+
+/// Stream every Pump.fun token creation seen by the Yellowstone node at `endpoint`, at processed
+/// commitment
+///
+/// Creates issued through other programs (CPI) are included. Must be called within a Tokio
+/// runtime.
+pub fn subscribe_pumpfun_creates(
+    endpoint: impl Into<String>,
+    x_token: Option<String>,
+) -> impl Stream<Item = CreateEvent> {
+    let filter = SubscribeRequestFilterTransactions {
+        vote: Some(false),
+        failed: Some(false),
+        account_include: vec![PUMPFUN.to_string()],
+        ..Default::default()
+    };
+    let request = SubscribeRequest {
+        transactions: HashMap::from([("pumpfun_creates".to_string(), filter)]),
+        commitment: Some(CommitmentLevel::Processed as i32),
+        ..Default::default()
+    };
+    super::subscribe(endpoint.into(), x_token, request, create_events)
+}
+
+fn create_events(update: SubscribeUpdate) -> Vec<CreateEvent> {
+    let timestamp = update
+        .created_at
+        .map(|created_at| created_at.seconds)
+        .unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let Some(UpdateOneof::Transaction(transaction_update)) = update.update_oneof else {
+        return Vec::new();
+    };
+    let Some(info) = transaction_update.transaction else {
+        return Vec::new();
+    };
+    let Ok(signature) = Signature::try_from(info.signature.as_slice()) else {
+        return Vec::new();
+    };
+    let Some(message) = info.transaction.and_then(|transaction| transaction.message) else {
+        return Vec::new();
+    };
+
+    // Static keys first, then the lookup table addresses, as the runtime orders them
+    let meta = info.meta.unwrap_or_default();
+    let keys: Vec<Pubkey> = message
+        .account_keys
+        .iter()
+        .chain(&meta.loaded_writable_addresses)
+        .chain(&meta.loaded_readonly_addresses)
+        .filter_map(|key| Pubkey::try_from(key.as_slice()).ok())
+        .collect();
+
+    let outer = message.instructions.iter().map(|ix| (ix.program_id_index, &ix.accounts, &ix.data));
+    let inner = meta
+        .inner_instructions
+        .iter()
+        .flat_map(|inner| &inner.instructions)
+        .map(|ix| (ix.program_id_index, &ix.accounts, &ix.data));
+
+    outer
+        .chain(inner)
+        .filter(|(program_id_index, _, _)| keys.get(*program_id_index as usize) == Some(&PUMPFUN))
+        .filter_map(|(_, accounts, data)| {
+            let accounts: Vec<Pubkey> =
+                accounts.iter().filter_map(|index| keys.get(*index as usize).copied()).collect();
+            parse_create_instruction(&accounts, data)
+        })
+        .map(|(mint, bonding_curve, creator)| CreateEvent {
+            signature,
+            slot: transaction_update.slot,
+            mint,
+            creator,
+            bonding_curve,
+            timestamp,
+        })
+        .collect()
+}
+
+/// `(mint, bonding_curve, creator)` of a Pump.fun create instruction, `None` for other instructions
+///
+/// Both versions take `name`, `symbol` and `uri` strings followed by the creator, and list the
+/// mint and bonding curve as accounts 0 and 2.
+pub(crate) fn parse_create_instruction(
+    accounts: &[Pubkey],
+    data: &[u8],
+) -> Option<(Pubkey, Pubkey, Pubkey)> {
+    let discriminator = data.get(..8)?;
+    if discriminator != CREATE_DISCRIMINATOR && discriminator != CREATE_V2_DISCRIMINATOR {
+        return None;
+    }
+    let mut offset = 8;
+    for _ in 0..3 {
+        let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        offset += 4 + len;
+    }
+    let creator = Pubkey::try_from(data.get(offset..offset + 32)?).ok()?;
+    Some((*accounts.first()?, *accounts.get(2)?, creator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_create_instruction() {
+        let creator = Pubkey::new_unique();
+        let mut data = CREATE_DISCRIMINATOR.to_vec();
+        for field in ["Token", "TKN", "https://example.com/meta.json"] {
+            data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            data.extend_from_slice(field.as_bytes());
+        }
+        data.extend_from_slice(creator.as_ref());
+        let accounts: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+
+        assert_eq!(
+            parse_create_instruction(&accounts, &data),
+            Some((accounts[0], accounts[2], creator))
+        );
+        assert_eq!(parse_create_instruction(&accounts, &data[..data.len() - 1]), None);
+        assert_eq!(parse_create_instruction(&accounts, &[0u8; 64]), None);
+    }
+}
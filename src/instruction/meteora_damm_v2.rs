@@ -8,6 +8,7 @@ use crate::{
 use anyhow::{anyhow, Result};
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
     signer::Signer,
 };
 
@@ -103,7 +104,7 @@ impl InstructionBuilder for MeteoraDammV2InstructionBuilder {
             AccountMeta::new(params.payer.pubkey(), true), // User Transfer Authority
             AccountMeta::new_readonly(protocol_params.token_a_program, false), // Token Program (readonly)
             AccountMeta::new_readonly(protocol_params.token_b_program, false), // Token Program (readonly)
-            accounts::METEORA_DAMM_V2_META, // Referral Token Account (readonly)
+            referral_meta(params.referral),                              // Referral Token Account
             AccountMeta::new_readonly(get_event_authority_pda(), false), // Event Authority (readonly)
             accounts::METEORA_DAMM_V2_META,                              // Program (readonly)
         ];
@@ -201,7 +202,7 @@ impl InstructionBuilder for MeteoraDammV2InstructionBuilder {
             AccountMeta::new(params.payer.pubkey(), true), // User Transfer Authority
             AccountMeta::new_readonly(protocol_params.token_a_program, false), // Token Program (readonly)
             AccountMeta::new_readonly(protocol_params.token_b_program, false), // Token Program (readonly)
-            accounts::METEORA_DAMM_V2_META, // Referral Token Account (readonly)
+            referral_meta(params.referral),                              // Referral Token Account
             AccountMeta::new_readonly(get_event_authority_pda(), false), // Event Authority (readonly)
             accounts::METEORA_DAMM_V2_META,                              // Program (readonly)
         ];
@@ -237,3 +238,11 @@ impl InstructionBuilder for MeteoraDammV2InstructionBuilder {
         Ok(instructions)
     }
 }
+
+/// The referral token account, or the program itself to leave the optional account empty
+fn referral_meta(referral: Option<Pubkey>) -> AccountMeta {
+    match referral {
+        Some(referral) => AccountMeta::new(referral, false),
+        None => accounts::METEORA_DAMM_V2_META,
+    }
+}
@@ -4,4 +4,5 @@ pub mod bonk;
 pub mod raydium_cpmm;
 pub mod raydium_amm_v4;
 pub mod meteora_damm_v2;
+pub mod pool_discovery;
 pub mod utils;
\ No newline at end of file
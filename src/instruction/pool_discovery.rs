@@ -0,0 +1,227 @@
+use anyhow::Result;
+use futures::future::try_join_all;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_commitment_config::CommitmentConfig;
+use solana_rpc_client_api::config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::common::SolanaRpcClient;
+use crate::constants::WSOL_TOKEN_ACCOUNT;
+use crate::instruction::utils::{pumpswap, raydium_cpmm};
+use crate::trading::factory::DexType;
+
+/// `getMultipleAccounts` accepts at most this many keys per call
+pub(crate) const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+/// Offset of the amount in an SPL token account, same for Token-2022
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+/// A pool pairing a mint with SOL, found by `discover_pools`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolInfo {
+    pub dex_type: DexType,
+    pub address: Pubkey,
+    /// Always WSOL
+    pub quote_mint: Pubkey,
+    /// Vault balance of the mint
+    pub token_reserve: u64,
+    /// Vault balance of the quote mint, used to rank pools by liquidity
+    pub quote_reserve: u64,
+}
+
+/// Settings for the `getProgramAccounts` scans of `discover_pools_with_config`
+#[derive(Debug, Clone, Copy)]
+pub struct PoolDiscoveryConfig {
+    /// Commitment of the scans, `None` for the client's commitment
+    pub commitment: Option<CommitmentConfig>,
+    /// Only download the mint and vault fields of each pool instead of the whole account
+    pub slice_data: bool,
+}
+
+impl Default for PoolDiscoveryConfig {
+    fn default() -> Self {
+        Self { commitment: None, slice_data: true }
+    }
+}
+
+/// Where a venue's pool account stores its two mints and their vaults, discriminator included
+struct PoolLayout {
+    dex_type: DexType,
+    program_id: Pubkey,
+    mint_a: usize,
+    mint_b: usize,
+    vault_a: usize,
+    vault_b: usize,
+}
+
+impl PoolLayout {
+    /// Smallest contiguous range covering the mints and vaults
+    fn slice(&self) -> (usize, usize) {
+        let offsets = [self.mint_a, self.mint_b, self.vault_a, self.vault_b];
+        let start = offsets.iter().min().copied().unwrap_or(0);
+        let end = offsets.iter().max().copied().unwrap_or(0) + 32;
+        (start, end - start)
+    }
+}
+
+const POOL_LAYOUTS: [PoolLayout; 2] = [
+    PoolLayout {
+        dex_type: DexType::PumpSwap,
+        program_id: pumpswap::accounts::AMM_PROGRAM,
+        mint_a: 43,
+        mint_b: 75,
+        vault_a: 139,
+        vault_b: 171,
+    },
+    PoolLayout {
+        dex_type: DexType::RaydiumCpmm,
+        program_id: raydium_cpmm::accounts::RAYDIUM_CPMM,
+        mint_a: 168,
+        mint_b: 200,
+        vault_a: 72,
+        vault_b: 104,
+    },
+];
+
+/// Pool found by a scan, before its vaults are read
+struct Candidate {
+    dex_type: DexType,
+    address: Pubkey,
+    token_vault: Pubkey,
+    quote_vault: Pubkey,
+}
+
+/// Find the PumpSwap and Raydium CPMM pools pairing `mint` with SOL, deepest first
+pub async fn discover_pools(rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<Vec<PoolInfo>> {
+    discover_pools_with_config(rpc, mint, PoolDiscoveryConfig::default()).await
+}
+
+/// Like `discover_pools`, with control over the commitment and payload of the scans.
+///
+/// Each venue is scanned with `getProgramAccounts` for both mint orders, which is heavy; cache the
+/// result rather than calling this per trade.
+pub async fn discover_pools_with_config(
+    rpc: &SolanaRpcClient,
+    mint: &Pubkey,
+    config: PoolDiscoveryConfig,
+) -> Result<Vec<PoolInfo>> {
+    let scans = POOL_LAYOUTS.iter().flat_map(|layout| {
+        [(layout, mint, &WSOL_TOKEN_ACCOUNT), (layout, &WSOL_TOKEN_ACCOUNT, mint)]
+    });
+    let candidates: Vec<Candidate> = try_join_all(
+        scans.map(|(layout, mint_a, mint_b)| scan_pools(rpc, layout, mint_a, mint_b, config)),
+    )
+    .await?
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let vaults: Vec<Pubkey> =
+        candidates.iter().flat_map(|candidate| [candidate.token_vault, candidate.quote_vault]).collect();
+    let mut balances = Vec::with_capacity(vaults.len());
+    for chunk in vaults.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let commitment = config.commitment.unwrap_or(rpc.commitment());
+        let accounts = rpc.get_multiple_accounts_with_commitment(chunk, commitment).await?.value;
+        balances.extend(accounts.into_iter().map(|account| token_account_amount(&account?.data)));
+    }
+
+    let mut pools: Vec<PoolInfo> = candidates
+        .iter()
+        .zip(balances.chunks(2))
+        .filter_map(|(candidate, balances)| {
+            Some(PoolInfo {
+                dex_type: candidate.dex_type,
+                address: candidate.address,
+                quote_mint: WSOL_TOKEN_ACCOUNT,
+                token_reserve: balances[0]?,
+                quote_reserve: balances[1]?,
+            })
+        })
+        .collect();
+    pools.sort_by_key(|pool| std::cmp::Reverse(pool.quote_reserve));
+    Ok(pools)
+}
+
+/// Pools of `layout` holding `mint_a` and `mint_b` in those positions
+async fn scan_pools(
+    rpc: &SolanaRpcClient,
+    layout: &PoolLayout,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    config: PoolDiscoveryConfig,
+) -> Result<Vec<Candidate>> {
+    let (slice_offset, slice_length) = layout.slice();
+    let data_slice = config
+        .slice_data
+        .then_some(UiDataSliceConfig { offset: slice_offset, length: slice_length });
+    let program_config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(layout.mint_a, &mint_a.to_bytes())),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(layout.mint_b, &mint_b.to_bytes())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice,
+            commitment: config.commitment,
+            min_context_slot: None,
+        },
+        with_context: None,
+        sort_results: None,
+    };
+    #[allow(deprecated)]
+    let accounts = rpc.get_program_accounts_with_config(&layout.program_id, program_config).await?;
+
+    let base = if config.slice_data { slice_offset } else { 0 };
+    let key_at = |data: &[u8], offset: usize| {
+        data.get(offset - base..offset - base + 32).and_then(|bytes| Pubkey::try_from(bytes).ok())
+    };
+    let token_is_a = *mint_b == WSOL_TOKEN_ACCOUNT;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            let vault_a = key_at(&account.data, layout.vault_a)?;
+            let vault_b = key_at(&account.data, layout.vault_b)?;
+            let (token_vault, quote_vault) =
+                if token_is_a { (vault_a, vault_b) } else { (vault_b, vault_a) };
+            Some(Candidate { dex_type: layout.dex_type, address, token_vault, quote_vault })
+        })
+        .collect())
+}
+
+fn token_account_amount(data: &[u8]) -> Option<u64> {
+    data.get(TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::utils::pumpswap_types::{pool_decode, POOL_SIZE};
+    use crate::instruction::utils::raydium_cpmm_types::{pool_state_decode, POOL_STATE_SIZE};
+
+    /// Account data with the four keys written at the layout offsets
+    fn account_data(layout: &PoolLayout, len: usize, keys: &[Pubkey; 4]) -> Vec<u8> {
+        let mut data = vec![0u8; 8 + len];
+        let offsets = [layout.mint_a, layout.mint_b, layout.vault_a, layout.vault_b];
+        for (offset, key) in offsets.iter().zip(keys) {
+            data[*offset..*offset + 32].copy_from_slice(key.as_ref());
+        }
+        data
+    }
+
+    #[test]
+    fn test_pool_layouts_match_account_types() {
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let pool = pool_decode(&account_data(&POOL_LAYOUTS[0], POOL_SIZE, &keys)[8..]).unwrap();
+        assert_eq!(
+            [pool.base_mint, pool.quote_mint, pool.pool_base_token_account, pool.pool_quote_token_account],
+            keys
+        );
+
+        let data = account_data(&POOL_LAYOUTS[1], POOL_STATE_SIZE, &keys);
+        let state = pool_state_decode(&data[8..]).unwrap();
+        assert_eq!([state.token0_mint, state.token1_mint, state.token0_vault, state.token1_vault], keys);
+        assert_eq!(POOL_LAYOUTS[1].slice(), (72, 160));
+    }
+}
@@ -1,12 +1,21 @@
 use crate::{
-    common::spl_token::close_account,
-    constants::{trade::trade::DEFAULT_SLIPPAGE, TOKEN_PROGRAM_2022},
-    trading::core::{
-        params::{PumpFunParams, SwapParams},
-        traits::InstructionBuilder,
+    common::{
+        account_cache::account_cache, spl_token::close_account, subscription_handle::PubsubGuard,
+        SolanaRpcClient,
+    },
+    constants::{trade::trade::DEFAULT_SLIPPAGE, SOL_TOKEN_ACCOUNT, TOKEN_PROGRAM_2022},
+    swqos::TradeType,
+    trading::{
+        core::{
+            params::{DexParamEnum, PumpFunParams, SwapParams, TradeParams},
+            traits::{DexProtocol, InstructionBuilder},
+        },
+        factory::DexType,
     },
 };
 use crate::{
+    instruction::pool_discovery::MAX_MULTIPLE_ACCOUNTS,
+    instruction::pumpswap,
     instruction::utils::pumpfun::{
         accounts, get_bonding_curve_pda, get_creator, get_user_volume_accumulator_pda,
         global_constants::{self}, BUY_DISCRIMINATOR, BUY_EXACT_SOL_IN_DISCRIMINATOR,
@@ -17,8 +26,18 @@ use crate::{
     },
 };
 use anyhow::{anyhow, Result};
+use borsh::BorshDeserialize;
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use solana_account_decoder::UiAccountEncoding;
+use solana_commitment_config::CommitmentConfig;
+use solana_rpc_client_api::config::RpcAccountInfoConfig;
+use solana_sdk::account::Account;
 use solana_sdk::instruction::AccountMeta;
-use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signer::Signer};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
 
 /// Instruction builder for PumpFun protocol
 pub struct PumpFunInstructionBuilder;
@@ -137,11 +156,7 @@ impl InstructionBuilder for PumpFunInstructionBuilder {
         }
 
         // Determine fee recipient based on mayhem mode
-        let fee_recipient_meta = if is_mayhem_mode {
-            global_constants::MAYHEM_FEE_RECIPIENT_META
-        } else {
-            global_constants::FEE_RECIPIENT_META
-        };
+        let fee_recipient_meta = fee_recipient_meta(params.rpc.as_deref(), is_mayhem_mode).await;
 
         let accounts: [AccountMeta; 16] = [
             global_constants::GLOBAL_ACCOUNT_META,
@@ -257,11 +272,7 @@ impl InstructionBuilder for PumpFunInstructionBuilder {
         sell_data[16..24].copy_from_slice(&min_sol_output.to_le_bytes());
 
         // Determine fee recipient based on mayhem mode
-        let fee_recipient_meta = if is_mayhem_mode {
-            global_constants::MAYHEM_FEE_RECIPIENT_META
-        } else {
-            global_constants::FEE_RECIPIENT_META
-        };
+        let fee_recipient_meta = fee_recipient_meta(params.rpc.as_deref(), is_mayhem_mode).await;
 
         let accounts: [AccountMeta; 14] = [
             global_constants::GLOBAL_ACCOUNT_META,
@@ -302,3 +313,549 @@ impl InstructionBuilder for PumpFunInstructionBuilder {
         Ok(instructions)
     }
 }
+
+/// Build a Pump.fun buy spending up to `sol_amount` lamports plus `slippage_bps`.
+///
+/// Fetches the bonding curve to size the token amount from the current reserves, then emits an
+/// idempotent create of the buyer's token account followed by `buy(token_amount, max_sol_cost)`.
+pub async fn build_buy(
+    rpc: &SolanaRpcClient,
+    mint: Pubkey,
+    sol_amount: u64,
+    slippage_bps: u16,
+    buyer: &Keypair,
+) -> Result<Vec<Instruction>> {
+    let protocol_params = PumpFunParams::from_mint_by_rpc(rpc, &mint).await?;
+    // Refreshes the fee recipient the builder reads from the cache
+    refresh_global_config(rpc).await;
+    if protocol_params.bonding_curve.complete {
+        return Err(anyhow!(
+            "Bonding curve of {} is complete, the token trades on PumpSwap, use pumpswap::build_buy",
+            mint
+        ));
+    }
+    let mut params = SwapParams::for_instructions(
+        buyer,
+        TradeType::Buy,
+        SOL_TOKEN_ACCOUNT,
+        mint,
+        sol_amount,
+        slippage_bps as u64,
+        DexParamEnum::PumpFun(protocol_params),
+    );
+    params.create_output_mint_ata = true;
+    params.use_exact_sol_amount = Some(false);
+    PumpFunInstructionBuilder.build_buy_instructions(&params).await
+}
+
+/// Build a Pump.fun sell of `token_amount` receiving at least the curve quote minus `slippage_bps`.
+///
+/// With `close_account` set, the seller's token account is closed (rent refunded) when the sell
+/// empties it; partial sells keep the account open.
+///
+/// A token that migrated, see `is_migrated`, is sold on its PumpSwap pool instead, through
+/// `pumpswap::build_sell`; `close_account` is then ignored.
+pub async fn build_sell(
+    rpc: &SolanaRpcClient,
+    mint: Pubkey,
+    token_amount: u64,
+    slippage_bps: u16,
+    seller: &Keypair,
+    close_account: bool,
+) -> Result<Vec<Instruction>> {
+    let protocol_params = PumpFunParams::from_mint_by_rpc(rpc, &mint).await;
+    let migrated = match &protocol_params {
+        Ok(protocol_params) => protocol_params.bonding_curve.complete,
+        Err(_) => has_pumpswap_pool(rpc, &mint).await,
+    };
+    if migrated {
+        log::info!(" [pumpfun] {} migrated, selling on PumpSwap", mint);
+        let (instructions, _pool) =
+            pumpswap::build_sell(rpc, mint, token_amount, slippage_bps, seller).await?;
+        return Ok(instructions);
+    }
+    let protocol_params = protocol_params?;
+    // Refreshes the fee recipient the builder reads from the cache
+    refresh_global_config(rpc).await;
+
+    let sells_full_balance = if close_account {
+        let user_token_account = crate::common::fast_fn::get_associated_token_address_with_program_id_fast(
+            &seller.pubkey(),
+            &mint,
+            &protocol_params.token_program,
+        );
+        let balance = rpc.get_token_account_balance(&user_token_account).await?;
+        balance.amount.parse::<u64>().map(|balance| balance == token_amount).unwrap_or(false)
+    } else {
+        false
+    };
+
+    let mut params = SwapParams::for_instructions(
+        seller,
+        TradeType::Sell,
+        mint,
+        SOL_TOKEN_ACCOUNT,
+        token_amount,
+        slippage_bps as u64,
+        DexParamEnum::PumpFun(protocol_params),
+    );
+    params.close_input_mint_ata = sells_full_balance;
+    PumpFunInstructionBuilder.build_sell_instructions(&params).await
+}
+
+#[async_trait::async_trait]
+impl DexProtocol for PumpFunInstructionBuilder {
+    fn venue(&self) -> DexType {
+        DexType::PumpFun
+    }
+
+    async fn build_buy(
+        &self,
+        rpc: &SolanaRpcClient,
+        params: &TradeParams,
+    ) -> Result<Vec<Instruction>> {
+        build_buy(rpc, params.mint, params.amount, params.slippage_bps, &params.payer).await
+    }
+
+    async fn build_sell(
+        &self,
+        rpc: &SolanaRpcClient,
+        params: &TradeParams,
+    ) -> Result<Vec<Instruction>> {
+        build_sell(rpc, params.mint, params.amount, params.slippage_bps, &params.payer, false).await
+    }
+
+    async fn get_price(&self, rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<f64> {
+        Ok(get_bonding_curve(rpc, mint).await?.price_in_sol())
+    }
+
+    /// Virtual reserves, which the curve prices against
+    async fn get_reserves(&self, rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<(u64, u64)> {
+        let curve = get_bonding_curve(rpc, mint).await?;
+        Ok((curve.virtual_sol_reserves, curve.virtual_token_reserves))
+    }
+}
+
+/// Reserves and status of a Pump.fun bonding curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BondingCurveState {
+    /// Bonding curve account address
+    pub address: Pubkey,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    /// Set once the curve has migrated, from then on the token trades on PumpSwap
+    pub complete: bool,
+    /// Creator of the token, `Pubkey::default()` for curves that pay no creator fee
+    pub creator: Pubkey,
+}
+
+impl BondingCurveState {
+    /// Decode the raw bonding curve account stored at `address`
+    pub fn from_account_data(address: Pubkey, data: &[u8]) -> Result<Self> {
+        if data.len() <= 8 {
+            return Err(anyhow!("Bonding curve {} account data is too short", address));
+        }
+        let bonding_curve = solana_sdk::borsh1::try_from_slice_unchecked::<
+            crate::common::bonding_curve::BondingCurveAccount,
+        >(&data[8..])
+        .map_err(|e| anyhow!("Failed to deserialize bonding curve account: {}", e))?;
+        Ok(Self {
+            address,
+            virtual_sol_reserves: bonding_curve.virtual_sol_reserves,
+            virtual_token_reserves: bonding_curve.virtual_token_reserves,
+            real_sol_reserves: bonding_curve.real_sol_reserves,
+            real_token_reserves: bonding_curve.real_token_reserves,
+            complete: bonding_curve.complete,
+            creator: bonding_curve.creator,
+        })
+    }
+
+    /// Spot price of one whole token in SOL, from the virtual reserves
+    pub fn price_in_sol(&self) -> f64 {
+        crate::utils::price::pumpfun::price_token_in_sol(
+            self.virtual_sol_reserves,
+            self.virtual_token_reserves,
+        )
+    }
+}
+
+/// Raw tokens a buy spending `sol_in` lamports receives on `curve`, protocol and creator fees
+/// included, as computed on-chain for `buy_exact_sol_in`. 0 once the curve is complete.
+pub fn tokens_out(curve: &BondingCurveState, sol_in: u64) -> u64 {
+    if curve.complete || curve.virtual_token_reserves == 0 {
+        return 0;
+    }
+    let fee_basis_points = total_fee_basis_points(curve) as u128;
+    // The fee is charged on top of the SOL that enters the curve
+    let net_sol = sol_in as u128 * 10_000 / (10_000 + fee_basis_points);
+    let virtual_token_reserves = curve.virtual_token_reserves as u128;
+    let tokens = net_sol * virtual_token_reserves / (curve.virtual_sol_reserves as u128 + net_sol);
+    tokens.min(curve.real_token_reserves as u128) as u64
+}
+
+/// Lamports a sell of `tokens_in` raw tokens receives on `curve` after protocol and creator fees.
+/// 0 once the curve is complete.
+pub fn sol_out(curve: &BondingCurveState, tokens_in: u64) -> u64 {
+    if curve.complete {
+        return 0;
+    }
+    get_sell_sol_amount_from_token_amount(
+        curve.virtual_token_reserves as u128,
+        curve.virtual_sol_reserves as u128,
+        curve.creator,
+        tokens_in,
+    )
+}
+
+fn total_fee_basis_points(curve: &BondingCurveState) -> u64 {
+    let creator_fee =
+        if curve.creator != Pubkey::default() { global_constants::CREATOR_FEE } else { 0 };
+    global_constants::FEE_BASIS_POINTS + creator_fee
+}
+
+/// Fetch the bonding curve of `mint` for quoting without building a trade.
+///
+/// Errors with a clear message when the curve account does not exist, i.e. the mint was not
+/// launched on Pump.fun.
+pub async fn get_bonding_curve(rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<BondingCurveState> {
+    let address = get_bonding_curve_pda(mint)
+        .ok_or_else(|| anyhow!("Failed to derive bonding curve address for {}", mint))?;
+    let account = rpc
+        .get_account_with_commitment(&address, rpc.commitment())
+        .await?
+        .value
+        .filter(|account| account.data.len() > 8)
+        .ok_or_else(|| anyhow!("Bonding curve {} not found, {} is not a Pump.fun mint", address, mint))?;
+    BondingCurveState::from_account_data(address, &account.data)
+}
+
+/// Whether `mint` left its bonding curve and now trades on PumpSwap.
+///
+/// Reads the curve's `complete` flag; when the curve cannot be loaded, e.g. it was closed after
+/// the migration, a PumpSwap pool of the mint counts as migrated. Fails if neither exists.
+pub async fn is_migrated(rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<bool> {
+    match get_bonding_curve(rpc, mint).await {
+        Ok(curve) => Ok(curve.complete),
+        Err(e) if has_pumpswap_pool(rpc, mint).await => {
+            log::debug!(" [pumpfun] {} has a PumpSwap pool but no curve: {}", mint, e);
+            Ok(true)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn has_pumpswap_pool(rpc: &SolanaRpcClient, mint: &Pubkey) -> bool {
+    crate::instruction::utils::pumpswap::find_pool(rpc, mint).await.is_ok()
+}
+
+/// Fetch the bonding curves of many mints with batched `getMultipleAccounts` calls, 100 accounts
+/// per request.
+///
+/// The output follows the order of `mints`, with `None` for a mint whose curve is missing or
+/// cannot be decoded, e.g. a mint not launched on Pump.fun. Fails only if a request fails.
+pub async fn get_bonding_curves(
+    rpc: &SolanaRpcClient,
+    mints: &[Pubkey],
+) -> Result<Vec<Option<BondingCurveState>>> {
+    let addresses: Vec<Option<Pubkey>> = mints.iter().map(get_bonding_curve_pda).collect();
+    let mut curves = Vec::with_capacity(mints.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        // Mints without a derivable curve are left out of the request
+        let keys: Vec<Pubkey> = chunk.iter().flatten().copied().collect();
+        let commitment = rpc.commitment();
+        let accounts = rpc.get_multiple_accounts_with_commitment(&keys, commitment).await?.value;
+        curves.extend(decode_bonding_curves(chunk, accounts));
+    }
+    Ok(curves)
+}
+
+/// Pair `getMultipleAccounts` results, one per `Some` address, back up with `addresses`
+fn decode_bonding_curves(
+    addresses: &[Option<Pubkey>],
+    accounts: Vec<Option<Account>>,
+) -> Vec<Option<BondingCurveState>> {
+    let mut accounts = accounts.into_iter();
+    addresses
+        .iter()
+        .map(|address| {
+            let address = (*address)?;
+            let account = accounts.next()??;
+            BondingCurveState::from_account_data(address, &account.data).ok()
+        })
+        .collect()
+}
+
+/// Stream the bonding curve of `mint` on every change, via `accountSubscribe` at processed
+/// commitment
+///
+/// The subscription is re-established with exponential backoff when the websocket drops.
+/// Dropping the stream stops the background task. Must be called within a Tokio runtime.
+pub fn subscribe_bonding_curve(
+    ws_url: impl Into<String>,
+    mint: Pubkey,
+) -> Result<impl Stream<Item = BondingCurveState>> {
+    let ws_url = ws_url.into();
+    let address = get_bonding_curve_pda(&mint)
+        .ok_or_else(|| anyhow!("Failed to derive bonding curve address for {}", mint))?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut backoff = SUBSCRIBE_BACKOFF_INITIAL;
+        loop {
+            let result = tokio::select! {
+                result = stream_bonding_curve(&ws_url, address, &tx, &mut backoff) => result,
+                _ = tx.closed() => return,
+            };
+            if let Err(e) = result {
+                log::warn!(
+                    " [pumpfun] bonding curve {} subscription failed, resubscribing in {:?}: {}",
+                    address,
+                    backoff,
+                    e
+                );
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(SUBSCRIBE_BACKOFF_MAX);
+        }
+    });
+    Ok(futures::stream::poll_fn(move |cx| rx.poll_recv(cx)))
+}
+
+/// Last decoded global config, kept past its cache expiry for `cached_fee_recipient`
+static GLOBAL_CONFIG_CACHE: Lazy<RwLock<Option<GlobalConfig>>> = Lazy::new(|| RwLock::new(None));
+
+/// Pump.fun's global account: the fee settings and the reserves new curves start from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub struct GlobalConfig {
+    pub initialized: bool,
+    pub authority: Pubkey,
+    /// Account the trade fee is paid to
+    pub fee_recipient: Pubkey,
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    /// Protocol fee of a trade in basis points
+    pub fee_basis_points: u64,
+    pub withdraw_authority: Pubkey,
+    pub enable_migrate: bool,
+    pub pool_migration_fee: u64,
+    /// Creator fee of a trade in basis points
+    pub creator_fee_basis_points: u64,
+    /// Further accounts the program accepts as fee recipient
+    pub fee_recipients: [Pubkey; 7],
+}
+
+impl GlobalConfig {
+    /// Decode the raw global account; fields the program appended later are ignored
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() <= 8 {
+            return Err(anyhow!("Global account data is too short"));
+        }
+        solana_sdk::borsh1::try_from_slice_unchecked::<GlobalConfig>(&data[8..])
+            .map_err(|e| anyhow!("Failed to deserialize global account: {}", e))
+    }
+}
+
+/// Fetch and decode the Pump.fun global account, read through `account_cache()` so it is only
+/// fetched again once the cached account expires
+pub async fn get_global_config(rpc: &SolanaRpcClient) -> Result<GlobalConfig> {
+    let account = account_cache().get_or_fetch(rpc, &global_constants::GLOBAL_ACCOUNT).await?;
+    let config = GlobalConfig::from_account_data(&account.data)?;
+    *GLOBAL_CONFIG_CACHE.write() = Some(config);
+    Ok(config)
+}
+
+/// Fee recipient of the last fetched global config, however old, `global_constants::FEE_RECIPIENT`
+/// before the first fetch
+fn cached_fee_recipient() -> Pubkey {
+    GLOBAL_CONFIG_CACHE
+        .read()
+        .map(|config| config.fee_recipient)
+        .unwrap_or(global_constants::FEE_RECIPIENT)
+}
+
+/// Fee recipient account of a trade: the mayhem recipient for mayhem curves, otherwise the global
+/// config's, refreshed through `rpc` when given. A failed refresh keeps the last known recipient.
+async fn fee_recipient_meta(rpc: Option<&SolanaRpcClient>, is_mayhem_mode: bool) -> AccountMeta {
+    if is_mayhem_mode {
+        return global_constants::MAYHEM_FEE_RECIPIENT_META;
+    }
+    if let Some(rpc) = rpc {
+        refresh_global_config(rpc).await;
+    }
+    AccountMeta::new(cached_fee_recipient(), false)
+}
+
+/// `get_global_config`, logging instead of failing so a trade can go on with the cached config
+async fn refresh_global_config(rpc: &SolanaRpcClient) {
+    if let Err(e) = get_global_config(rpc).await {
+        log::warn!(" [pumpfun] failed to refresh the global config, keeping the cached one: {}", e);
+    }
+}
+
+const SUBSCRIBE_BACKOFF_INITIAL: Duration = Duration::from_millis(500);
+const SUBSCRIBE_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// One websocket connection's lifetime, errors when the subscription ends
+async fn stream_bonding_curve(
+    ws_url: &str,
+    address: Pubkey,
+    tx: &UnboundedSender<BondingCurveState>,
+    backoff: &mut Duration,
+) -> Result<()> {
+    let pubsub = PubsubGuard::connect(ws_url).await?;
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(CommitmentConfig::processed()),
+        ..Default::default()
+    };
+    let (mut updates, unsubscribe) =
+        pubsub.client().account_subscribe(&address, Some(config)).await?;
+    pubsub.track(unsubscribe);
+    while let Some(update) = updates.next().await {
+        *backoff = SUBSCRIBE_BACKOFF_INITIAL;
+        let Some(data) = update.value.data.decode() else {
+            continue;
+        };
+        match BondingCurveState::from_account_data(address, &data) {
+            Ok(state) => {
+                if tx.send(state).is_err() {
+                    break;
+                }
+            }
+            Err(e) => log::warn!(" [pumpfun] skipping bonding curve {} update: {}", address, e),
+        }
+    }
+    drop(updates);
+    pubsub.close().await;
+    Err(anyhow!("account subscription closed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_config_from_account_data() {
+        let fee_recipient = Pubkey::new_unique();
+        let mut data = vec![0u8; 8];
+        data.push(1);
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(fee_recipient.as_ref());
+        let reserves_and_fee =
+            [1_073_000_000_000_000u64, 30_000_000_000, 793_100_000_000_000, 1_000_000_000_000_000, 95];
+        for value in reserves_and_fee {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.push(1);
+        data.extend_from_slice(&15_000_001u64.to_le_bytes());
+        data.extend_from_slice(&30u64.to_le_bytes());
+        data.extend_from_slice(&[0u8; 32 * 7]);
+        // Fields appended by later program versions
+        data.extend_from_slice(&[0u8; 64]);
+
+        let config = GlobalConfig::from_account_data(&data).unwrap();
+        assert_eq!(config.fee_recipient, fee_recipient);
+        assert_eq!(config.fee_basis_points, 95);
+        assert_eq!(config.initial_virtual_sol_reserves, 30_000_000_000);
+        assert_eq!(config.creator_fee_basis_points, 30);
+        assert!(GlobalConfig::from_account_data(&data[..100]).is_err());
+    }
+
+    #[test]
+    fn test_bonding_curve_price_in_sol() {
+        let state = BondingCurveState {
+            address: Pubkey::default(),
+            virtual_sol_reserves: 30_000_000_000,
+            virtual_token_reserves: 1_073_000_000_000_000,
+            real_sol_reserves: 0,
+            real_token_reserves: 793_100_000_000_000,
+            complete: false,
+            creator: Pubkey::default(),
+        };
+        let price = state.price_in_sol();
+        assert!((price - 30.0 / 1_073_000_000.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_decode_bonding_curves_keeps_order() {
+        let mut data = vec![0u8; 8];
+        for value in [1_073_000_000_000_000u64, 30_000_000_000, 793_100_000_000_000, 0, 1 << 50] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(0);
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.push(0);
+        let account = |data: Vec<u8>| Some(Account { data, ..Account::default() });
+
+        let addresses = [Some(Pubkey::new_unique()), None, Some(Pubkey::new_unique())];
+        let accounts = vec![account(vec![0; 4]), account(data)];
+        let curves = decode_bonding_curves(&addresses, accounts);
+        assert_eq!(curves.len(), 3);
+        assert!(curves[0].is_none() && curves[1].is_none());
+        let curve = curves[2].unwrap();
+        assert_eq!(curve.address, addresses[2].unwrap());
+        assert_eq!(curve.virtual_sol_reserves, 30_000_000_000);
+    }
+
+    #[test]
+    fn test_tokens_out_and_sol_out_match_curve() {
+        let curve = BondingCurveState {
+            address: Pubkey::default(),
+            virtual_sol_reserves: 30_000_000_000,
+            virtual_token_reserves: 1_073_000_000_000_000,
+            real_sol_reserves: 0,
+            real_token_reserves: 793_100_000_000_000,
+            complete: false,
+            creator: Pubkey::new_unique(),
+        };
+        let tokens = tokens_out(&curve, 1_000_000_000);
+        assert_eq!(tokens, 34_199_203_154_141);
+        // What the program charges for those tokens, fee rounded up, fits in the SOL spent
+        let (vsr, vtr) = (curve.virtual_sol_reserves as u128, curve.virtual_token_reserves as u128);
+        let cost = tokens as u128 * vsr / (vtr - tokens as u128) + 1;
+        assert!(cost + (cost * 125).div_ceil(10_000) <= 1_000_000_000);
+
+        assert_eq!(sol_out(&curve, tokens), 915_057_913);
+        assert_eq!(tokens_out(&BondingCurveState { complete: true, ..curve }, 1_000_000_000), 0);
+    }
+
+    #[tokio::test]
+    async fn test_is_migrated_reads_complete_flag() {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let mut data = vec![0u8; 8];
+        for reserve in [1_000u64, 2_000, 0, 0, 1_000_000] {
+            data.extend_from_slice(&reserve.to_le_bytes());
+        }
+        data.push(1);
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.push(0);
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "context": { "slot": 1 },
+                "value": {
+                    "data": [STANDARD.encode(&data), "base64"],
+                    "executable": false,
+                    "lamports": 1_000_000,
+                    "owner": accounts::PUMPFUN.to_string(),
+                    "rentEpoch": 0,
+                    "space": data.len(),
+                },
+            },
+        })
+        .to_string();
+
+        // The curve lookup goes through `SolanaRpcClient` directly, so it needs a real endpoint
+        let (url, server) = crate::common::rpc_provider::serve_json_rpc_once(body).await;
+        let rpc = SolanaRpcClient::new(url);
+
+        assert!(is_migrated(&rpc, &Pubkey::new_unique()).await.unwrap());
+        assert!(server.await.unwrap().contains("getAccountInfo"));
+    }
+}
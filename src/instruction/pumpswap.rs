@@ -1,15 +1,18 @@
 use crate::{
-    constants::trade::trade::DEFAULT_SLIPPAGE,
+    common::SolanaRpcClient,
+    constants::{trade::trade::DEFAULT_SLIPPAGE, SOL_TOKEN_ACCOUNT},
     instruction::utils::pumpswap::{
         accounts, fee_recipient_ata, get_user_volume_accumulator_pda, BUY_DISCRIMINATOR,
         BUY_EXACT_QUOTE_IN_DISCRIMINATOR, SELL_DISCRIMINATOR,
     },
+    swqos::TradeType,
     trading::{
         common::wsol_manager,
         core::{
-            params::{PumpSwapParams, SwapParams},
-            traits::InstructionBuilder,
+            params::{DexParamEnum, PumpSwapParams, SwapParams, TradeParams},
+            traits::{DexProtocol, InstructionBuilder},
         },
+        factory::DexType,
     },
     utils::calc::pumpswap::{buy_quote_input_internal, sell_base_input_internal},
 };
@@ -17,6 +20,7 @@ use anyhow::{anyhow, Result};
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
+    signature::Keypair,
     signer::Signer,
 };
 use tracing::error;
@@ -422,3 +426,119 @@ impl InstructionBuilder for PumpSwapInstructionBuilder {
         Ok(instructions)
     }
 }
+
+/// Build a PumpSwap buy spending `sol_amount` lamports on `mint`, for tokens that migrated off the
+/// Pump.fun bonding curve (`complete` set).
+///
+/// Finds the mint's pool and its vaults, wraps the SOL into a temporary WSOL account and creates the
+/// buyer's token account if needed. Returns the instructions together with the pool address used.
+pub async fn build_buy(
+    rpc: &SolanaRpcClient,
+    mint: Pubkey,
+    sol_amount: u64,
+    slippage_bps: u16,
+    buyer: &Keypair,
+) -> Result<(Vec<Instruction>, Pubkey)> {
+    let protocol_params = PumpSwapParams::from_mint_by_rpc(rpc, &mint).await?;
+    let pool = protocol_params.pool;
+    let mut params = SwapParams::for_instructions(
+        buyer,
+        TradeType::Buy,
+        SOL_TOKEN_ACCOUNT,
+        mint,
+        sol_amount,
+        slippage_bps as u64,
+        DexParamEnum::PumpSwap(protocol_params),
+    );
+    params.create_input_mint_ata = true;
+    params.close_input_mint_ata = true;
+    params.create_output_mint_ata = true;
+    let instructions = PumpSwapInstructionBuilder.build_buy_instructions(&params).await?;
+    Ok((instructions, pool))
+}
+
+/// Build a PumpSwap sell of `token_amount` of `mint` receiving at least the pool quote minus
+/// `slippage_bps`, unwrapped back to SOL.
+///
+/// Returns the instructions together with the pool address used.
+pub async fn build_sell(
+    rpc: &SolanaRpcClient,
+    mint: Pubkey,
+    token_amount: u64,
+    slippage_bps: u16,
+    seller: &Keypair,
+) -> Result<(Vec<Instruction>, Pubkey)> {
+    let protocol_params = PumpSwapParams::from_mint_by_rpc(rpc, &mint).await?;
+    let pool = protocol_params.pool;
+    let mut params = SwapParams::for_instructions(
+        seller,
+        TradeType::Sell,
+        mint,
+        SOL_TOKEN_ACCOUNT,
+        token_amount,
+        slippage_bps as u64,
+        DexParamEnum::PumpSwap(protocol_params),
+    );
+    params.create_output_mint_ata = true;
+    params.close_output_mint_ata = true;
+    let instructions = PumpSwapInstructionBuilder.build_sell_instructions(&params).await?;
+    Ok((instructions, pool))
+}
+
+#[async_trait::async_trait]
+impl DexProtocol for PumpSwapInstructionBuilder {
+    fn venue(&self) -> DexType {
+        DexType::PumpSwap
+    }
+
+    async fn build_buy(
+        &self,
+        rpc: &SolanaRpcClient,
+        params: &TradeParams,
+    ) -> Result<Vec<Instruction>> {
+        let (instructions, _pool) =
+            build_buy(rpc, params.mint, params.amount, params.slippage_bps, &params.payer).await?;
+        Ok(instructions)
+    }
+
+    async fn build_sell(
+        &self,
+        rpc: &SolanaRpcClient,
+        params: &TradeParams,
+    ) -> Result<Vec<Instruction>> {
+        let (instructions, _pool) =
+            build_sell(rpc, params.mint, params.amount, params.slippage_bps, &params.payer).await?;
+        Ok(instructions)
+    }
+
+    async fn get_price(&self, rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<f64> {
+        let pool = PumpSwapParams::from_mint_by_rpc(rpc, mint).await?;
+        let base_decimals = rpc.get_token_supply(&pool.base_mint).await?.decimals;
+        let quote_decimals = rpc.get_token_supply(&pool.quote_mint).await?.decimals;
+        let price = if pool.base_mint == *mint {
+            crate::utils::price::pumpswap::price_base_in_quote(
+                pool.pool_base_token_reserves,
+                pool.pool_quote_token_reserves,
+                base_decimals,
+                quote_decimals,
+            )
+        } else {
+            crate::utils::price::pumpswap::price_quote_in_base(
+                pool.pool_base_token_reserves,
+                pool.pool_quote_token_reserves,
+                base_decimals,
+                quote_decimals,
+            )
+        };
+        Ok(price)
+    }
+
+    async fn get_reserves(&self, rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<(u64, u64)> {
+        let pool = PumpSwapParams::from_mint_by_rpc(rpc, mint).await?;
+        if pool.base_mint == *mint {
+            Ok((pool.pool_quote_token_reserves, pool.pool_base_token_reserves))
+        } else {
+            Ok((pool.pool_base_token_reserves, pool.pool_quote_token_reserves))
+        }
+    }
+}
@@ -1,13 +1,24 @@
 use crate::{
-    common::fast_fn::get_associated_token_address_with_program_id_fast_use_seed,
+    common::{
+        fast_fn::{
+            get_associated_token_address_with_program_id_fast,
+            get_associated_token_address_with_program_id_fast_use_seed,
+        },
+        token_program::detect_mint,
+        SolanaRpcClient,
+    },
     constants::trade::trade::DEFAULT_SLIPPAGE,
     instruction::utils::raydium_cpmm::{
-        accounts, get_observation_state_pda, get_pool_pda, get_vault_account,
-        SWAP_BASE_IN_DISCRIMINATOR,
+        accounts, fetch_pool_state, get_observation_state_pda, get_pool_pda,
+        get_pool_token_balances, get_vault_account, SWAP_BASE_IN_DISCRIMINATOR,
     },
-    trading::core::{
-        params::{RaydiumCpmmParams, SwapParams},
-        traits::InstructionBuilder,
+    swqos::TradeType,
+    trading::{
+        core::{
+            params::{DexParamEnum, RaydiumCpmmParams, SwapParams, TradeParams},
+            traits::{DexProtocol, InstructionBuilder},
+        },
+        factory::DexType,
     },
     utils::calc::raydium_cpmm::compute_swap_amount,
 };
@@ -306,3 +317,274 @@ impl InstructionBuilder for RaydiumCpmmInstructionBuilder {
         Ok(instructions)
     }
 }
+
+/// Current vault balances of a Raydium CPMM pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolReserves {
+    pub token0_mint: Pubkey,
+    pub token1_mint: Pubkey,
+    pub token0_reserve: u64,
+    pub token1_reserve: u64,
+}
+
+impl PoolReserves {
+    /// Minimum output for swapping `amount_in` of `input_mint` after pool fees and
+    /// `slippage_basis_points`, `None` if the mint is not in the pool. Token-2022 transfer fees
+    /// are not included, see `TransferFee::amount_after_fee`.
+    pub fn min_amount_out(
+        &self,
+        input_mint: &Pubkey,
+        amount_in: u64,
+        slippage_basis_points: u64,
+    ) -> Option<u64> {
+        let is_token0_in = if *input_mint == self.token0_mint {
+            true
+        } else if *input_mint == self.token1_mint {
+            false
+        } else {
+            return None;
+        };
+        let result = compute_swap_amount(
+            self.token0_reserve,
+            self.token1_reserve,
+            is_token0_in,
+            amount_in,
+            slippage_basis_points,
+        );
+        Some(result.min_amount_out)
+    }
+}
+
+/// Fetch the vault balances of `pool`, to size `min_amount_out` before calling `build_swap`
+pub async fn get_pool_reserves(rpc: &SolanaRpcClient, pool: &Pubkey) -> Result<PoolReserves> {
+    let pool_state = fetch_pool_state(rpc, pool).await?;
+    let (token0_reserve, token1_reserve) =
+        get_pool_token_balances(rpc, pool, &pool_state.token0_mint, &pool_state.token1_mint)
+            .await?;
+    Ok(PoolReserves {
+        token0_mint: pool_state.token0_mint,
+        token1_mint: pool_state.token1_mint,
+        token0_reserve,
+        token1_reserve,
+    })
+}
+
+/// Build a Raydium CPMM `swap_base_input` of `amount_in` of `input_mint` through `pool`.
+///
+/// Reads the pool state to resolve the output mint, vaults, token programs and observation state,
+/// and swaps between the user's associated token accounts. The accounts must already exist, with
+/// WSOL wrapped beforehand when trading from SOL.
+pub async fn build_swap(
+    rpc: &SolanaRpcClient,
+    pool: Pubkey,
+    input_mint: Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+    user: Pubkey,
+) -> Result<Instruction> {
+    if amount_in == 0 {
+        return Err(anyhow!("Amount cannot be zero"));
+    }
+    let pool_state = fetch_pool_state(rpc, &pool).await?;
+    let (input_vault, input_program, output_mint, output_vault, output_program) =
+        if input_mint == pool_state.token0_mint {
+            (
+                pool_state.token0_vault,
+                pool_state.token0_program,
+                pool_state.token1_mint,
+                pool_state.token1_vault,
+                pool_state.token1_program,
+            )
+        } else if input_mint == pool_state.token1_mint {
+            (
+                pool_state.token1_vault,
+                pool_state.token1_program,
+                pool_state.token0_mint,
+                pool_state.token0_vault,
+                pool_state.token0_program,
+            )
+        } else {
+            return Err(anyhow!("Mint {} is not traded in pool {}", input_mint, pool));
+        };
+
+    let input_token_account =
+        get_associated_token_address_with_program_id_fast(&user, &input_mint, &input_program);
+    let output_token_account =
+        get_associated_token_address_with_program_id_fast(&user, &output_mint, &output_program);
+
+    let accounts: [AccountMeta; 13] = [
+        AccountMeta::new(user, true),                                // Payer (signer)
+        accounts::AUTHORITY_META,                                    // Authority (readonly)
+        AccountMeta::new_readonly(pool_state.amm_config, false),     // Amm Config (readonly)
+        AccountMeta::new(pool, false),                               // Pool State
+        AccountMeta::new(input_token_account, false),                // Input Token Account
+        AccountMeta::new(output_token_account, false),               // Output Token Account
+        AccountMeta::new(input_vault, false),                        // Input Vault Account
+        AccountMeta::new(output_vault, false),                       // Output Vault Account
+        AccountMeta::new_readonly(input_program, false),             // Input Token Program (readonly)
+        AccountMeta::new_readonly(output_program, false),            // Output Token Program (readonly)
+        AccountMeta::new_readonly(input_mint, false),                // Input token mint (readonly)
+        AccountMeta::new_readonly(output_mint, false),               // Output token mint (readonly)
+        AccountMeta::new(pool_state.observation_key, false),         // Observation State Account
+    ];
+    let mut data = [0u8; 24];
+    data[..8].copy_from_slice(SWAP_BASE_IN_DISCRIMINATOR);
+    data[8..16].copy_from_slice(&amount_in.to_le_bytes());
+    data[16..24].copy_from_slice(&min_amount_out.to_le_bytes());
+
+    Ok(Instruction::new_with_bytes(accounts::RAYDIUM_CPMM, &data, accounts.to_vec()))
+}
+
+/// A Raydium CPMM venue bound to one SOL pool, CPMM pools cannot be derived from the mint alone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaydiumCpmmPool {
+    pub pool: Pubkey,
+}
+
+impl RaydiumCpmmPool {
+    pub fn new(pool: Pubkey) -> Self {
+        Self { pool }
+    }
+
+    async fn swap_params(
+        &self,
+        rpc: &SolanaRpcClient,
+        params: &TradeParams,
+        trade_type: TradeType,
+    ) -> Result<SwapParams> {
+        let protocol_params = RaydiumCpmmParams::from_pool_address_by_rpc(rpc, &self.pool).await?;
+        let mints = [protocol_params.base_mint, protocol_params.quote_mint];
+        if !mints.contains(&crate::constants::WSOL_TOKEN_ACCOUNT) || !mints.contains(&params.mint) {
+            return Err(anyhow!("Pool {} is not a SOL pool for {}", self.pool, params.mint));
+        }
+        let (input_mint, output_mint) = match trade_type {
+            TradeType::Buy => (crate::constants::WSOL_TOKEN_ACCOUNT, params.mint),
+            _ => (params.mint, crate::constants::WSOL_TOKEN_ACCOUNT),
+        };
+
+        // The program checks the minimum against what actually arrives, so a Token-2022 transfer
+        // fee is taken off the input of a sell and off the output of a buy
+        let transfer_fee = detect_mint(rpc, &params.mint).await?.transfer_fee;
+        let fixed_output_amount = transfer_fee.map(|fee| {
+            let amount_in = match trade_type {
+                TradeType::Buy => params.amount,
+                _ => fee.amount_after_fee(params.amount),
+            };
+            let min_amount_out = compute_swap_amount(
+                protocol_params.base_reserve,
+                protocol_params.quote_reserve,
+                input_mint == protocol_params.base_mint,
+                amount_in,
+                params.slippage_bps as u64,
+            )
+            .min_amount_out;
+            match trade_type {
+                TradeType::Buy => fee.amount_after_fee(min_amount_out),
+                _ => min_amount_out,
+            }
+        });
+
+        let mut swap_params = SwapParams::for_instructions(
+            &params.payer,
+            trade_type,
+            input_mint,
+            output_mint,
+            params.amount,
+            params.slippage_bps as u64,
+            DexParamEnum::RaydiumCpmm(protocol_params),
+        );
+        // Buys wrap SOL into a temporary WSOL input, sells unwrap the WSOL output
+        if trade_type == TradeType::Buy {
+            swap_params.create_input_mint_ata = true;
+            swap_params.close_input_mint_ata = true;
+        }
+        swap_params.create_output_mint_ata = true;
+        swap_params.close_output_mint_ata = trade_type != TradeType::Buy;
+        swap_params.fixed_output_amount = fixed_output_amount;
+        Ok(swap_params)
+    }
+}
+
+#[async_trait::async_trait]
+impl DexProtocol for RaydiumCpmmPool {
+    fn venue(&self) -> DexType {
+        DexType::RaydiumCpmm
+    }
+
+    async fn build_buy(
+        &self,
+        rpc: &SolanaRpcClient,
+        params: &TradeParams,
+    ) -> Result<Vec<Instruction>> {
+        let swap_params = self.swap_params(rpc, params, TradeType::Buy).await?;
+        RaydiumCpmmInstructionBuilder.build_buy_instructions(&swap_params).await
+    }
+
+    async fn build_sell(
+        &self,
+        rpc: &SolanaRpcClient,
+        params: &TradeParams,
+    ) -> Result<Vec<Instruction>> {
+        let swap_params = self.swap_params(rpc, params, TradeType::Sell).await?;
+        RaydiumCpmmInstructionBuilder.build_sell_instructions(&swap_params).await
+    }
+
+    async fn get_price(&self, rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<f64> {
+        let pool_state = fetch_pool_state(rpc, &self.pool).await?;
+        let (token0_reserve, token1_reserve) = get_pool_token_balances(
+            rpc,
+            &self.pool,
+            &pool_state.token0_mint,
+            &pool_state.token1_mint,
+        )
+        .await?;
+        if *mint == pool_state.token0_mint {
+            Ok(crate::utils::price::raydium_cpmm::price_base_in_quote(
+                token0_reserve,
+                token1_reserve,
+                pool_state.mint0_decimals,
+                pool_state.mint1_decimals,
+            ))
+        } else if *mint == pool_state.token1_mint {
+            Ok(crate::utils::price::raydium_cpmm::price_quote_in_base(
+                token0_reserve,
+                token1_reserve,
+                pool_state.mint0_decimals,
+                pool_state.mint1_decimals,
+            ))
+        } else {
+            Err(anyhow!("Mint {} is not traded in pool {}", mint, self.pool))
+        }
+    }
+    async fn get_reserves(&self, rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<(u64, u64)> {
+        let reserves = get_pool_reserves(rpc, &self.pool).await?;
+        if *mint == reserves.token0_mint {
+            Ok((reserves.token1_reserve, reserves.token0_reserve))
+        } else if *mint == reserves.token1_mint {
+            Ok((reserves.token0_reserve, reserves.token1_reserve))
+        } else {
+            Err(anyhow!("Mint {} is not traded in pool {}", mint, self.pool))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_reserves_min_amount_out_follows_direction() {
+        let reserves = PoolReserves {
+            token0_mint: Pubkey::new_unique(),
+            token1_mint: Pubkey::new_unique(),
+            token0_reserve: 1_000_000_000,
+            token1_reserve: 4_000_000_000,
+        };
+        let token0_in = reserves.min_amount_out(&reserves.token0_mint, 1_000_000, 100).unwrap();
+        let token1_in = reserves.min_amount_out(&reserves.token1_mint, 1_000_000, 100).unwrap();
+        // 1 token0 is worth ~4 token1, minus fees and 1% slippage
+        assert!(token0_in > 3_900_000 && token0_in < 4_000_000);
+        assert!(token1_in > 240_000 && token1_in < 250_000);
+        assert_eq!(reserves.min_amount_out(&Pubkey::new_unique(), 1_000_000, 100), None);
+    }
+}
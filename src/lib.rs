@@ -1,10 +1,13 @@
 pub mod common;
 pub mod constants;
+pub mod events;
+pub mod grpc;
 pub mod instruction;
 pub mod perf;
 pub mod swqos;
 pub mod trading;
 pub mod utils;
+use crate::common::blockhash_cache::BlockhashCache;
 use crate::common::nonce_cache::DurableNonceInfo;
 use crate::common::GasFeeStrategy;
 use crate::common::{TradeConfig, InfrastructureConfig};
@@ -130,6 +133,8 @@ pub struct TradingClient {
     /// Whether to use seed optimization for all ATA operations (default: true)
     /// Applies to all token account creations across buy and sell operations
     pub use_seed_optimize: bool,
+    /// Blockhash used when a trade's `recent_blockhash` is unset (default: None)
+    pub blockhash_cache: Option<Arc<BlockhashCache>>,
 }
 
 static INSTANCE: Mutex<Option<Arc<TradingClient>>> = Mutex::new(None);
@@ -144,6 +149,7 @@ impl Clone for TradingClient {
             infrastructure: self.infrastructure.clone(),
             middleware_manager: self.middleware_manager.clone(),
             use_seed_optimize: self.use_seed_optimize,
+            blockhash_cache: self.blockhash_cache.clone(),
         }
     }
 }
@@ -193,6 +199,9 @@ pub struct TradeBuyParams {
     /// When Some(false), uses regular buy instruction where slippage is applied to SOL/quote input.
     /// This option only applies to PumpFun and PumpSwap DEXes; it is ignored for other DEXes.
     pub use_exact_sol_amount: Option<bool>,
+    /// Referral token account credited with a share of the trading fee, only honored by
+    /// Meteora DAMM v2; the trade is rejected for the other DEXes
+    pub referral: Option<Pubkey>,
 }
 
 /// Parameters for executing sell orders across different DEX protocols
@@ -237,6 +246,9 @@ pub struct TradeSellParams {
     pub gas_fee_strategy: GasFeeStrategy,
     /// Whether to simulate the transaction instead of executing it
     pub simulate: bool,
+    /// Referral token account credited with a share of the trading fee, only honored by
+    /// Meteora DAMM v2; the trade is rejected for the other DEXes
+    pub referral: Option<Pubkey>,
 }
 
 impl TradingClient {
@@ -266,6 +278,7 @@ impl TradingClient {
             infrastructure,
             middleware_manager: None,
             use_seed_optimize,
+            blockhash_cache: None,
         }
     }
 
@@ -301,6 +314,7 @@ impl TradingClient {
             infrastructure,
             middleware_manager: None,
             use_seed_optimize,
+            blockhash_cache: None,
         }
     }
 
@@ -443,6 +457,7 @@ impl TradingClient {
             infrastructure,
             middleware_manager: None,
             use_seed_optimize: trade_config.use_seed_optimize,
+            blockhash_cache: None,
         };
 
         let mut current = INSTANCE.lock();
@@ -466,6 +481,25 @@ impl TradingClient {
         self
     }
 
+    /// Uses a shared blockhash cache for trades without an explicit `recent_blockhash`
+    ///
+    /// Saves the `getLatestBlockhash` round-trip on every trade when the cache is kept fresh,
+    /// e.g. by `BlockhashCache::start` with a 2 second refresh interval.
+    ///
+    /// # Arguments
+    /// * `blockhash_cache` - The cache to read the blockhash from
+    pub fn with_blockhash_cache(mut self, blockhash_cache: Arc<BlockhashCache>) -> Self {
+        self.blockhash_cache = Some(blockhash_cache);
+        self
+    }
+
+    /// The trade's own blockhash, otherwise the cached one
+    fn recent_blockhash(&self, recent_blockhash: Option<Hash>) -> Option<Hash> {
+        recent_blockhash.or_else(|| {
+            self.blockhash_cache.as_ref().and_then(|cache| cache.get()).map(|(blockhash, _)| blockhash)
+        })
+    }
+
     /// Gets the RPC client instance for direct Solana blockchain interactions
     ///
     /// This provides access to the underlying Solana RPC client that can be used
@@ -558,7 +592,7 @@ impl TradingClient {
             input_amount: Some(params.input_token_amount),
             slippage_basis_points: params.slippage_basis_points,
             address_lookup_table_account: params.address_lookup_table_account,
-            recent_blockhash: params.recent_blockhash,
+            recent_blockhash: self.recent_blockhash(params.recent_blockhash),
             wait_transaction_confirmed: params.wait_transaction_confirmed,
             protocol_params: protocol_params.clone(),
             open_seed_optimize: self.use_seed_optimize, // 使用全局seed优化配置
@@ -574,6 +608,7 @@ impl TradingClient {
             gas_fee_strategy: params.gas_fee_strategy,
             simulate: params.simulate,
             use_exact_sol_amount: params.use_exact_sol_amount,
+            referral: params.referral,
         };
 
         // Validate protocol params
@@ -597,6 +632,9 @@ impl TradingClient {
         if !is_valid_params {
             return Err(anyhow::anyhow!("Invalid protocol params for Trade"));
         }
+        if params.referral.is_some() && !params.dex_type.supports_referral() {
+            return Err(anyhow::anyhow!("{:?} does not support referral accounts", params.dex_type));
+        }
 
         let swap_result = executor.swap(buy_params).await;
         let result =
@@ -668,7 +706,7 @@ impl TradingClient {
             input_amount: Some(params.input_token_amount),
             slippage_basis_points: params.slippage_basis_points,
             address_lookup_table_account: params.address_lookup_table_account,
-            recent_blockhash: params.recent_blockhash,
+            recent_blockhash: self.recent_blockhash(params.recent_blockhash),
             wait_transaction_confirmed: params.wait_transaction_confirmed,
             protocol_params: protocol_params.clone(),
             with_tip: params.with_tip,
@@ -684,6 +722,7 @@ impl TradingClient {
             gas_fee_strategy: params.gas_fee_strategy,
             simulate: params.simulate,
             use_exact_sol_amount: None,
+            referral: params.referral,
         };
 
         // Validate protocol params
@@ -707,6 +746,9 @@ impl TradingClient {
         if !is_valid_params {
             return Err(anyhow::anyhow!("Invalid protocol params for Trade"));
         }
+        if params.referral.is_some() && !params.dex_type.supports_referral() {
+            return Err(anyhow::anyhow!("{:?} does not support referral accounts", params.dex_type));
+        }
 
         // Execute sell based on tip preference
         let swap_result = executor.swap(sell_params).await;
@@ -1,5 +1,5 @@
-use crate::swqos::common::{poll_transaction_confirmation, serialize_transaction_and_encode};
-use rand::seq::IndexedRandom;
+use crate::swqos::common::{choose_tip_account, probe_endpoint, SwqosError, serialize_transaction_and_encode};
+use crate::swqos::provider_options::{impl_provider_builders, ProviderOptions};
 use reqwest::{Client, header::{HeaderMap, HeaderValue, CONTENT_TYPE}};
 use serde_json::json;
 use std::{sync::Arc, time::Instant};
@@ -8,6 +8,7 @@ use std::time::Duration;
 use solana_transaction_status::UiTransactionEncoding;
 
 use anyhow::Result;
+use solana_sdk::signature::Signature;
 use solana_sdk::transaction::VersionedTransaction;
 use crate::swqos::{SwqosType, TradeType};
 use crate::swqos::SwqosClientTrait;
@@ -23,28 +24,43 @@ pub struct BlockRazorClient {
     pub auth_token: String,
     pub rpc_client: Arc<SolanaRpcClient>,
     pub http_client: Client,
+    /// Rate limit, auto-tip, confirmation and observer settings, see `ProviderOptions`
+    pub options: ProviderOptions,
     pub ping_handle: Arc<tokio::sync::Mutex<Option<JoinHandle<()>>>>,
     pub stop_ping: Arc<AtomicBool>,
 }
 
+impl_provider_builders!(BlockRazorClient);
+
 #[async_trait::async_trait]
 impl SwqosClientTrait for BlockRazorClient {
-    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<()> {
-        self.send_transaction(trade_type, transaction, wait_confirmation).await
+    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<Signature, SwqosError> {
+        self.options.observe_failures(transaction, self.send_transaction(trade_type, transaction, wait_confirmation)).await
     }
 
-    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<()> {
+    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError> {
         self.send_transactions(trade_type, transactions, wait_confirmation).await
     }
 
     fn get_tip_account(&self) -> Result<String> {
-        let tip_account = *BLOCKRAZOR_TIP_ACCOUNTS.choose(&mut rand::rng()).or_else(|| BLOCKRAZOR_TIP_ACCOUNTS.first()).unwrap();
-        Ok(tip_account.to_string())
+        choose_tip_account(BLOCKRAZOR_TIP_ACCOUNTS)
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        BLOCKRAZOR_TIP_ACCOUNTS.iter().map(|account| account.to_string()).collect()
     }
 
     fn get_swqos_type(&self) -> SwqosType {
         SwqosType::BlockRazor
     }
+
+    fn provider_options(&self) -> Option<&ProviderOptions> {
+        Some(&self.options)
+    }
+
+    async fn health_check(&self) -> Result<Duration> {
+        probe_endpoint(&self.http_client, &self.endpoint).await
+    }
 }
 
 impl BlockRazorClient {
@@ -69,6 +85,7 @@ impl BlockRazorClient {
             endpoint, 
             auth_token, 
             http_client,
+            options: ProviderOptions::default(),
             ping_handle: Arc::new(tokio::sync::Mutex::new(None)),
             stop_ping: Arc::new(AtomicBool::new(false)),
         };
@@ -153,7 +170,9 @@ impl BlockRazorClient {
         Ok(())
     }
 
-    pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<()> {
+    pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<Signature, SwqosError> {
+        let transaction = self.options.prepare(self, transaction).await?;
+        let transaction = transaction.as_ref();
         let start_time = Instant::now();
         let (content, signature) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
 
@@ -174,18 +193,24 @@ impl BlockRazorClient {
             .await?;
 
         // Parse JSON response
-        if let Ok(response_json) = serde_json::from_str::<serde_json::Value>(&response_text) {
-            if response_json.get("result").is_some() || response_json.get("signature").is_some() {
-                println!(" [blockrazor] {} submitted: {:?}", trade_type, start_time.elapsed());
-            } else if let Some(_error) = response_json.get("error") {
-                eprintln!(" [blockrazor] {} submission failed: {:?}", trade_type, _error);
-            }
+        let rejected = |message: String| SwqosError::ProviderRejected { provider: SwqosType::BlockRazor, message };
+        let response_json = serde_json::from_str::<serde_json::Value>(&response_text).map_err(|_| {
+            eprintln!(" [blockrazor] {} submission failed: {:?}", trade_type, response_text);
+            rejected(response_text.clone())
+        })?;
+        if response_json.get("result").is_some() || response_json.get("signature").is_some() {
+            println!(" [blockrazor] {} submitted: {:?}", trade_type, start_time.elapsed());
+        } else if let Some(_error) = response_json.get("error") {
+            eprintln!(" [blockrazor] {} submission failed: {:?}", trade_type, _error);
+            return Err(rejected(_error.to_string()));
         } else {
             eprintln!(" [blockrazor] {} submission failed: {:?}", trade_type, response_text);
+            return Err(rejected(response_text));
         }
 
+        self.options.on_submitted(self.get_swqos_type(), &signature, start_time.elapsed());
         let start_time: Instant = Instant::now();
-        match poll_transaction_confirmation(&self.rpc_client, signature, wait_confirmation).await {
+        match self.options.confirm(&self.rpc_client, trade_type, signature, wait_confirmation).await {
             Ok(_) => (),
             Err(e) => {
                 println!(" signature: {:?}", signature);
@@ -198,14 +223,15 @@ impl BlockRazorClient {
             println!(" [blockrazor] {} confirmed: {:?}", trade_type, start_time.elapsed());
         }
 
-        Ok(())
+        Ok(signature)
     }
 
-    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<()> {
+    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError> {
+        let mut signatures = Vec::with_capacity(transactions.len());
         for transaction in transactions {
-            self.send_transaction(trade_type, transaction, wait_confirmation).await?;
+            signatures.push(self.send_transaction(trade_type, transaction, wait_confirmation).await?);
         }
-        Ok(())
+        Ok(signatures)
     }
 }
 
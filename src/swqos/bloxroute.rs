@@ -1,5 +1,5 @@
-use crate::swqos::common::{poll_transaction_confirmation, serialize_transaction_and_encode, FormatBase64VersionedTransaction};
-use rand::seq::IndexedRandom;
+use crate::swqos::common::{choose_tip_account, probe_endpoint, SwqosError, serialize_transaction_and_encode, FormatBase64VersionedTransaction};
+use crate::swqos::provider_options::{impl_provider_builders, ProviderOptions};
 use reqwest::Client;
 use std::{sync::Arc, time::Instant};
 
@@ -7,6 +7,7 @@ use std::time::Duration;
 use solana_transaction_status::UiTransactionEncoding;
 
 use anyhow::Result;
+use solana_sdk::signature::Signature;
 use solana_sdk::transaction::VersionedTransaction;
 use crate::swqos::{SwqosType, TradeType};
 use crate::swqos::SwqosClientTrait;
@@ -20,26 +21,41 @@ pub struct BloxrouteClient {
     pub auth_token: String,
     pub rpc_client: Arc<SolanaRpcClient>,
     pub http_client: Client,
+    /// Rate limit, auto-tip, confirmation and observer settings, see `ProviderOptions`
+    pub options: ProviderOptions,
 }
 
+impl_provider_builders!(BloxrouteClient);
+
 #[async_trait::async_trait]
 impl SwqosClientTrait for BloxrouteClient {
-    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<()> {
-        self.send_transaction(trade_type, transaction, wait_confirmation).await
+    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<Signature, SwqosError> {
+        self.options.observe_failures(transaction, self.send_transaction(trade_type, transaction, wait_confirmation)).await
     }
 
-    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<()> {
+    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError> {
         self.send_transactions(trade_type, transactions, wait_confirmation).await
     }
 
     fn get_tip_account(&self) -> Result<String> {
-        let tip_account = *BLOX_TIP_ACCOUNTS.choose(&mut rand::rng()).or_else(|| BLOX_TIP_ACCOUNTS.first()).unwrap();
-        Ok(tip_account.to_string())
+        choose_tip_account(BLOX_TIP_ACCOUNTS)
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        BLOX_TIP_ACCOUNTS.iter().map(|account| account.to_string()).collect()
     }
 
     fn get_swqos_type(&self) -> SwqosType {
         SwqosType::Bloxroute
     }
+
+    fn provider_options(&self) -> Option<&ProviderOptions> {
+        Some(&self.options)
+    }
+
+    async fn health_check(&self) -> Result<Duration> {
+        probe_endpoint(&self.http_client, &self.endpoint).await
+    }
 }
 
 impl BloxrouteClient {
@@ -58,10 +74,12 @@ impl BloxrouteClient {
             .connect_timeout(Duration::from_millis(2000))  // Reduced from 5s to 2s
             .build()
             .unwrap();
-        Self { rpc_client: Arc::new(rpc_client), endpoint, auth_token, http_client }
+        Self { rpc_client: Arc::new(rpc_client), endpoint, auth_token, http_client, options: ProviderOptions::default() }
     }
 
-    pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<()> {
+    pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<Signature, SwqosError> {
+        let transaction = self.options.prepare(self, transaction).await?;
+        let transaction = transaction.as_ref();
         let start_time = Instant::now();
         let (content, signature) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
 
@@ -84,18 +102,24 @@ impl BloxrouteClient {
             .await?;
 
         // 5. Use `serde_json::from_str()` to parse JSON, reducing extra wait from `.json().await?`
-        if let Ok(response_json) = serde_json::from_str::<serde_json::Value>(&response_text) {
-            if response_json.get("result").is_some() {
-                println!(" [bloxroute] {} submitted: {:?}", trade_type, start_time.elapsed());
-            } else if let Some(_error) = response_json.get("error") {
-                eprintln!(" [bloxroute] {} submission failed: {:?}", trade_type, _error);
-            }
+        let rejected = |message: String| SwqosError::ProviderRejected { provider: SwqosType::Bloxroute, message };
+        let response_json = serde_json::from_str::<serde_json::Value>(&response_text).map_err(|_| {
+            eprintln!(" [bloxroute] {} submission failed: {:?}", trade_type, response_text);
+            rejected(response_text.clone())
+        })?;
+        if response_json.get("result").is_some() {
+            println!(" [bloxroute] {} submitted: {:?}", trade_type, start_time.elapsed());
+        } else if let Some(_error) = response_json.get("error") {
+            eprintln!(" [bloxroute] {} submission failed: {:?}", trade_type, _error);
+            return Err(rejected(_error.to_string()));
         } else {
             eprintln!(" [bloxroute] {} submission failed: {:?}", trade_type, response_text);
+            return Err(rejected(response_text));
         }
 
+        self.options.on_submitted(self.get_swqos_type(), &signature, start_time.elapsed());
         let start_time: Instant = Instant::now();
-        match poll_transaction_confirmation(&self.rpc_client, signature, wait_confirmation).await {
+        match self.options.confirm(&self.rpc_client, trade_type, signature, wait_confirmation).await {
             Ok(_) => (),
             Err(e) => {
                 println!(" signature: {:?}", signature);
@@ -108,10 +132,10 @@ impl BloxrouteClient {
             println!(" [bloxroute] {} confirmed: {:?}", trade_type, start_time.elapsed());
         }
 
-        Ok(())
+        Ok(signature)
     }
 
-    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, _wait_confirmation: bool) -> Result<()> {
+    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, _wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError> {
         let start_time = Instant::now();
 
         let body = serde_json::json!({
@@ -137,14 +161,21 @@ impl BloxrouteClient {
             .text()
             .await?;
 
-        if let Ok(response_json) = serde_json::from_str::<serde_json::Value>(&response_text) {
-            if response_json.get("result").is_some() {
-                println!(" bloxroute {} submitted: {:?}", trade_type, start_time.elapsed());
-            } else if let Some(_error) = response_json.get("error") {
-                eprintln!(" bloxroute {} submission failed: {:?}", trade_type, _error);
-            }
+        let rejected = |message: String| SwqosError::ProviderRejected { provider: SwqosType::Bloxroute, message };
+        let response_json = serde_json::from_str::<serde_json::Value>(&response_text).map_err(|_| {
+            eprintln!(" bloxroute {} submission failed: {:?}", trade_type, response_text);
+            rejected(response_text.clone())
+        })?;
+        if response_json.get("result").is_some() {
+            println!(" bloxroute {} submitted: {:?}", trade_type, start_time.elapsed());
+        } else if let Some(_error) = response_json.get("error") {
+            eprintln!(" bloxroute {} submission failed: {:?}", trade_type, _error);
+            return Err(rejected(_error.to_string()));
+        } else {
+            eprintln!(" bloxroute {} submission failed: {:?}", trade_type, response_text);
+            return Err(rejected(response_text));
         }
 
-        Ok(())
+        Ok(transactions.iter().filter_map(|tx| tx.signatures.first().copied()).collect())
     }
 }
\ No newline at end of file
@@ -0,0 +1,269 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::swqos::common::{RpcErrorKind, SwqosError};
+use crate::swqos::provider_options::ProviderOptions;
+use crate::swqos::{BundleId, BundleStatus, SwqosClient, SwqosClientTrait, SwqosType, TradeType};
+
+/// Whether `CircuitBreaker` lets submissions through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Submissions go through
+    Closed,
+    /// Submissions fail with `SwqosError::CircuitOpen` until the cooldown ends
+    Open,
+    /// The cooldown ended; the next submission probes the provider, success closes the circuit and
+    /// failure opens it for another cooldown
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    /// When the circuit opened, or when the last probe was let through
+    opened_at: Option<Instant>,
+    probing: bool,
+}
+
+/// Wraps a provider and stops submitting through it after `failure_threshold` consecutive
+/// failures, so a dead endpoint does not silently eat every trade.
+///
+/// Only errors pointing at the provider count: unreachable endpoints, transient rejections and
+/// transactions the provider accepted but never forwarded (`SwqosError::TransactionDropped`).
+/// Any other outcome, including a transaction failing on-chain, proves the provider works and
+/// resets the count.
+pub struct CircuitBreaker {
+    inner: Arc<SwqosClient>,
+    failure_threshold: u32,
+    cooldown: Duration,
+    breaker: Mutex<Breaker>,
+}
+
+impl CircuitBreaker {
+    pub fn new(inner: Arc<SwqosClient>, failure_threshold: u32, cooldown: Duration) -> Self {
+        let failure_threshold = failure_threshold.max(1);
+        Self { inner, failure_threshold, cooldown, breaker: Mutex::default() }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let breaker = self.breaker.lock();
+        match breaker.opened_at {
+            None => CircuitState::Closed,
+            Some(_) if breaker.probing => CircuitState::HalfOpen,
+            Some(opened_at) if opened_at.elapsed() >= self.cooldown => CircuitState::HalfOpen,
+            Some(_) => CircuitState::Open,
+        }
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.breaker.lock().consecutive_failures
+    }
+
+    /// Let a submission through, or fail with `SwqosError::CircuitOpen`
+    fn admit(&self) -> Result<(), SwqosError> {
+        let mut breaker = self.breaker.lock();
+        let Some(opened_at) = breaker.opened_at else {
+            return Ok(());
+        };
+        let elapsed = opened_at.elapsed();
+        if elapsed < self.cooldown {
+            let provider = self.inner.get_swqos_type();
+            return Err(SwqosError::CircuitOpen { provider, retry_in: self.cooldown - elapsed });
+        }
+        // One probe per cooldown; restarting the cooldown keeps a dropped probe from wedging the
+        // circuit half-open
+        breaker.opened_at = Some(Instant::now());
+        breaker.probing = true;
+        Ok(())
+    }
+
+    fn record<T>(&self, result: &Result<T, SwqosError>) {
+        let mut breaker = self.breaker.lock();
+        match result {
+            Err(e) if is_provider_failure(e) => {
+                breaker.consecutive_failures += 1;
+                if breaker.probing || breaker.consecutive_failures >= self.failure_threshold {
+                    log::warn!(
+                        " [circuit] {:?} open after {} consecutive failures: {}",
+                        self.inner.get_swqos_type(),
+                        breaker.consecutive_failures,
+                        e
+                    );
+                    breaker.opened_at = Some(Instant::now());
+                    breaker.probing = false;
+                }
+            }
+            _ => *breaker = Breaker::default(),
+        }
+    }
+}
+
+fn is_provider_failure(error: &SwqosError) -> bool {
+    matches!(
+        error,
+        SwqosError::RpcSend(_)
+            | SwqosError::RpcRejected { kind: RpcErrorKind::Transient, .. }
+            | SwqosError::TransactionDropped { .. }
+    )
+}
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for CircuitBreaker {
+    async fn send_transaction(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        wait_confirmation: bool,
+    ) -> Result<Signature, SwqosError> {
+        self.admit()?;
+        let result = self.inner.send_transaction(trade_type, transaction, wait_confirmation).await;
+        self.record(&result);
+        result
+    }
+
+    async fn send_transactions(
+        &self,
+        trade_type: TradeType,
+        transactions: &Vec<VersionedTransaction>,
+        wait_confirmation: bool,
+    ) -> Result<Vec<Signature>, SwqosError> {
+        self.admit()?;
+        let result =
+            self.inner.send_transactions(trade_type, transactions, wait_confirmation).await;
+        self.record(&result);
+        result
+    }
+
+    async fn send_bundle(
+        &self,
+        transactions: &[VersionedTransaction],
+    ) -> Result<BundleId, SwqosError> {
+        self.admit()?;
+        let result = self.inner.send_bundle(transactions).await;
+        self.record(&result);
+        result
+    }
+
+    async fn poll_bundle_status(
+        &self,
+        id: &BundleId,
+        timeout: Duration,
+    ) -> Result<BundleStatus, SwqosError> {
+        self.inner.poll_bundle_status(id, timeout).await
+    }
+
+    fn get_tip_account(&self) -> Result<String> {
+        self.inner.get_tip_account()
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        self.inner.get_tip_accounts()
+    }
+
+    fn get_swqos_type(&self) -> SwqosType {
+        self.inner.get_swqos_type()
+    }
+
+    fn provider_options(&self) -> Option<&ProviderOptions> {
+        self.inner.provider_options()
+    }
+
+    async fn health_check(&self) -> Result<Duration> {
+        self.inner.health_check().await
+    }
+
+    fn min_tip_lamports(&self) -> u64 {
+        self.inner.min_tip_lamports()
+    }
+
+    fn ensure_tip(&self, transaction: &VersionedTransaction) -> Result<(), SwqosError> {
+        self.inner.ensure_tip(transaction)
+    }
+
+    fn ensure_fee_ceiling(&self, transaction: &VersionedTransaction) -> Result<(), SwqosError> {
+        self.inner.ensure_fee_ceiling(transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[derive(Default)]
+    struct FlakyClient {
+        down: AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl SwqosClientTrait for FlakyClient {
+        async fn send_transaction(
+            &self,
+            _trade_type: TradeType,
+            _transaction: &VersionedTransaction,
+            _wait_confirmation: bool,
+        ) -> Result<Signature, SwqosError> {
+            if self.down.load(Ordering::Relaxed) {
+                return Err(SwqosError::RpcSend("connection refused".to_string()));
+            }
+            Ok(Signature::default())
+        }
+
+        async fn send_transactions(
+            &self,
+            _trade_type: TradeType,
+            _transactions: &Vec<VersionedTransaction>,
+            _wait_confirmation: bool,
+        ) -> Result<Vec<Signature>, SwqosError> {
+            Ok(Vec::new())
+        }
+
+        fn get_tip_account(&self) -> Result<String> {
+            Err(anyhow::anyhow!("no tip accounts"))
+        }
+
+        fn get_tip_accounts(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn get_swqos_type(&self) -> SwqosType {
+            SwqosType::Default
+        }
+
+        async fn health_check(&self) -> Result<Duration> {
+            Ok(Duration::ZERO)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_recovers() {
+        let flaky = Arc::new(FlakyClient::default());
+        let breaker = CircuitBreaker::new(flaky.clone(), 2, Duration::from_millis(50));
+        let tx = VersionedTransaction::default();
+        let send = || breaker.send_transaction(TradeType::Buy, &tx, false);
+
+        flaky.down.store(true, Ordering::Relaxed);
+        assert!(matches!(send().await, Err(SwqosError::RpcSend(_))));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(matches!(send().await, Err(SwqosError::RpcSend(_))));
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(matches!(send().await, Err(SwqosError::CircuitOpen { .. })));
+
+        // A failed probe opens the circuit again right away
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(matches!(send().await, Err(SwqosError::RpcSend(_))));
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        flaky.down.store(false, Ordering::Relaxed);
+        assert!(send().await.is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.consecutive_failures(), 0);
+    }
+}
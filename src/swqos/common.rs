@@ -1,4 +1,12 @@
-use crate::common::types::SolanaRpcClient;
+use crate::common::rpc_provider::RpcProvider;
+use crate::common::subscription_handle::PubsubGuard;
+use crate::constants::swqos::{
+    ASTRALANE_TIP_ACCOUNTS, BLOCKRAZOR_TIP_ACCOUNTS, BLOX_TIP_ACCOUNTS, FLASHBLOCK_TIP_ACCOUNTS,
+    HELIUS_SENDER_TIP_ACCOUNTS, JITO_TIP_ACCOUNTS, LIGHTSPEED_TIP_ACCOUNTS, NEXTBLOCK_TIP_ACCOUNTS,
+    NODE1_TIP_ACCOUNTS, NOZOMI_TIP_ACCOUNTS, SOYAS_TIP_ACCOUNTS, SPEEDLANDING_TIP_ACCOUNTS,
+    STELLIUM_TIP_ACCOUNTS, ZEROSLOT_TIP_ACCOUNTS,
+};
+use crate::swqos::SwqosType;
 use anyhow::Result;
 use base64::engine::general_purpose::{self, STANDARD};
 use base64::Engine;
@@ -6,15 +14,33 @@ use bincode::serialize;
 use reqwest::Client;
 use serde_json;
 use serde_json::json;
+use futures::future::join_all;
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_client::rpc_config::RpcSignatureSubscribeConfig;
+use solana_client::rpc_response::{ProcessedSignatureResult, RpcSignatureResult};
+use solana_commitment_config::{CommitmentConfig, CommitmentLevel};
 use solana_client::rpc_client::SerializableTransaction;
 use solana_client::rpc_config::RpcTransactionConfig;
-use solana_sdk::signature::Signature;
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use solana_sdk::clock::Slot;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::message::{compiled_instruction::CompiledInstruction, VersionedMessage};
+use solana_sdk::signature::{Keypair, Signature, Signer};
 use solana_sdk::transaction::VersionedTransaction;
 use solana_sdk::transaction::{Transaction, TransactionError};
 use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
+use std::collections::HashSet;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Clone)]
 pub struct TradeError {
@@ -36,10 +62,265 @@ impl From<anyhow::Error> for TradeError {
         if let Some(te) = e.downcast_ref::<TradeError>() {
             return te.clone();
         }
+        if let Some(SwqosError::TransactionFailed(te)) = e.downcast_ref::<SwqosError>() {
+            return te.clone();
+        }
         TradeError { code: 500, message: format!("{}", e), instruction: None }
     }
 }
 
+/// Default upper bound for waiting on a transaction confirmation
+pub const DEFAULT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default commitment a transaction must reach to count as confirmed
+pub const DEFAULT_CONFIRMATION_COMMITMENT: CommitmentLevel = CommitmentLevel::Confirmed;
+/// Default spacing of `getSignatureStatuses` polls: 200ms doubling up to 2s
+pub const DEFAULT_CONFIRMATION_POLL_BACKOFF: PollBackoff =
+    PollBackoff { initial: Duration::from_millis(200), max: Duration::from_secs(2) };
+
+/// What counts as confirmed once a transaction reaches the requested commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfirmationStrategy {
+    /// Return as soon as the commitment is reached
+    #[default]
+    FirstSeen,
+    /// Then wait until the cluster is `extra_slots` past the landing slot and check the signature
+    /// is still present, failing with `SwqosError::ReorgDropped` if it vanished. For high-value
+    /// transfers where a confirmed-then-dropped transaction is unacceptable.
+    ReorgSafe { extra_slots: u64 },
+}
+
+/// Exponential backoff with jitter between confirmation polls.
+///
+/// The delay starts at `initial`, doubles after every poll up to `max`, and each sleep adds up to
+/// 50% random jitter so concurrent pollers spread their load on the RPC node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Default for PollBackoff {
+    fn default() -> Self {
+        DEFAULT_CONFIRMATION_POLL_BACKOFF
+    }
+}
+
+impl PollBackoff {
+    /// Delay before poll number `attempt + 1`, without jitter
+    pub fn base_delay(&self, attempt: u32) -> Duration {
+        self.initial.saturating_mul(2u32.saturating_pow(attempt)).min(self.max)
+    }
+
+    /// Sleep for the jittered delay of `attempt` and advance it, never past `deadline`
+    async fn wait(&self, attempt: &mut u32, deadline: Instant) {
+        let jitter = rand::rng().random_range(0.0..0.5);
+        let delay = self.base_delay(*attempt).mul_f64(1.0 + jitter);
+        *attempt = attempt.saturating_add(1);
+        sleep(delay.min(deadline.saturating_duration_since(Instant::now()))).await;
+    }
+}
+
+/// Whether a failed RPC submission is worth retrying
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcErrorKind {
+    /// Node behind, blockhash not yet seen, rate limited or unavailable; the same transaction may
+    /// succeed shortly
+    Transient,
+    /// Rejected for a reason retrying cannot fix, e.g. insufficient funds
+    Permanent,
+}
+
+/// JSON-RPC code of a node that has fallen behind the cluster
+pub const RPC_NODE_UNHEALTHY: i64 = -32005;
+
+/// Classify an error returned by `sendTransaction`
+pub fn classify_client_error(e: &ClientError) -> RpcErrorKind {
+    if matches!(e.get_transaction_error(), Some(TransactionError::BlockhashNotFound)) {
+        return RpcErrorKind::Transient;
+    }
+    match e.kind() {
+        ClientErrorKind::Io(_) => RpcErrorKind::Transient,
+        ClientErrorKind::Reqwest(e) => match e.status() {
+            Some(status) if status.as_u16() == 429 || status.as_u16() == 503 => RpcErrorKind::Transient,
+            Some(_) => RpcErrorKind::Permanent,
+            // No response at all: connect failure or timeout
+            None => RpcErrorKind::Transient,
+        },
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, data, .. }) => {
+            if *code == RPC_NODE_UNHEALTHY || matches!(data, RpcResponseErrorData::NodeUnhealthy { .. }) {
+                RpcErrorKind::Transient
+            } else {
+                RpcErrorKind::Permanent
+            }
+        }
+        _ => RpcErrorKind::Permanent,
+    }
+}
+
+/// Whether `sendTransaction` refused the transaction only because the cluster already processed
+/// it, i.e. an earlier submission of the same signature landed
+pub fn is_already_processed(e: &ClientError) -> bool {
+    matches!(e.get_transaction_error(), Some(TransactionError::AlreadyProcessed))
+        || e.to_string().contains("This transaction has already been processed")
+}
+
+/// Errors returned by `SwqosClientTrait` submissions
+#[derive(Debug, Clone)]
+pub enum SwqosError {
+    /// The request to the RPC node or provider endpoint could not be completed
+    RpcSend(String),
+    /// The RPC node refused `sendTransaction` or another request, classified by
+    /// `classify_client_error`
+    RpcRejected { kind: RpcErrorKind, message: String },
+    /// The transaction or a provider payload could not be encoded or decoded; nothing points at
+    /// the provider itself
+    Serialization(String),
+    /// The transaction was seen by the cluster but not confirmed before the deadline
+    ConfirmationTimeout { signature: Signature, timeout: Duration },
+    /// The transaction was never seen by the cluster before the deadline
+    TransactionDropped { signature: Signature, timeout: Duration },
+    /// The provider answered but refused the transaction
+    ProviderRejected { provider: SwqosType, message: String },
+    /// The transaction landed but failed on-chain
+    TransactionFailed(TradeError),
+    /// The provider does not implement this operation
+    Unsupported { provider: SwqosType, operation: &'static str },
+    /// The recent blockhash of the transaction is no longer valid, re-sign it with a fresh one
+    BlockhashExpired { blockhash: Hash },
+    /// Waiting was aborted through a `CancellationToken`; the transaction may still land
+    Cancelled { signature: Signature },
+    /// The transaction does not pay the provider's tip account at least `min_lamports`
+    MissingTip { provider: SwqosType, min_lamports: u64 },
+    /// The serialized transaction exceeds `MAX_TRANSACTION_SIZE` bytes and would be dropped
+    TransactionTooLarge { size: usize },
+    /// The transaction was confirmed in `slot` but is gone after the extra slots of
+    /// `ConfirmationStrategy::ReorgSafe`, its fork was abandoned
+    ReorgDropped { signature: Signature, slot: u64 },
+    /// The payer's balance cannot cover the trade, its fees and rent; nothing was submitted
+    InsufficientFunds { needed: u64, available: u64 },
+    /// The priority fee plus tip of the transaction exceeds `set_max_fee_lamports`; nothing was
+    /// submitted
+    FeeCeilingExceeded { requested: u64, ceiling: u64 },
+    /// `CircuitBreaker` stopped submitting through the provider after repeated failures; nothing
+    /// was submitted
+    CircuitOpen { provider: SwqosType, retry_in: Duration },
+    /// `SendConfig::pre_simulate` found the transaction would fail with `err`; nothing was
+    /// submitted
+    SimulationRevert { err: TransactionError, logs: Vec<String> },
+    /// The idempotency guard of `MultiSwqosClient` already submitted this signed transaction; it
+    /// was not sent again
+    AlreadySubmitted { signature: Signature },
+    /// The bundle holds `len` transactions, outside the 1 to `max` the provider accepts; nothing
+    /// was submitted
+    InvalidBundle { len: usize, max: usize },
+}
+
+impl std::fmt::Display for SwqosError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SwqosError::RpcSend(message) => write!(f, "Transaction submission failed: {}", message),
+            SwqosError::RpcRejected { kind, message } => {
+                write!(f, "Transaction submission failed ({:?}): {}", kind, message)
+            }
+            SwqosError::Serialization(message) => write!(f, "Serialization failed: {}", message),
+            SwqosError::ConfirmationTimeout { signature, timeout } => {
+                write!(f, "Transaction {}'s confirmation timed out after {:?}", signature, timeout)
+            }
+            SwqosError::TransactionDropped { signature, timeout } => {
+                write!(f, "Transaction {} was not seen on-chain after {:?}, dropped", signature, timeout)
+            }
+            SwqosError::ProviderRejected { provider, message } => {
+                write!(f, "{:?} rejected the transaction: {}", provider, message)
+            }
+            SwqosError::TransactionFailed(e) => write!(f, "{}", e),
+            SwqosError::Unsupported { provider, operation } => {
+                write!(f, "{:?} does not support {}", provider, operation)
+            }
+            SwqosError::BlockhashExpired { blockhash } => {
+                write!(f, "Blockhash {} has expired, re-sign the transaction", blockhash)
+            }
+            SwqosError::Cancelled { signature } => write!(f, "Waiting for transaction {} was cancelled", signature),
+            SwqosError::MissingTip { provider, min_lamports } => {
+                write!(f, "Transaction has no tip of at least {} lamports to a {:?} tip account", min_lamports, provider)
+            }
+            SwqosError::TransactionTooLarge { size } => write!(
+                f,
+                "Transaction is {} bytes, over the {} byte limit; split it or use an address lookup table",
+                size, MAX_TRANSACTION_SIZE
+            ),
+            SwqosError::ReorgDropped { signature, slot } => {
+                write!(f, "Transaction {} confirmed in slot {} was dropped by a reorg", signature, slot)
+            }
+            SwqosError::InsufficientFunds { needed, available } => {
+                write!(f, "Insufficient funds: {} lamports needed, {} available", needed, available)
+            }
+            SwqosError::FeeCeilingExceeded { requested, ceiling } => {
+                write!(f, "Priority fee and tip of {} lamports exceed the {} lamport ceiling", requested, ceiling)
+            }
+            SwqosError::CircuitOpen { provider, retry_in } => {
+                write!(f, "Circuit for {:?} is open after repeated failures, retry in {:?}", provider, retry_in)
+            }
+            SwqosError::SimulationRevert { err, logs } => {
+                write!(f, "Simulation failed, transaction not submitted: {}", err)?;
+                if let Some(last) = logs.last() {
+                    write!(f, " (last log: {})", last)?;
+                }
+                Ok(())
+            }
+            SwqosError::AlreadySubmitted { signature } => {
+                write!(f, "Transaction {} was already submitted, not sending it again", signature)
+            }
+            SwqosError::InvalidBundle { len, max } => {
+                write!(f, "Bundle has {} transactions, between 1 and {} are accepted", len, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SwqosError {}
+
+impl From<TradeError> for SwqosError {
+    fn from(e: TradeError) -> Self {
+        SwqosError::TransactionFailed(e)
+    }
+}
+
+impl From<anyhow::Error> for SwqosError {
+    fn from(e: anyhow::Error) -> Self {
+        if let Some(se) = e.downcast_ref::<SwqosError>() {
+            return se.clone();
+        }
+        if let Some(te) = e.downcast_ref::<TradeError>() {
+            return SwqosError::TransactionFailed(te.clone());
+        }
+        SwqosError::RpcSend(format!("{}", e))
+    }
+}
+
+impl From<reqwest::Error> for SwqosError {
+    fn from(e: reqwest::Error) -> Self {
+        SwqosError::RpcSend(format!("{}", e))
+    }
+}
+
+impl From<serde_json::Error> for SwqosError {
+    fn from(e: serde_json::Error) -> Self {
+        SwqosError::Serialization(e.to_string())
+    }
+}
+
+impl From<bincode::Error> for SwqosError {
+    fn from(e: bincode::Error) -> Self {
+        SwqosError::Serialization(e.to_string())
+    }
+}
+
+impl From<ClientError> for SwqosError {
+    fn from(e: ClientError) -> Self {
+        SwqosError::RpcRejected { kind: classify_client_error(&e), message: e.to_string() }
+    }
+}
+
 // 使用高性能序列化
 
 pub trait FormatBase64VersionedTransaction {
@@ -53,24 +334,157 @@ impl FormatBase64VersionedTransaction for VersionedTransaction {
     }
 }
 
+/// Run a confirmation future, returning `SwqosError::Cancelled` as soon as `cancel` fires
+pub async fn with_cancellation<T>(
+    signature: Signature,
+    cancel: Option<&CancellationToken>,
+    confirmation: impl std::future::Future<Output = Result<T, SwqosError>>,
+) -> Result<T, SwqosError> {
+    let Some(cancel) = cancel else {
+        return confirmation.await;
+    };
+    tokio::select! {
+        result = confirmation => result,
+        _ = cancel.cancelled() => Err(SwqosError::Cancelled { signature }),
+    }
+}
+
+/// Wait until the confirmed tip is `extra_slots` past the slot the confirmed transaction landed in,
+/// then check the signature is still present and return the slot it is in now.
+///
+/// Fails with `SwqosError::ReorgDropped` if the signature vanished or now reports an error, and
+/// `SwqosError::ConfirmationTimeout` if the cluster does not advance far enough within `timeout`.
+/// This is the check behind `ConfirmationStrategy::ReorgSafe`.
+pub async fn verify_landed_slot(
+    rpc: &dyn RpcProvider,
+    signature: Signature,
+    extra_slots: u64,
+    timeout: Duration,
+    backoff: PollBackoff,
+) -> Result<Slot, SwqosError> {
+    let landed_slot = match rpc.get_signature_statuses(&[signature]).await?.value[0].clone() {
+        Some(status) if status.err.is_none() => status.slot,
+        // Already gone right after confirming, the landing slot is unknown
+        _ => return Err(SwqosError::ReorgDropped { signature, slot: 0 }),
+    };
+
+    let deadline = Instant::now() + timeout;
+    let mut attempt = 0u32;
+    while rpc.get_slot_with_commitment(CommitmentConfig::confirmed()).await? < landed_slot + extra_slots {
+        if Instant::now() >= deadline {
+            return Err(SwqosError::ConfirmationTimeout { signature, timeout });
+        }
+        backoff.wait(&mut attempt, deadline).await;
+    }
+
+    match rpc.get_signature_statuses(&[signature]).await?.value[0].clone() {
+        Some(status) if status.err.is_none() => Ok(status.slot),
+        _ => Err(SwqosError::ReorgDropped { signature, slot: landed_slot }),
+    }
+}
+
+/// Poll `getSignatureStatuses`, spaced by `backoff`, until the transaction is confirmed,
+/// fails on-chain (`SwqosError::TransactionFailed`), or `timeout` elapses
+/// (`SwqosError::TransactionDropped` if it was never seen, `SwqosError::ConfirmationTimeout` otherwise).
+///
+/// `commitment` is the level the transaction must reach. Higher commitment means slower
+/// confirmation: `Confirmed` takes roughly a second after landing, `Finalized` ~32 slots more.
 pub async fn poll_transaction_confirmation(
-    rpc: &SolanaRpcClient,
+    rpc: &dyn RpcProvider,
     txt_sig: Signature,
     wait_confirmation: bool,
-) -> Result<Signature> {
-    // 如果不需要等待确认，立即返回签名
+    commitment: CommitmentLevel,
+    timeout: Duration,
+    backoff: PollBackoff,
+) -> Result<Signature, SwqosError> {
+    poll_confirmation(rpc, txt_sig, wait_confirmation, commitment, timeout, backoff, None)
+        .await
+        .map(|_| txt_sig)
+}
+
+/// `poll_transaction_confirmation` returning the slot the transaction landed in, read from the
+/// same `getSignatureStatuses` answer that confirmed it
+pub async fn poll_transaction_landed_slot(
+    rpc: &dyn RpcProvider,
+    txt_sig: Signature,
+    commitment: CommitmentLevel,
+    timeout: Duration,
+    backoff: PollBackoff,
+) -> Result<Slot, SwqosError> {
+    let slot = poll_confirmation(rpc, txt_sig, true, commitment, timeout, backoff, None).await?;
+    Ok(slot.unwrap_or_default())
+}
+
+/// `poll_transaction_confirmation` that climbs the commitment ladder: `on_processed` is called with
+/// the landing slot as soon as the transaction is first seen without an error, then polling goes
+/// on until `commitment`.
+///
+/// Gives a sub-second "seen in a block" signal while still waiting for the safer level; the early
+/// signal can still be rolled back, only the returned result is final.
+pub async fn poll_transaction_confirmation_ladder(
+    rpc: &dyn RpcProvider,
+    txt_sig: Signature,
+    commitment: CommitmentLevel,
+    timeout: Duration,
+    backoff: PollBackoff,
+    on_processed: impl FnOnce(Slot) + Send,
+) -> Result<Signature, SwqosError> {
+    let mut on_processed = Some(on_processed);
+    let on_seen: Box<dyn FnMut(CommitmentLevel, Slot) + Send + '_> = Box::new(move |_, slot| {
+        if let Some(on_processed) = on_processed.take() {
+            on_processed(slot);
+        }
+    });
+    poll_confirmation(rpc, txt_sig, true, commitment, timeout, backoff, Some(on_seen))
+        .await
+        .map(|_| txt_sig)
+}
+
+/// `poll_transaction_landed_slot` that calls `on_seen` each time the transaction is observed at a
+/// higher commitment level than before, including the final one, with the slot it was seen in
+pub async fn poll_transaction_progress(
+    rpc: &dyn RpcProvider,
+    txt_sig: Signature,
+    commitment: CommitmentLevel,
+    timeout: Duration,
+    backoff: PollBackoff,
+    on_seen: impl FnMut(CommitmentLevel, Slot) + Send,
+) -> Result<Slot, SwqosError> {
+    let on_seen: Box<dyn FnMut(CommitmentLevel, Slot) + Send + '_> = Box::new(on_seen);
+    let slot = poll_confirmation(rpc, txt_sig, true, commitment, timeout, backoff, Some(on_seen))
+        .await?;
+    Ok(slot.unwrap_or_default())
+}
+
+async fn poll_confirmation(
+    rpc: &dyn RpcProvider,
+    txt_sig: Signature,
+    wait_confirmation: bool,
+    commitment: CommitmentLevel,
+    timeout: Duration,
+    backoff: PollBackoff,
+    mut on_seen: Option<Box<dyn FnMut(CommitmentLevel, Slot) + Send + '_>>,
+) -> Result<Option<Slot>, SwqosError> {
+    // 如果不需要等待确认，立即返回，落地 slot 未知
     if !wait_confirmation {
-        return Ok(txt_sig);
+        return Ok(None);
     }
 
-    let timeout: Duration = Duration::from_secs(15); // 🔧 增加到15秒，避免网络拥堵时超时
-    let interval: Duration = Duration::from_millis(1000);
     let start: Instant = Instant::now();
+    let deadline = start + timeout;
+    let mut attempt = 0u32;
     let mut poll_count = 0u32;
+    // 是否曾在链上看到过该交易，用于区分超时和被丢弃
+    let mut seen = false;
+    // 已上报给 on_seen 的最高确认级别
+    let mut reported: Option<CommitmentLevel> = None;
 
     loop {
         if start.elapsed() >= timeout {
-            return Err(anyhow::anyhow!("Transaction {}'s confirmation timed out", txt_sig));
+            if seen {
+                return Err(SwqosError::ConfirmationTimeout { signature: txt_sig, timeout });
+            }
+            return Err(SwqosError::TransactionDropped { signature: txt_sig, timeout });
         }
 
         poll_count += 1;
@@ -78,13 +492,19 @@ pub async fn poll_transaction_confirmation(
         let status = rpc.get_signature_statuses(&[txt_sig]).await?;
         match status.value[0].clone() {
             Some(status) => {
-                if status.err.is_none()
-                    && (status.confirmation_status
-                        == Some(TransactionConfirmationStatus::Confirmed)
-                        || status.confirmation_status
-                            == Some(TransactionConfirmationStatus::Finalized))
-                {
-                    return Ok(txt_sig);
+                seen = true;
+                if status.err.is_none() {
+                    let level = status_commitment(status.confirmation_status.as_ref());
+                    if let Some(on_seen) = on_seen.as_mut() {
+                        let rank = commitment_rank(level);
+                        if reported.is_none_or(|reported| rank > commitment_rank(reported)) {
+                            reported = Some(level);
+                            on_seen(level, status.slot);
+                        }
+                    }
+                }
+                if status.err.is_none() && commitment_reached(status.confirmation_status.as_ref(), commitment) {
+                    return Ok(Some(status.slot));
                 }
                 // 如果 getSignatureStatuses 返回了错误，立即获取详细信息
                 if status.err.is_some() {
@@ -93,19 +513,19 @@ pub async fn poll_transaction_confirmation(
             }
             None => {
                 // 交易还未上链，继续等待，不调用 getTransaction
-                sleep(interval).await;
+                backoff.wait(&mut attempt, deadline).await;
                 continue;
             }
         }
 
         // 优化：只在以下情况调用 getTransaction
         // 1. getSignatureStatuses 返回了错误
-        // 2. 或者已经轮询了较长时间（超过10次，即10秒）
+        // 2. 或者已经轮询了较长时间（超过10次）
         let should_get_transaction = status.value[0].as_ref().map(|s| s.err.is_some()).unwrap_or(false)
             || poll_count >= 10;
 
         if !should_get_transaction {
-            sleep(interval).await;
+            backoff.wait(&mut attempt, deadline).await;
             continue;
         }
 
@@ -115,7 +535,12 @@ pub async fn poll_transaction_confirmation(
                 RpcTransactionConfig {
                     encoding: Some(UiTransactionEncoding::JsonParsed),
                     max_supported_transaction_version: Some(0),
-                    commitment: Some(solana_commitment_config::CommitmentConfig::confirmed()),
+                    // getTransaction 不支持 processed，最低使用 confirmed
+                    commitment: Some(if commitment == CommitmentLevel::Finalized {
+                        CommitmentConfig::finalized()
+                    } else {
+                        CommitmentConfig::confirmed()
+                    }),
                 },
             )
             .await
@@ -123,18 +548,18 @@ pub async fn poll_transaction_confirmation(
             Ok(details) => details,
             Err(_) => {
                 // 交易可能还未上链，继续等待
-                sleep(interval).await;
+                backoff.wait(&mut attempt, deadline).await;
                 continue;
             }
         };
 
         let meta = tx_details.transaction.meta;
         if meta.is_none() {
-            sleep(interval).await;
+            backoff.wait(&mut attempt, deadline).await;
         } else {
             let meta = meta.unwrap();
             if meta.err.is_none() {
-                return Ok(txt_sig);
+                return Ok(Some(tx_details.slot));
             } else {
                 // 从 log_messages 中提取错误信息
                 let mut error_msg = String::new();
@@ -162,32 +587,9 @@ pub async fn poll_transaction_confirmation(
                 let tx_err: TransactionError =
                     serde_json::from_value(serde_json::to_value(&ui_err)?)?;
                 
-                // 直接使用Solana原生的InstructionError中的错误码
-                let mut code = 0u32;
-                let mut index = None;
-                match &tx_err {
-                    TransactionError::InstructionError(i, i_error) => {
-                        // 直接匹配所有InstructionError类型，Custom也是其中之一
-                        code = match i_error {
-                            solana_sdk::instruction::InstructionError::Custom(c) => *c,
-                            solana_sdk::instruction::InstructionError::GenericError => 1,
-                            solana_sdk::instruction::InstructionError::InvalidArgument => 2,
-                            solana_sdk::instruction::InstructionError::InvalidInstructionData => 3,
-                            solana_sdk::instruction::InstructionError::InvalidAccountData => 4,
-                            solana_sdk::instruction::InstructionError::AccountDataTooSmall => 5,
-                            solana_sdk::instruction::InstructionError::InsufficientFunds => 6,
-                            solana_sdk::instruction::InstructionError::IncorrectProgramId => 7,
-                            solana_sdk::instruction::InstructionError::MissingRequiredSignature => 8,
-                            solana_sdk::instruction::InstructionError::AccountAlreadyInitialized => 9,
-                            solana_sdk::instruction::InstructionError::UninitializedAccount => 10,
-                            _ => 999, // 其他未知错误
-                        };
-                        index = Some(*i);
-                    }
-                    _ => {}
-                }
-                
-                return Err(anyhow::Error::new(TradeError {
+                let (code, index) = instruction_error_code(&tx_err);
+
+                return Err(SwqosError::TransactionFailed(TradeError {
                     code: code,
                     message: format!("{} {:?}", tx_err, error_msg),
                     instruction: index,
@@ -197,6 +599,593 @@ pub async fn poll_transaction_confirmation(
     }
 }
 
+/// `getSignatureStatuses` accepts at most this many signatures per request
+pub const MAX_SIGNATURE_STATUSES_PER_REQUEST: usize = 256;
+
+/// Confirm many signatures with batched `getSignatureStatuses` polls, returning one outcome per
+/// signature in input order.
+///
+/// Each signature resolves on its own: confirmed or failed ones drop out of the next poll, and
+/// whatever is still pending when `timeout` elapses gets `ConfirmationTimeout` (seen) or
+/// `TransactionDropped` (never seen).
+pub async fn confirm_many(
+    rpc: &dyn RpcProvider,
+    signatures: &[Signature],
+    commitment: CommitmentLevel,
+    timeout: Duration,
+    backoff: PollBackoff,
+) -> Vec<Result<(), SwqosError>> {
+    let start = Instant::now();
+    let deadline = start + timeout;
+    let mut attempt = 0u32;
+    let mut outcomes: Vec<Option<Result<(), SwqosError>>> = vec![None; signatures.len()];
+    let mut seen = vec![false; signatures.len()];
+
+    loop {
+        let pending: Vec<usize> = (0..signatures.len()).filter(|i| outcomes[*i].is_none()).collect();
+        if pending.is_empty() {
+            break;
+        }
+        if start.elapsed() >= timeout {
+            for i in pending {
+                let signature = signatures[i];
+                outcomes[i] = Some(Err(if seen[i] {
+                    SwqosError::ConfirmationTimeout { signature, timeout }
+                } else {
+                    SwqosError::TransactionDropped { signature, timeout }
+                }));
+            }
+            break;
+        }
+
+        for chunk in pending.chunks(MAX_SIGNATURE_STATUSES_PER_REQUEST) {
+            let batch: Vec<Signature> = chunk.iter().map(|i| signatures[*i]).collect();
+            let statuses = match rpc.get_signature_statuses(&batch).await {
+                Ok(statuses) => statuses.value,
+                Err(e) => {
+                    // 单次RPC失败不终止，下一轮重试
+                    log::warn!("getSignatureStatuses failed: {}", e);
+                    continue;
+                }
+            };
+            for (i, status) in chunk.iter().zip(statuses) {
+                let Some(status) = status else { continue };
+                seen[*i] = true;
+                if let Some(tx_err) = status.err {
+                    let (code, index) = instruction_error_code(&tx_err);
+                    outcomes[*i] = Some(Err(SwqosError::TransactionFailed(TradeError {
+                        code,
+                        message: tx_err.to_string(),
+                        instruction: index,
+                    })));
+                } else if commitment_reached(status.confirmation_status.as_ref(), commitment) {
+                    outcomes[*i] = Some(Ok(()));
+                }
+            }
+        }
+
+        if outcomes.iter().any(|outcome| outcome.is_none()) {
+            backoff.wait(&mut attempt, deadline).await;
+        }
+    }
+
+    outcomes.into_iter().map(|outcome| outcome.unwrap_or(Ok(()))).collect()
+}
+
+/// Whether a `getSignatureStatuses` confirmation status satisfies `commitment`
+pub fn commitment_reached(status: Option<&TransactionConfirmationStatus>, commitment: CommitmentLevel) -> bool {
+    matches!(
+        (status, commitment),
+        (Some(TransactionConfirmationStatus::Finalized), _)
+            | (Some(TransactionConfirmationStatus::Confirmed), CommitmentLevel::Processed | CommitmentLevel::Confirmed)
+            | (Some(TransactionConfirmationStatus::Processed), CommitmentLevel::Processed)
+    )
+}
+
+/// Commitment level a signature status has reached; nodes that omit it have at least processed it
+fn status_commitment(status: Option<&TransactionConfirmationStatus>) -> CommitmentLevel {
+    match status {
+        Some(TransactionConfirmationStatus::Finalized) => CommitmentLevel::Finalized,
+        Some(TransactionConfirmationStatus::Confirmed) => CommitmentLevel::Confirmed,
+        Some(TransactionConfirmationStatus::Processed) | None => CommitmentLevel::Processed,
+    }
+}
+
+pub(crate) fn commitment_rank(commitment: CommitmentLevel) -> u8 {
+    match commitment {
+        CommitmentLevel::Processed => 0,
+        CommitmentLevel::Confirmed => 1,
+        CommitmentLevel::Finalized => 2,
+    }
+}
+
+/// Error code and instruction index of a failed transaction, as reported in `TradeError`
+fn instruction_error_code(tx_err: &TransactionError) -> (u32, Option<u8>) {
+    // 直接使用Solana原生的InstructionError中的错误码
+    match tx_err {
+        TransactionError::InstructionError(i, i_error) => {
+            // 直接匹配所有InstructionError类型，Custom也是其中之一
+            let code = match i_error {
+                solana_sdk::instruction::InstructionError::Custom(c) => *c,
+                solana_sdk::instruction::InstructionError::GenericError => 1,
+                solana_sdk::instruction::InstructionError::InvalidArgument => 2,
+                solana_sdk::instruction::InstructionError::InvalidInstructionData => 3,
+                solana_sdk::instruction::InstructionError::InvalidAccountData => 4,
+                solana_sdk::instruction::InstructionError::AccountDataTooSmall => 5,
+                solana_sdk::instruction::InstructionError::InsufficientFunds => 6,
+                solana_sdk::instruction::InstructionError::IncorrectProgramId => 7,
+                solana_sdk::instruction::InstructionError::MissingRequiredSignature => 8,
+                solana_sdk::instruction::InstructionError::AccountAlreadyInitialized => 9,
+                solana_sdk::instruction::InstructionError::UninitializedAccount => 10,
+                _ => 999, // 其他未知错误
+            };
+            (code, Some(*i))
+        }
+        _ => (0, None),
+    }
+}
+
+/// Wait for a `signatureSubscribe` notification at `commitment` instead of polling.
+/// Subscription/connection problems are reported as `SwqosError::RpcSend` so callers can fall back to polling.
+pub async fn subscribe_transaction_confirmation(
+    ws_url: &str,
+    signature: Signature,
+    commitment: CommitmentConfig,
+    timeout: Duration,
+) -> Result<Signature, SwqosError> {
+    let landed = subscribe_transaction_landed_slot(ws_url, signature, commitment, timeout).await;
+    landed.map(|_| signature)
+}
+
+/// `subscribe_transaction_confirmation` returning the slot of the notification, the slot the
+/// transaction landed in
+pub async fn subscribe_transaction_landed_slot(
+    ws_url: &str,
+    signature: Signature,
+    commitment: CommitmentConfig,
+    timeout: Duration,
+) -> Result<Slot, SwqosError> {
+    // Dropping this future mid-wait still unsubscribes and closes the socket, see `PubsubGuard`
+    let pubsub =
+        PubsubGuard::connect(ws_url).await.map_err(|e| SwqosError::RpcSend(e.to_string()))?;
+    let notification = {
+        let (mut stream, unsubscribe) = pubsub
+            .client()
+            .signature_subscribe(
+                &signature,
+                Some(RpcSignatureSubscribeConfig {
+                    commitment: Some(commitment),
+                    enable_received_notification: Some(false),
+                }),
+            )
+            .await
+            .map_err(|e| SwqosError::RpcSend(e.to_string()))?;
+        pubsub.track(unsubscribe);
+        tokio::time::timeout(timeout, stream.next()).await
+    };
+    pubsub.close().await;
+
+    match notification {
+        Err(_) => Err(SwqosError::ConfirmationTimeout { signature, timeout }),
+        Ok(None) => Err(SwqosError::RpcSend("signature subscription closed".to_string())),
+        Ok(Some(response)) => signature_result(response.value).map(|()| response.context.slot),
+    }
+}
+
+/// Outcome of one signature yielded by `confirm_stream`
+pub type ConfirmResult = Result<(), SwqosError>;
+
+/// One stage of a send yielded by `SolRpcClient::send_with_progress`, stamped with when it was
+/// observed. The stream ends after `Confirmed` or `Failed`.
+#[derive(Debug)]
+pub enum SendProgress {
+    /// The RPC node accepted the transaction
+    Submitted { signature: Signature, at: Instant },
+    /// Seen at a commitment level below the target, e.g. processed while waiting for confirmed
+    Seen { commitment: CommitmentLevel, slot: Slot, at: Instant },
+    /// Reached the target commitment
+    Confirmed { signature: Signature, slot: Slot, at: Instant },
+    /// Rejected, failed on-chain, dropped, timed out or cancelled
+    Failed { error: SwqosError, at: Instant },
+}
+
+impl SendProgress {
+    /// Whether the stream ends with this event
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, SendProgress::Confirmed { .. } | SendProgress::Failed { .. })
+    }
+}
+
+/// Confirm many signatures over a single websocket, yielding each one as its `signatureSubscribe`
+/// notification arrives.
+///
+/// All subscriptions share one `PubsubClient` connection and one deadline: signatures still
+/// pending after `timeout` are yielded with `ConfirmationTimeout`. Connection and subscription
+/// failures are yielded as `SwqosError::RpcSend` for the affected signatures. Dropping the stream
+/// unsubscribes and closes the connection.
+pub fn confirm_stream(
+    ws_url: &str,
+    signatures: Vec<Signature>,
+    commitment: CommitmentConfig,
+    timeout: Duration,
+) -> impl Stream<Item = (Signature, ConfirmResult)> {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let ws_url = ws_url.to_string();
+    tokio::spawn(async move {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let pubsub = match PubsubGuard::connect(&ws_url).await {
+            Ok(pubsub) => pubsub,
+            Err(e) => {
+                for signature in signatures {
+                    let _ = sender.send((signature, Err(SwqosError::RpcSend(e.to_string()))));
+                }
+                return;
+            }
+        };
+        {
+            let config = RpcSignatureSubscribeConfig {
+                commitment: Some(commitment),
+                enable_received_notification: Some(false),
+            };
+            let subscribe =
+                |signature| pubsub.client().signature_subscribe(signature, Some(config.clone()));
+            let subscriptions = join_all(signatures.iter().map(subscribe)).await;
+
+            let mut pending = HashSet::new();
+            let mut streams = Vec::new();
+            for (signature, subscription) in signatures.iter().zip(subscriptions) {
+                match subscription {
+                    Ok((stream, unsubscribe)) => {
+                        let signature = *signature;
+                        pending.insert(signature);
+                        streams.push(stream.map(move |response| (signature, response.value)));
+                        pubsub.track(unsubscribe);
+                    }
+                    Err(e) => {
+                        let _ = sender.send((*signature, Err(SwqosError::RpcSend(e.to_string()))));
+                    }
+                }
+            }
+
+            let mut notifications = futures::stream::select_all(streams);
+            while !pending.is_empty() {
+                let (signature, value) = tokio::select! {
+                    notification = notifications.next() => match notification {
+                        Some(notification) => notification,
+                        None => break,
+                    },
+                    _ = tokio::time::sleep_until(deadline) => break,
+                    _ = sender.closed() => break,
+                };
+                if pending.remove(&signature) && sender.send((signature, signature_result(value))).is_err() {
+                    break;
+                }
+            }
+            for signature in pending {
+                let _ = sender.send((signature, Err(SwqosError::ConfirmationTimeout { signature, timeout })));
+            }
+
+        }
+        pubsub.close().await;
+    });
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+}
+
+fn signature_result(value: RpcSignatureResult) -> Result<(), SwqosError> {
+    match value {
+        RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err: None }) => Ok(()),
+        RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err: Some(ui_err) }) => {
+            let tx_err: TransactionError = ui_err.into();
+            let (code, index) = instruction_error_code(&tx_err);
+            Err(SwqosError::TransactionFailed(TradeError { code, message: tx_err.to_string(), instruction: index }))
+        }
+        RpcSignatureResult::ReceivedSignature(_) => {
+            Err(SwqosError::RpcSend("unexpected received-signature notification".to_string()))
+        }
+    }
+}
+
+/// Pick a random tip account so concurrent tips do not hotspot a single account
+pub fn choose_tip_account(accounts: &[Pubkey]) -> Result<String> {
+    accounts
+        .choose(&mut rand::rng())
+        .map(|account| account.to_string())
+        .ok_or_else(|| anyhow::anyhow!("no valid tip accounts found"))
+}
+
+/// Largest serialized transaction the cluster accepts, the payload of one UDP packet
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+/// Serialized size of the transaction, `SwqosError::TransactionTooLarge` above `MAX_TRANSACTION_SIZE`
+pub fn validate_size(transaction: &VersionedTransaction) -> Result<usize, SwqosError> {
+    let size = bincode::serialized_size(transaction)? as usize;
+    if size > MAX_TRANSACTION_SIZE {
+        return Err(SwqosError::TransactionTooLarge { size });
+    }
+    Ok(size)
+}
+
+/// Ceiling checked by `ensure_fee_ceiling`, `u64::MAX` while unset
+static MAX_FEE_LAMPORTS: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Compute unit limit the runtime grants per instruction without a `SetComputeUnitLimit`
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+/// Highest compute unit limit a transaction can request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Reject, before submission, every transaction whose priority fee plus tip exceeds `ceiling`
+/// lamports, whatever strategy set them; `None` removes the ceiling. Applies process-wide.
+pub fn set_max_fee_lamports(ceiling: Option<u64>) {
+    MAX_FEE_LAMPORTS.store(ceiling.unwrap_or(u64::MAX), Ordering::Relaxed);
+}
+
+/// Ceiling set by `set_max_fee_lamports`
+pub fn max_fee_lamports() -> Option<u64> {
+    let ceiling = MAX_FEE_LAMPORTS.load(Ordering::Relaxed);
+    (ceiling != u64::MAX).then_some(ceiling)
+}
+
+/// Priority fee the transaction's compute budget instructions pay at their unit limit, in lamports
+pub fn priority_fee_lamports(transaction: &VersionedTransaction) -> u64 {
+    let account_keys = transaction.message.static_account_keys();
+    let mut units_limit = None;
+    let mut micro_lamports = 0u64;
+    let mut other_instructions = 0u64;
+    for instruction in transaction.message.instructions() {
+        let data = &instruction.data;
+        match account_keys.get(instruction.program_id_index as usize) {
+            Some(program_id) if *program_id == solana_compute_budget_interface::id() => {
+                // SetComputeUnitLimit: discriminator 2 and a u32, SetComputeUnitPrice: 3 and a u64
+                match (data.first(), data.get(1..)) {
+                    (Some(2), Some(limit)) if limit.len() == 4 => {
+                        units_limit = Some(u32::from_le_bytes(limit.try_into().unwrap()) as u64)
+                    }
+                    (Some(3), Some(price)) if price.len() == 8 => {
+                        micro_lamports = u64::from_le_bytes(price.try_into().unwrap())
+                    }
+                    _ => {}
+                }
+            }
+            _ => other_instructions += 1,
+        }
+    }
+    let units = units_limit
+        .unwrap_or(other_instructions * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        .min(MAX_COMPUTE_UNIT_LIMIT as u64);
+    (units as u128 * micro_lamports as u128).div_ceil(1_000_000) as u64
+}
+
+/// Fail with `SwqosError::FeeCeilingExceeded` if the priority fee plus the transfers to
+/// `tip_accounts` exceed the ceiling set by `set_max_fee_lamports`
+pub fn ensure_fee_ceiling(transaction: &VersionedTransaction, tip_accounts: &[Pubkey]) -> Result<(), SwqosError> {
+    let Some(ceiling) = max_fee_lamports() else {
+        return Ok(());
+    };
+    let requested = priority_fee_lamports(transaction).saturating_add(tip_lamports(transaction, tip_accounts));
+    if requested > ceiling {
+        return Err(SwqosError::FeeCeilingExceeded { requested, ceiling });
+    }
+    Ok(())
+}
+
+/// Base fee charged per transaction signature
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Tip accounts of every built-in provider, the tips `estimate_fees` recognizes
+static KNOWN_TIP_ACCOUNTS: Lazy<Vec<Pubkey>> = Lazy::new(|| {
+    [
+        JITO_TIP_ACCOUNTS,
+        NEXTBLOCK_TIP_ACCOUNTS,
+        ZEROSLOT_TIP_ACCOUNTS,
+        NOZOMI_TIP_ACCOUNTS,
+        BLOX_TIP_ACCOUNTS,
+        NODE1_TIP_ACCOUNTS,
+        FLASHBLOCK_TIP_ACCOUNTS,
+        BLOCKRAZOR_TIP_ACCOUNTS,
+        ASTRALANE_TIP_ACCOUNTS,
+        STELLIUM_TIP_ACCOUNTS,
+        LIGHTSPEED_TIP_ACCOUNTS,
+        SOYAS_TIP_ACCOUNTS,
+        SPEEDLANDING_TIP_ACCOUNTS,
+        HELIUS_SENDER_TIP_ACCOUNTS,
+    ]
+    .concat()
+});
+
+/// Tip accounts of every built-in provider
+pub fn known_tip_accounts() -> &'static [Pubkey] {
+    &KNOWN_TIP_ACCOUNTS
+}
+
+/// What a transaction pays to land, in lamports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeBreakdown {
+    /// `LAMPORTS_PER_SIGNATURE` per required signature
+    pub base_fee: u64,
+    /// Compute unit price times the unit limit, see `priority_fee_lamports`
+    pub priority_fee: u64,
+    /// System Program transfers to provider tip accounts
+    pub tip: u64,
+    pub total: u64,
+}
+
+/// Fees of `transaction`, counting transfers to any built-in provider's tip account as tips.
+///
+/// Without compute budget instructions the priority fee is 0; see `estimate_fees_with_tip_accounts`
+/// for relays whose tip accounts are not built in.
+pub fn estimate_fees(transaction: &VersionedTransaction) -> FeeBreakdown {
+    estimate_fees_with_tip_accounts(transaction, &KNOWN_TIP_ACCOUNTS)
+}
+
+/// Like `estimate_fees`, counting only transfers to `tip_accounts` as tips
+pub fn estimate_fees_with_tip_accounts(
+    transaction: &VersionedTransaction,
+    tip_accounts: &[Pubkey],
+) -> FeeBreakdown {
+    let signatures = transaction.message.header().num_required_signatures as u64;
+    let base_fee = signatures * LAMPORTS_PER_SIGNATURE;
+    let priority_fee = priority_fee_lamports(transaction);
+    let tip = tip_lamports(transaction, tip_accounts);
+    let total = base_fee.saturating_add(priority_fee).saturating_add(tip);
+    FeeBreakdown { base_fee, priority_fee, tip, total }
+}
+
+/// Total lamports the transaction transfers to `tip_accounts` through System Program transfers.
+///
+/// Only static account keys are considered, tip accounts are never loaded from lookup tables.
+pub fn tip_lamports(transaction: &VersionedTransaction, tip_accounts: &[Pubkey]) -> u64 {
+    let account_keys = transaction.message.static_account_keys();
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .filter_map(|instruction| {
+            let program_id = account_keys.get(instruction.program_id_index as usize)?;
+            let recipient = account_keys.get(*instruction.accounts.get(1)? as usize)?;
+            let data = &instruction.data;
+            // SystemInstruction::Transfer: u32 discriminator 2 followed by a little-endian u64
+            if *program_id != solana_system_interface::program::ID
+                || data.len() != 12
+                || data[..4] != [2, 0, 0, 0]
+                || !tip_accounts.contains(recipient)
+            {
+                return None;
+            }
+            Some(u64::from_le_bytes(data[4..12].try_into().ok()?))
+        })
+        .sum()
+}
+
+/// Tip a provider client inserts into transactions that lack one, see
+/// `SwqosClientTrait::ensure_or_insert_tip`
+#[derive(Clone)]
+pub struct AutoTip {
+    /// Fee payer and only signer of the transactions, it pays the tip and re-signs
+    pub payer: Arc<Keypair>,
+    pub lamports: u64,
+}
+
+/// Copy of `transaction` with a transfer of `lamports` from `payer` to `tip_account` appended,
+/// re-signed by `payer`. The copy has a new signature.
+///
+/// `payer` must be the fee payer and the only signer. The tip account and the System Program
+/// are added as static keys, so neither may be loaded from one of the message's lookup tables.
+pub fn insert_tip(
+    transaction: &VersionedTransaction,
+    payer: &Keypair,
+    tip_account: &Pubkey,
+    lamports: u64,
+) -> Result<VersionedTransaction> {
+    let mut message = transaction.message.clone();
+    if message.header().num_required_signatures != 1 {
+        return Err(anyhow::anyhow!("Only single-signer transactions can be re-signed with a tip"));
+    }
+    if message.static_account_keys().first() != Some(&payer.pubkey()) {
+        return Err(anyhow::anyhow!("{} is not the fee payer of the transaction", payer.pubkey()));
+    }
+    let tip_index = static_key_index(&mut message, tip_account, true)?;
+    let system_program = solana_system_interface::program::ID;
+    let program_id_index = static_key_index(&mut message, &system_program, false)?;
+    let transfer =
+        solana_system_interface::instruction::transfer(&payer.pubkey(), tip_account, lamports);
+    let instruction =
+        CompiledInstruction { program_id_index, accounts: vec![0, tip_index], data: transfer.data };
+    match &mut message {
+        VersionedMessage::Legacy(message) => message.instructions.push(instruction),
+        VersionedMessage::V0(message) => message.instructions.push(instruction),
+    }
+    Ok(VersionedTransaction::try_new(message, &[payer])?)
+}
+
+/// Index of `key` among the static account keys, appending it as an unsigned account that is
+/// writable or read-only; keys after the insertion point, lookup table keys included, shift by one
+fn static_key_index(message: &mut VersionedMessage, key: &Pubkey, writable: bool) -> Result<u8> {
+    if let Some(index) = message.static_account_keys().iter().position(|existing| existing == key) {
+        if writable && !message.is_maybe_writable(index, None) {
+            return Err(anyhow::anyhow!("{} is a read-only account of the transaction", key));
+        }
+        return Ok(index as u8);
+    }
+    let (header, account_keys, instructions) = match message {
+        VersionedMessage::Legacy(message) => {
+            (&mut message.header, &mut message.account_keys, &mut message.instructions)
+        }
+        VersionedMessage::V0(message) => {
+            (&mut message.header, &mut message.account_keys, &mut message.instructions)
+        }
+    };
+    let index = if writable {
+        account_keys.len() - header.num_readonly_unsigned_accounts as usize
+    } else {
+        header.num_readonly_unsigned_accounts += 1;
+        account_keys.len()
+    };
+    let index =
+        u8::try_from(index).map_err(|_| anyhow::anyhow!("Transaction has too many accounts"))?;
+    account_keys.insert(index as usize, *key);
+    let shift = |i: &mut u8| {
+        if *i >= index {
+            *i += 1;
+        }
+    };
+    for instruction in instructions.iter_mut() {
+        shift(&mut instruction.program_id_index);
+        instruction.accounts.iter_mut().for_each(shift);
+    }
+    Ok(index)
+}
+
+/// Stable key of the trade a transaction carries: SHA-256 over the message header, account keys,
+/// instructions and lookup tables, leaving out the recent blockhash and the signatures.
+///
+/// The same trade rebuilt with a fresh blockhash or re-signed keeps its fingerprint, so it can key
+/// an idempotency store where the first signature cannot.
+pub fn trade_fingerprint(transaction: &VersionedTransaction) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    let message = &transaction.message;
+    let mut hasher = Sha256::new();
+    let header = message.header();
+    hasher.update([
+        matches!(message, VersionedMessage::V0(_)) as u8,
+        header.num_required_signatures,
+        header.num_readonly_signed_accounts,
+        header.num_readonly_unsigned_accounts,
+    ]);
+    // Lengths keep the boundaries between variable-sized fields unambiguous
+    let keys = message.static_account_keys();
+    hasher.update((keys.len() as u64).to_le_bytes());
+    keys.iter().for_each(|key| hasher.update(key.as_ref()));
+    let instructions = message.instructions();
+    hasher.update((instructions.len() as u64).to_le_bytes());
+    for instruction in instructions {
+        hasher.update([instruction.program_id_index]);
+        hasher.update((instruction.accounts.len() as u64).to_le_bytes());
+        hasher.update(&instruction.accounts);
+        hasher.update((instruction.data.len() as u64).to_le_bytes());
+        hasher.update(&instruction.data);
+    }
+    let lookups = message.address_table_lookups().unwrap_or_default();
+    hasher.update((lookups.len() as u64).to_le_bytes());
+    for lookup in lookups {
+        hasher.update(lookup.account_key.as_ref());
+        hasher.update((lookup.writable_indexes.len() as u64).to_le_bytes());
+        hasher.update(&lookup.writable_indexes);
+        hasher.update((lookup.readonly_indexes.len() as u64).to_le_bytes());
+        hasher.update(&lookup.readonly_indexes);
+    }
+    hasher.finalize().into()
+}
+
+/// Time a lightweight GET against `url`; any HTTP response counts as reachable
+pub async fn probe_endpoint(client: &Client, url: &str) -> Result<Duration> {
+    let start = Instant::now();
+    client.get(url).send().await?;
+    Ok(start.elapsed())
+}
+
 pub async fn send_nb_transaction(client: Client, endpoint: &str, auth_token: &str, transaction: &Transaction) -> Result<Signature, anyhow::Error> {
     // 序列化交易
     let serialized = bincode::serialize(transaction)
@@ -276,4 +1265,402 @@ pub async fn serialize_smart_transaction_and_encode(
         _ => return Err(anyhow::anyhow!("Unsupported encoding")),
     };
     Ok((serialized, *signature))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swqos_error_survives_anyhow_round_trip() {
+        let err = SwqosError::ProviderRejected { provider: SwqosType::Jito, message: "bad tip".to_string() };
+        let any: anyhow::Error = err.into();
+        match SwqosError::from(any) {
+            SwqosError::ProviderRejected { provider, message } => {
+                assert_eq!(provider, SwqosType::Jito);
+                assert_eq!(message, "bad tip");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_trade_error_from_transaction_failed() {
+        let err = SwqosError::TransactionFailed(TradeError { code: 6004, message: "slippage".to_string(), instruction: Some(2) });
+        let trade_error = TradeError::from(anyhow::Error::from(err));
+        assert_eq!(trade_error.code, 6004);
+        assert_eq!(trade_error.instruction, Some(2));
+    }
+
+    #[test]
+    fn test_choose_tip_account() {
+        use crate::constants::swqos::JITO_TIP_ACCOUNTS;
+
+        let account = choose_tip_account(JITO_TIP_ACCOUNTS).unwrap();
+        assert!(JITO_TIP_ACCOUNTS.contains(&Pubkey::from_str(&account).unwrap()));
+        assert!(choose_tip_account(&[]).is_err());
+    }
+
+    #[test]
+    fn test_commitment_reached() {
+        use TransactionConfirmationStatus::*;
+
+        assert!(commitment_reached(Some(&Confirmed), CommitmentLevel::Confirmed));
+        assert!(commitment_reached(Some(&Finalized), CommitmentLevel::Confirmed));
+        assert!(!commitment_reached(Some(&Processed), CommitmentLevel::Confirmed));
+        assert!(!commitment_reached(Some(&Confirmed), CommitmentLevel::Finalized));
+        assert!(commitment_reached(Some(&Processed), CommitmentLevel::Processed));
+        assert!(!commitment_reached(None, CommitmentLevel::Processed));
+    }
+
+    #[tokio::test]
+    async fn test_with_cancellation_aborts_wait() {
+        let signature = Signature::new_unique();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let pending = std::future::pending::<Result<Signature, SwqosError>>();
+        match with_cancellation(signature, Some(&cancel), pending).await {
+            Err(SwqosError::Cancelled { signature: s }) => assert_eq!(s, signature),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_confirm_stream_reports_every_signature_when_unreachable() {
+        let signatures = vec![Signature::new_unique(), Signature::new_unique()];
+        let outcomes: Vec<(Signature, ConfirmResult)> = confirm_stream(
+            "ws://127.0.0.1:1",
+            signatures.clone(),
+            CommitmentConfig::confirmed(),
+            Duration::from_secs(5),
+        )
+        .collect()
+        .await;
+        assert_eq!(outcomes.iter().map(|(signature, _)| *signature).collect::<Vec<_>>(), signatures);
+        assert!(outcomes.iter().all(|(_, outcome)| matches!(outcome, Err(SwqosError::RpcSend(_)))));
+    }
+
+    #[test]
+    fn test_tip_lamports_counts_transfers_to_tip_accounts() {
+        use solana_sdk::message::{Message, VersionedMessage};
+        use solana_sdk::signer::{keypair::Keypair, Signer};
+
+        let payer = Keypair::new();
+        let tip_account = Pubkey::new_unique();
+        let instructions = vec![
+            solana_system_interface::instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 7),
+            solana_system_interface::instruction::transfer(&payer.pubkey(), &tip_account, 1_000),
+        ];
+        let message = VersionedMessage::Legacy(Message::new(&instructions, Some(&payer.pubkey())));
+        let tx = VersionedTransaction { signatures: vec![Signature::default()], message };
+
+        assert_eq!(tip_lamports(&tx, &[tip_account]), 1_000);
+        assert_eq!(tip_lamports(&tx, &[Pubkey::new_unique()]), 0);
+    }
+
+    #[test]
+    fn test_fee_ceiling_sums_priority_fee_and_tip() {
+        use solana_compute_budget_interface::ComputeBudgetInstruction;
+        use solana_sdk::message::{Message, VersionedMessage};
+        use solana_sdk::signer::{keypair::Keypair, Signer};
+
+        let payer = Keypair::new();
+        let tip_account = Pubkey::new_unique();
+        let instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(50_000),
+            solana_system_interface::instruction::transfer(&payer.pubkey(), &tip_account, 1_000),
+        ];
+        let message = VersionedMessage::Legacy(Message::new(&instructions, Some(&payer.pubkey())));
+        let tx = VersionedTransaction { signatures: vec![Signature::default()], message };
+        // 200k units at 50k micro-lamports each
+        assert_eq!(priority_fee_lamports(&tx), 10_000);
+
+        // Other tests submit transactions without compute budget or tip, a ceiling does not affect them
+        set_max_fee_lamports(Some(10_500));
+        assert!(ensure_fee_ceiling(&tx, &[Pubkey::new_unique()]).is_ok());
+        let err = ensure_fee_ceiling(&tx, &[tip_account]).unwrap_err();
+        set_max_fee_lamports(None);
+        assert!(matches!(err, SwqosError::FeeCeilingExceeded { requested: 11_000, ceiling: 10_500 }));
+        assert!(ensure_fee_ceiling(&tx, &[tip_account]).is_ok());
+    }
+
+    #[test]
+    fn test_estimate_fees_breaks_down_components() {
+        use solana_compute_budget_interface::ComputeBudgetInstruction;
+        use solana_sdk::message::Message;
+
+        let payer = Keypair::new();
+        let tip = solana_system_interface::instruction::transfer(
+            &payer.pubkey(),
+            &JITO_TIP_ACCOUNTS[0],
+            100_000,
+        );
+        let other = solana_system_interface::instruction::transfer(
+            &payer.pubkey(),
+            &Pubkey::new_unique(),
+            1_000,
+        );
+        let transaction = |instructions: &[solana_sdk::instruction::Instruction]| {
+            let message = Message::new(instructions, Some(&payer.pubkey()));
+            VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[&payer]).unwrap()
+        };
+
+        let budgeted = transaction(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(10_000),
+            tip,
+            other.clone(),
+        ]);
+        assert_eq!(
+            estimate_fees(&budgeted),
+            FeeBreakdown { base_fee: 5_000, priority_fee: 2_000, tip: 100_000, total: 107_000 }
+        );
+
+        let plain = transaction(&[other]);
+        assert_eq!(
+            estimate_fees(&plain),
+            FeeBreakdown { base_fee: 5_000, priority_fee: 0, tip: 0, total: 5_000 }
+        );
+    }
+
+    #[test]
+    fn test_classify_client_error() {
+        let error = |kind: ClientErrorKind| ClientError::from(kind);
+        let response_error = |code: i64| {
+            error(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code,
+                message: String::new(),
+                data: RpcResponseErrorData::Empty,
+            }))
+        };
+
+        assert_eq!(
+            classify_client_error(&error(ClientErrorKind::TransactionError(TransactionError::BlockhashNotFound))),
+            RpcErrorKind::Transient
+        );
+        assert_eq!(classify_client_error(&response_error(RPC_NODE_UNHEALTHY)), RpcErrorKind::Transient);
+        assert_eq!(
+            classify_client_error(&error(ClientErrorKind::TransactionError(
+                TransactionError::InsufficientFundsForFee
+            ))),
+            RpcErrorKind::Permanent
+        );
+        assert_eq!(classify_client_error(&response_error(-32602)), RpcErrorKind::Permanent);
+    }
+
+    #[test]
+    fn test_conversions_keep_error_kind() {
+        let json_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert!(matches!(SwqosError::from(json_error), SwqosError::Serialization(_)));
+
+        let io_error = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+        let client_error = ClientError::from(ClientErrorKind::Io(io_error));
+        assert!(matches!(
+            SwqosError::from(client_error),
+            SwqosError::RpcRejected { kind: RpcErrorKind::Transient, .. }
+        ));
+        let client_error = ClientError::from(ClientErrorKind::TransactionError(
+            TransactionError::InsufficientFundsForFee,
+        ));
+        assert!(matches!(
+            SwqosError::from(client_error),
+            SwqosError::RpcRejected { kind: RpcErrorKind::Permanent, .. }
+        ));
+    }
+
+    #[test]
+    fn test_validate_size_rejects_oversized_transaction() {
+        use solana_sdk::instruction::Instruction;
+        use solana_sdk::message::{Message, VersionedMessage};
+
+        let payer = Pubkey::new_unique();
+        let transaction_with = |data_len: usize| {
+            let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &vec![0; data_len], vec![]);
+            let message = VersionedMessage::Legacy(Message::new(&[instruction], Some(&payer)));
+            VersionedTransaction { signatures: vec![Signature::default()], message }
+        };
+
+        let size = validate_size(&transaction_with(100)).unwrap();
+        assert_eq!(size, bincode::serialize(&transaction_with(100)).unwrap().len());
+        match validate_size(&transaction_with(MAX_TRANSACTION_SIZE)) {
+            Err(SwqosError::TransactionTooLarge { size }) => assert!(size > MAX_TRANSACTION_SIZE),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ladder_reports_processed_before_confirmed() {
+        use crate::common::rpc_provider::MockRpc;
+        use solana_transaction_status::TransactionStatus;
+
+        let signature = Signature::new_unique();
+        let status = |confirmation_status| {
+            Some(TransactionStatus {
+                slot: 7,
+                confirmations: Some(0),
+                status: Ok(()),
+                err: None,
+                confirmation_status: Some(confirmation_status),
+            })
+        };
+        let mock = MockRpc::new();
+        mock.push_signature_status(signature, None);
+        mock.push_signature_status(signature, status(TransactionConfirmationStatus::Processed));
+        mock.push_signature_status(signature, status(TransactionConfirmationStatus::Processed));
+        mock.push_signature_status(signature, status(TransactionConfirmationStatus::Confirmed));
+
+        let backoff =
+            PollBackoff { initial: Duration::from_millis(1), max: Duration::from_millis(1) };
+        let processed_slot = std::sync::Mutex::new(None);
+        let confirmed = poll_transaction_confirmation_ladder(
+            &mock,
+            signature,
+            CommitmentLevel::Confirmed,
+            Duration::from_secs(5),
+            backoff,
+            |slot| *processed_slot.lock().unwrap() = Some(slot),
+        )
+        .await;
+        assert_eq!(confirmed.unwrap(), signature);
+        assert_eq!(*processed_slot.lock().unwrap(), Some(7));
+    }
+
+    #[test]
+    fn test_poll_backoff_doubles_up_to_cap() {
+        let backoff = PollBackoff::default();
+        assert_eq!(backoff.base_delay(0), Duration::from_millis(200));
+        assert_eq!(backoff.base_delay(1), Duration::from_millis(400));
+        assert_eq!(backoff.base_delay(3), Duration::from_millis(1600));
+        assert_eq!(backoff.base_delay(4), Duration::from_secs(2));
+        assert_eq!(backoff.base_delay(u32::MAX), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_trade_fingerprint_ignores_blockhash_and_signatures() {
+        let payer = Keypair::new();
+        let transfer = solana_system_interface::instruction::transfer(
+            &payer.pubkey(),
+            &Pubkey::new_unique(),
+            1_000,
+        );
+        let build = |payer: &Keypair, lamports: u64, blockhash: Hash| {
+            let mut instruction = transfer.clone();
+            instruction.data[4..12].copy_from_slice(&lamports.to_le_bytes());
+            let message = solana_sdk::message::Message::new_with_blockhash(
+                &[instruction],
+                Some(&payer.pubkey()),
+                &blockhash,
+            );
+            VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[payer]).unwrap()
+        };
+
+        let first = build(&payer, 1_000, Hash::new_unique());
+        let rebuilt = build(&payer, 1_000, Hash::new_unique());
+        assert_ne!(first.signatures, rebuilt.signatures);
+        assert_eq!(trade_fingerprint(&first), trade_fingerprint(&rebuilt));
+
+        assert_ne!(
+            trade_fingerprint(&first),
+            trade_fingerprint(&build(&payer, 1_001, Hash::new_unique()))
+        );
+    }
+
+    #[test]
+    fn test_insert_tip_keeps_existing_instructions() {
+        use solana_sdk::message::Message;
+
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let tip_account = Pubkey::new_unique();
+        let transfer =
+            solana_system_interface::instruction::transfer(&payer.pubkey(), &recipient, 5);
+        let message = VersionedMessage::Legacy(Message::new(&[transfer], Some(&payer.pubkey())));
+        let tx = VersionedTransaction::try_new(message, &[&payer]).unwrap();
+
+        let tipped = insert_tip(&tx, &payer, &tip_account, 10_000).unwrap();
+        assert_ne!(tipped.signatures[0], tx.signatures[0]);
+        assert!(tipped.verify_with_results().iter().all(|verified| *verified));
+        assert_eq!(tip_lamports(&tipped, &[tip_account]), 10_000);
+        // The original transfer still pays `recipient` through the System Program
+        assert_eq!(tip_lamports(&tipped, &[recipient]), 5);
+        assert_eq!(tipped.message.header().num_readonly_unsigned_accounts, 1);
+
+        assert!(insert_tip(&tx, &Keypair::new(), &tip_account, 10_000).is_err());
+    }
+
+    #[derive(Default)]
+    struct PubsubCounters {
+        open: AtomicU64,
+        subscribed: AtomicU64,
+        unsubscribed: AtomicU64,
+    }
+
+    /// `signatureSubscribe` server that never notifies, counting connections and subscriptions
+    async fn spawn_pubsub_server(counters: Arc<PubsubCounters>) -> String {
+        use futures::SinkExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let counters = counters.clone();
+                counters.open.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    if let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await {
+                        while let Some(Ok(message)) = ws.next().await {
+                            let Message::Text(text) = message else { continue };
+                            let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                            let result = match request["method"].as_str() {
+                                Some("signatureSubscribe") => {
+                                    json!(counters.subscribed.fetch_add(1, Ordering::SeqCst))
+                                }
+                                Some("signatureUnsubscribe") => {
+                                    counters.unsubscribed.fetch_add(1, Ordering::SeqCst);
+                                    json!(true)
+                                }
+                                _ => continue,
+                            };
+                            let id = &request["id"];
+                            let response = json!({"jsonrpc": "2.0", "result": result, "id": id});
+                            let response = Message::Text(response.to_string().into());
+                            if ws.send(response).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    counters.open.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_dropped_subscriptions_are_closed() {
+        let counters = Arc::new(PubsubCounters::default());
+        let url = spawn_pubsub_server(counters.clone()).await;
+
+        for _ in 0..10 {
+            // Abandon each confirmation future while it waits for a notification
+            join_all((0..100).map(|_| {
+                let wait = subscribe_transaction_landed_slot(
+                    &url,
+                    Signature::default(),
+                    CommitmentConfig::confirmed(),
+                    Duration::from_secs(60),
+                );
+                tokio::time::timeout(Duration::from_millis(200), wait)
+            }))
+            .await;
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while counters.open.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(counters.open.load(Ordering::SeqCst), 0);
+        assert_eq!(counters.subscribed.load(Ordering::SeqCst), 1000);
+        assert_eq!(counters.unsubscribed.load(Ordering::SeqCst), 1000);
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::constants::swqos::JITO_TIP_ACCOUNTS;
+use crate::swqos::common::{choose_tip_account, validate_size, SwqosError};
+use crate::swqos::solana_rpc::{print_versioned_transaction_instructions, DiagnosticLevel};
+use crate::swqos::{SwqosClientTrait, SwqosType, TradeType};
+
+/// Builds and signs through the regular trade path but never broadcasts.
+///
+/// `send_transaction` logs the decoded instructions and returns the transaction's own
+/// first signature, which is deterministic for a given signer and message.
+/// Tips are built against the Jito tip accounts so the transaction has the shape of a real tipped submission.
+/// The full instruction dump is logged unless lowered with `with_diagnostic_level`.
+#[derive(Debug, Clone)]
+pub struct DryRunClient {
+    pub diagnostic_level: DiagnosticLevel,
+}
+
+impl Default for DryRunClient {
+    fn default() -> Self {
+        Self { diagnostic_level: DiagnosticLevel::Full }
+    }
+}
+
+impl DryRunClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_diagnostic_level(mut self, diagnostic_level: DiagnosticLevel) -> Self {
+        self.diagnostic_level = diagnostic_level;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for DryRunClient {
+    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, _wait_confirmation: bool) -> Result<Signature, SwqosError> {
+        validate_size(transaction)?;
+        let signature = transaction.signatures.first().copied().unwrap_or_default();
+        log::info!(" [dry-run] {} not submitted, signature: {:?}", trade_type, signature);
+        print_versioned_transaction_instructions(transaction, self.diagnostic_level);
+        Ok(signature)
+    }
+
+    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError> {
+        let mut signatures = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            signatures.push(self.send_transaction(trade_type, transaction, wait_confirmation).await?);
+        }
+        Ok(signatures)
+    }
+
+    fn get_tip_account(&self) -> Result<String> {
+        choose_tip_account(JITO_TIP_ACCOUNTS)
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        JITO_TIP_ACCOUNTS.iter().map(|account| account.to_string()).collect()
+    }
+
+    fn get_swqos_type(&self) -> SwqosType {
+        SwqosType::DryRun
+    }
+
+    async fn health_check(&self) -> Result<Duration> {
+        Ok(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_hash::Hash;
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+
+    #[tokio::test]
+    async fn test_dry_run_returns_transaction_signature() {
+        let payer = Keypair::new();
+        let message = Message::new_with_blockhash(&[], Some(&payer.pubkey()), &Hash::default());
+        let transaction = VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[&payer]).unwrap();
+
+        let signature = DryRunClient::new().send_transaction(TradeType::Buy, &transaction, true).await.unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+    }
+}
@@ -0,0 +1,221 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use reqwest::Client;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::common::SolanaRpcClient;
+use crate::swqos::common::{
+    choose_tip_account, probe_endpoint, serialize_transaction_and_encode, SwqosError,
+};
+use crate::swqos::provider_options::{impl_provider_builders, ProviderOptions};
+use crate::swqos::{SwqosClientTrait, SwqosType, TradeType};
+
+/// Any relay speaking the standard `sendTransaction` JSON-RPC, for endpoints that are not one of
+/// the built-in providers.
+///
+/// Transactions are POSTed base64 encoded to `url` with `headers` attached, then confirmed through
+/// the regular RPC. With `tip_accounts` set, transactions must tip one of them at least `min_tip`
+/// lamports, as with the built-in tipped providers.
+#[derive(Clone)]
+pub struct GenericRelayClient {
+    pub url: String,
+    /// Extra HTTP headers of every request, e.g. an API key
+    pub headers: Vec<(String, String)>,
+    pub tip_accounts: Vec<Pubkey>,
+    /// Minimum tip in lamports, ignored without `tip_accounts`
+    pub min_tip: u64,
+    pub rpc_client: Arc<SolanaRpcClient>,
+    pub http_client: Client,
+    /// Rate limit, auto-tip, confirmation and observer settings, see `ProviderOptions`
+    pub options: ProviderOptions,
+}
+
+impl_provider_builders!(GenericRelayClient);
+
+impl GenericRelayClient {
+    pub fn new(rpc_url: String, url: String) -> Self {
+        let http_client = Client::builder()
+            .pool_idle_timeout(Duration::from_secs(120))
+            .pool_max_idle_per_host(256)
+            .tcp_keepalive(Some(Duration::from_secs(60)))
+            .tcp_nodelay(true)
+            .timeout(Duration::from_millis(3000))
+            .connect_timeout(Duration::from_millis(2000))
+            .build()
+            .unwrap();
+        Self {
+            url,
+            headers: Vec::new(),
+            tip_accounts: Vec::new(),
+            min_tip: 0,
+            rpc_client: Arc::new(SolanaRpcClient::new(rpc_url)),
+            http_client,
+            options: ProviderOptions::default(),
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Require transactions to tip one of `tip_accounts` at least `min_tip` lamports
+    pub fn with_tip_accounts(mut self, tip_accounts: Vec<Pubkey>, min_tip: u64) -> Self {
+        self.tip_accounts = tip_accounts;
+        self.min_tip = min_tip;
+        self
+    }
+
+    async fn submit(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        wait_confirmation: bool,
+    ) -> Result<Signature, SwqosError> {
+        let transaction = self.options.prepare(self, transaction).await?;
+        let transaction = transaction.as_ref();
+        let start_time = Instant::now();
+        let (content, signature) =
+            serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
+
+        let request_body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sendTransaction",
+            "params": [content, { "encoding": "base64", "skipPreflight": true }]
+        });
+        let mut request = self
+            .http_client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(request_body.to_string());
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response_text = request.send().await?.text().await?;
+
+        let rejected = |message: String| SwqosError::ProviderRejected {
+            provider: SwqosType::GenericRelay,
+            message,
+        };
+        let response: serde_json::Value =
+            serde_json::from_str(&response_text).map_err(|_| rejected(response_text.clone()))?;
+        if let Some(error) = response.get("error") {
+            log::error!(" [relay] {} submission failed: {}", trade_type, error);
+            return Err(rejected(error.to_string()));
+        }
+        if response.get("result").is_none() {
+            return Err(rejected(response_text));
+        }
+        log::info!(" [relay] {} submitted: {:?}", trade_type, start_time.elapsed());
+        self.options.on_submitted(SwqosType::GenericRelay, &signature, start_time.elapsed());
+
+        let start_time = Instant::now();
+        self.options
+            .confirm(&self.rpc_client, trade_type, signature, wait_confirmation)
+            .await
+        .inspect_err(|_| {
+            log::error!(" [relay] {} confirmation failed: {:?}", trade_type, start_time.elapsed());
+        })?;
+        if wait_confirmation {
+            log::info!(" [relay] {} confirmed: {:?}", trade_type, start_time.elapsed());
+        }
+        Ok(signature)
+    }
+}
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for GenericRelayClient {
+    async fn send_transaction(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        wait_confirmation: bool,
+    ) -> Result<Signature, SwqosError> {
+        let submit = self.submit(trade_type, transaction, wait_confirmation);
+        self.options.observe_failures(transaction, submit).await
+    }
+
+    async fn send_transactions(
+        &self,
+        trade_type: TradeType,
+        transactions: &Vec<VersionedTransaction>,
+        wait_confirmation: bool,
+    ) -> Result<Vec<Signature>, SwqosError> {
+        let mut signatures = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            signatures.push(self.submit(trade_type, transaction, wait_confirmation).await?);
+        }
+        Ok(signatures)
+    }
+
+    fn get_tip_account(&self) -> Result<String> {
+        choose_tip_account(&self.tip_accounts)
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        self.tip_accounts.iter().map(|account| account.to_string()).collect()
+    }
+
+    fn get_swqos_type(&self) -> SwqosType {
+        SwqosType::GenericRelay
+    }
+
+    fn provider_options(&self) -> Option<&ProviderOptions> {
+        Some(&self.options)
+    }
+
+    async fn health_check(&self) -> Result<Duration> {
+        probe_endpoint(&self.http_client, &self.url).await
+    }
+
+    fn min_tip_lamports(&self) -> u64 {
+        self.min_tip
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::signer::{keypair::Keypair, Signer};
+    use crate::common::rpc_provider::serve_json_rpc_once;
+
+    #[tokio::test]
+    async fn test_posts_base64_transaction_with_headers() {
+        let response = r#"{"jsonrpc":"2.0","result":"ok","id":1}"#.to_string();
+        let (url, server) = serve_json_rpc_once(response).await;
+        let tip_account = Pubkey::new_unique();
+        let client = GenericRelayClient::new("http://127.0.0.1:1".to_string(), url)
+            .with_header("x-relay-key", "secret")
+            .with_tip_accounts(vec![tip_account], 1_000);
+
+        let payer = Keypair::new();
+        let tip =
+            solana_system_interface::instruction::transfer(&payer.pubkey(), &tip_account, 1_000);
+        let message = VersionedMessage::Legacy(Message::new(&[tip], Some(&payer.pubkey())));
+        let tx = VersionedTransaction::try_new(message, &[&payer]).unwrap();
+
+        let signature = client.send_transaction(TradeType::Buy, &tx, false).await.unwrap();
+        assert_eq!(signature, tx.signatures[0]);
+        let request = server.await.unwrap();
+        assert!(request.contains("x-relay-key: secret"));
+        assert!(request.contains(r#""encoding":"base64""#));
+
+        let untipped = VersionedTransaction::try_new(
+            VersionedMessage::Legacy(Message::new(&[], Some(&payer.pubkey()))),
+            &[&payer],
+        )
+        .unwrap();
+        assert!(matches!(
+            client.send_transaction(TradeType::Buy, &untipped, false).await,
+            Err(SwqosError::MissingTip { min_lamports: 1_000, .. })
+        ));
+    }
+}
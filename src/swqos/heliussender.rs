@@ -0,0 +1,158 @@
+use crate::swqos::common::{choose_tip_account, probe_endpoint, SwqosError, serialize_transaction_and_encode};
+use crate::swqos::provider_options::{impl_provider_builders, ProviderOptions};
+use reqwest::Client;
+use serde_json::json;
+use std::{sync::Arc, time::Instant};
+
+use std::time::Duration;
+use solana_transaction_status::UiTransactionEncoding;
+
+use anyhow::Result;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use crate::swqos::{SwqosType, TradeType};
+use crate::swqos::SwqosClientTrait;
+
+use crate::{common::SolanaRpcClient, constants::swqos::HELIUS_SENDER_TIP_ACCOUNTS};
+
+/// Helius Sender: low-latency `sendTransaction` that routes to validators and Jito at once.
+///
+/// Every transaction must carry a tip to one of `HELIUS_SENDER_TIP_ACCOUNTS` and a compute unit price.
+#[derive(Clone)]
+pub struct HeliusSenderClient {
+    pub endpoint: String,
+    pub auth_token: String,
+    pub rpc_client: Arc<SolanaRpcClient>,
+    pub http_client: Client,
+    /// Rate limit, auto-tip, confirmation and observer settings, see `ProviderOptions`
+    pub options: ProviderOptions,
+}
+
+impl_provider_builders!(HeliusSenderClient);
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for HeliusSenderClient {
+    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<Signature, SwqosError> {
+        self.options.observe_failures(transaction, self.send_transaction(trade_type, transaction, wait_confirmation)).await
+    }
+
+    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError> {
+        self.send_transactions(trade_type, transactions, wait_confirmation).await
+    }
+
+    fn get_tip_account(&self) -> Result<String> {
+        choose_tip_account(HELIUS_SENDER_TIP_ACCOUNTS)
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        HELIUS_SENDER_TIP_ACCOUNTS.iter().map(|account| account.to_string()).collect()
+    }
+
+    fn get_swqos_type(&self) -> SwqosType {
+        SwqosType::HeliusSender
+    }
+
+    fn provider_options(&self) -> Option<&ProviderOptions> {
+        Some(&self.options)
+    }
+
+    async fn health_check(&self) -> Result<Duration> {
+        probe_endpoint(&self.http_client, &self.endpoint).await
+    }
+}
+
+impl HeliusSenderClient {
+    pub fn new(rpc_url: String, endpoint: String, auth_token: String) -> Self {
+        let rpc_client = SolanaRpcClient::new(rpc_url);
+        let http_client = Client::builder()
+            // Optimized connection pool settings for high performance
+            .pool_idle_timeout(Duration::from_secs(120))
+            .pool_max_idle_per_host(256)  // Increased from 64 to 256
+            .tcp_keepalive(Some(Duration::from_secs(60)))  // Reduced from 1200 to 60
+            .tcp_nodelay(true)  // Disable Nagle's algorithm for lower latency
+            .http2_keep_alive_interval(Duration::from_secs(10))
+            .http2_keep_alive_timeout(Duration::from_secs(5))
+            .http2_adaptive_window(true)  // Enable adaptive flow control
+            .timeout(Duration::from_millis(3000))  // Reduced from 10s to 3s
+            .connect_timeout(Duration::from_millis(2000))  // Reduced from 5s to 2s
+            .build()
+            .unwrap();
+        Self { rpc_client: Arc::new(rpc_client), endpoint, auth_token, http_client, options: ProviderOptions::default() }
+    }
+
+    pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<Signature, SwqosError> {
+        let transaction = self.options.prepare(self, transaction).await?;
+        let transaction = transaction.as_ref();
+        let start_time = Instant::now();
+        let (content, signature) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
+
+        let request_body = serde_json::to_string(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sendTransaction",
+            "params": [
+                content,
+                // Sender requires skipPreflight and does its own retries
+                { "encoding": "base64", "skipPreflight": true, "maxRetries": 0 }
+            ]
+        }))?;
+
+        // Sender works without an API key, it is only needed for higher rate limits
+        let mut url = String::with_capacity(self.endpoint.len() + self.auth_token.len() + 20);
+        url.push_str(&self.endpoint);
+        if !self.auth_token.is_empty() {
+            url.push_str("?api-key=");
+            url.push_str(&self.auth_token);
+        }
+
+        // 4. Use `text().await?` directly, avoiding async JSON parsing from `json().await?`
+        let response_text = self.http_client.post(&url)
+            .body(request_body) // Pass string directly, avoiding `json()` overhead
+            .header("Content-Type", "application/json") // Explicitly specify JSON header
+            .send()
+            .await?
+            .text()
+            .await?;
+
+        // 5. Use `serde_json::from_str()` to parse JSON, reducing extra wait from `.json().await?`
+        let rejected = |message: String| SwqosError::ProviderRejected { provider: SwqosType::HeliusSender, message };
+        let response_json = serde_json::from_str::<serde_json::Value>(&response_text).map_err(|_| {
+            eprintln!(" [helius sender] {} submission failed: {:?}", trade_type, response_text);
+            rejected(response_text.clone())
+        })?;
+        if response_json.get("result").is_some() {
+            println!(" [helius sender] {} submitted: {:?}", trade_type, start_time.elapsed());
+        } else if let Some(_error) = response_json.get("error") {
+            eprintln!(" [helius sender] {} submission failed: {:?}", trade_type, _error);
+            return Err(rejected(_error.to_string()));
+        } else {
+            eprintln!(" [helius sender] {} submission failed: {:?}", trade_type, response_text);
+            return Err(rejected(response_text));
+        }
+
+        self.options.on_submitted(self.get_swqos_type(), &signature, start_time.elapsed());
+        let start_time: Instant = Instant::now();
+        match self.options.confirm(&self.rpc_client, trade_type, signature, wait_confirmation).await {
+            Ok(_) => (),
+            Err(e) => {
+                println!(" signature: {:?}", signature);
+                println!(" [helius sender] {} confirmation failed: {:?}", trade_type, start_time.elapsed());
+                return Err(e);
+            },
+        }
+        if wait_confirmation {
+            println!(" signature: {:?}", signature);
+            println!(" [helius sender] {} confirmed: {:?}", trade_type, start_time.elapsed());
+        }
+
+        Ok(signature)
+    }
+
+    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError> {
+        let mut signatures = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            signatures.push(self.send_transaction(trade_type, transaction, wait_confirmation).await?);
+        }
+        Ok(signatures)
+    }
+}
\ No newline at end of file
@@ -1,6 +1,6 @@
 
-use crate::swqos::common::{poll_transaction_confirmation, serialize_transaction_and_encode, FormatBase64VersionedTransaction};
-use rand::seq::IndexedRandom;
+use crate::swqos::common::{choose_tip_account, probe_endpoint, tip_lamports, validate_size, SwqosError, serialize_transaction_and_encode, FormatBase64VersionedTransaction};
+use crate::swqos::provider_options::{impl_provider_builders, ProviderOptions};
 use reqwest::Client;
 use serde_json::json;
 use std::{sync::Arc, time::Instant};
@@ -9,41 +9,139 @@ use std::time::Duration;
 use solana_transaction_status::UiTransactionEncoding;
 
 use anyhow::Result;
+use solana_sdk::signature::Signature;
 use solana_sdk::transaction::VersionedTransaction;
-use crate::swqos::{SwqosType, TradeType};
+use crate::swqos::{BundleId, BundleStatus, SwqosRegion, SwqosType, TradeType};
 use crate::swqos::SwqosClientTrait;
 
-use crate::{common::SolanaRpcClient, constants::swqos::JITO_TIP_ACCOUNTS};
+use crate::{common::SolanaRpcClient, constants::swqos::{JITO_TIP_ACCOUNTS, SWQOS_ENDPOINTS_JITO}};
+use futures::future::join_all;
+use rand::seq::IndexedRandom;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use solana_system_interface::instruction::transfer;
+
+/// Most transactions the block engine accepts in one bundle
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+/// Jito's published tip accounts, a bundle only lands if it tips one of them
+pub fn tip_accounts() -> &'static [Pubkey] {
+    JITO_TIP_ACCOUNTS
+}
+
+/// System Program transfer of `lamports` from `from` to a random Jito tip account
+pub fn build_tip_instruction(from: &Pubkey, lamports: u64) -> Instruction {
+    let tip_account = JITO_TIP_ACCOUNTS.choose(&mut rand::rng()).expect("Jito tip accounts are not empty");
+    transfer(from, tip_account, lamports)
+}
+
+/// Block-engine region a `JitoClient` submits to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum JitoRegion {
+    Amsterdam,
+    Frankfurt,
+    London,
+    NewYork,
+    SaltLakeCity,
+    Tokyo,
+    /// The global endpoint, which Jito routes to the region closest to the caller
+    #[default]
+    Closest,
+}
+
+impl JitoRegion {
+    /// Every pinned region, `Closest` excluded
+    pub const ALL: [JitoRegion; 6] = [
+        JitoRegion::Amsterdam,
+        JitoRegion::Frankfurt,
+        JitoRegion::London,
+        JitoRegion::NewYork,
+        JitoRegion::SaltLakeCity,
+        JitoRegion::Tokyo,
+    ];
+
+    pub fn endpoint(self) -> &'static str {
+        let region = match self {
+            JitoRegion::Amsterdam => SwqosRegion::Amsterdam,
+            JitoRegion::Frankfurt => SwqosRegion::Frankfurt,
+            JitoRegion::London => SwqosRegion::London,
+            JitoRegion::NewYork => SwqosRegion::NewYork,
+            JitoRegion::SaltLakeCity => SwqosRegion::SLC,
+            JitoRegion::Tokyo => SwqosRegion::Tokyo,
+            JitoRegion::Closest => SwqosRegion::Default,
+        };
+        SWQOS_ENDPOINTS_JITO[region as usize]
+    }
 
+    /// Probe every region in `ALL` concurrently and return the one answering fastest, once at
+    /// startup rather than per trade; fails if none answers within `timeout`
+    pub async fn fastest(timeout: Duration) -> Result<JitoRegion> {
+        let http_client = Client::builder().timeout(timeout).connect_timeout(timeout).build()?;
+        let latencies = join_all(Self::ALL.iter().map(|region| probe_endpoint(&http_client, region.endpoint()))).await;
+        Self::ALL
+            .into_iter()
+            .zip(latencies)
+            .filter_map(|(region, latency)| Some((region, latency.ok()?)))
+            .min_by_key(|(_, latency)| *latency)
+            .map(|(region, _)| region)
+            .ok_or_else(|| anyhow::anyhow!("No Jito block-engine region answered within {:?}", timeout))
+    }
+}
 
 pub struct JitoClient {
     pub endpoint: String,
     pub auth_token: String,
     pub rpc_client: Arc<SolanaRpcClient>,
     pub http_client: Client,
+    /// Rate limit, auto-tip, confirmation and observer settings, see `ProviderOptions`
+    pub options: ProviderOptions,
 }
 
+impl_provider_builders!(JitoClient);
+
 #[async_trait::async_trait]
 impl SwqosClientTrait for JitoClient {
-    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<()> {
-        self.send_transaction_impl(trade_type, transaction, wait_confirmation).await
+    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<Signature, SwqosError> {
+        self.options.observe_failures(transaction, self.send_transaction_impl(trade_type, transaction, wait_confirmation)).await
     }
 
-    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<()> {
+    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError> {
         self.send_transactions_impl(trade_type, transactions, wait_confirmation).await
     }
 
     fn get_tip_account(&self) -> Result<String> {
-        if let Some(acc) = JITO_TIP_ACCOUNTS.choose(&mut rand::rng()) {
-            Ok(acc.to_string())
-        } else {
-            Err(anyhow::anyhow!("no valid tip accounts found"))
-        }
+        choose_tip_account(JITO_TIP_ACCOUNTS)
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        JITO_TIP_ACCOUNTS.iter().map(|account| account.to_string()).collect()
     }
 
     fn get_swqos_type(&self) -> SwqosType {
         SwqosType::Jito
     }
+
+    fn provider_options(&self) -> Option<&ProviderOptions> {
+        Some(&self.options)
+    }
+
+    async fn health_check(&self) -> Result<Duration> {
+        probe_endpoint(&self.http_client, &self.endpoint).await
+    }
+
+    async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<BundleId, SwqosError> {
+        self.send_bundle_impl(transactions).await
+    }
+
+    async fn poll_bundle_status(&self, id: &BundleId, timeout: Duration) -> Result<BundleStatus, SwqosError> {
+        let start = Instant::now();
+        loop {
+            let status = self.get_bundle_status(id).await?;
+            if status != BundleStatus::Pending || start.elapsed() >= timeout {
+                return Ok(status);
+            }
+            tokio::time::sleep(BUNDLE_STATUS_POLL_INTERVAL).await;
+        }
+    }
 }
 
 impl JitoClient {
@@ -62,10 +160,18 @@ impl JitoClient {
             .connect_timeout(Duration::from_millis(2000))  // Reduced from 5s to 2s
             .build()
             .unwrap();
-        Self { rpc_client: Arc::new(rpc_client), endpoint, auth_token, http_client }
+        Self { rpc_client: Arc::new(rpc_client), endpoint, auth_token, http_client, options: ProviderOptions::default() }
     }
 
-    pub async fn send_transaction_impl(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<()> {
+    /// Submit to `region`'s block engine, e.g. the result of `JitoRegion::fastest`
+    pub fn with_region(mut self, region: JitoRegion) -> Self {
+        self.endpoint = region.endpoint().to_string();
+        self
+    }
+
+    pub async fn send_transaction_impl(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<Signature, SwqosError> {
+        let transaction = self.options.prepare(self, transaction).await?;
+        let transaction = transaction.as_ref();
         let start_time = Instant::now();
         let (content, signature) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
 
@@ -100,18 +206,24 @@ impl JitoClient {
             .text()
             .await?;
 
-        if let Ok(response_json) = serde_json::from_str::<serde_json::Value>(&response_text) {
-            if response_json.get("result").is_some() {
-                println!(" [jito] {} submitted: {:?}", trade_type, start_time.elapsed());
-            } else if let Some(_error) = response_json.get("error") {
-                eprintln!(" [jito] {} submission failed: {:?}", trade_type, _error);
-            }
+        let rejected = |message: String| SwqosError::ProviderRejected { provider: SwqosType::Jito, message };
+        let response_json = serde_json::from_str::<serde_json::Value>(&response_text).map_err(|_| {
+            eprintln!(" [jito] {} submission failed: {:?}", trade_type, response_text);
+            rejected(response_text.clone())
+        })?;
+        if response_json.get("result").is_some() {
+            println!(" [jito] {} submitted: {:?}", trade_type, start_time.elapsed());
+        } else if let Some(_error) = response_json.get("error") {
+            eprintln!(" [jito] {} submission failed: {:?}", trade_type, _error);
+            return Err(rejected(_error.to_string()));
         } else {
             eprintln!(" [jito] {} submission failed: {:?}", trade_type, response_text);
+            return Err(rejected(response_text));
         }
 
+        self.options.on_submitted(self.get_swqos_type(), &signature, start_time.elapsed());
         let start_time: Instant = Instant::now();
-        match poll_transaction_confirmation(&self.rpc_client, signature, wait_confirmation).await {
+        match self.options.confirm(&self.rpc_client, trade_type, signature, wait_confirmation).await {
             Ok(_) => (),
             Err(e) => {
                 println!(" signature: {:?}", signature);
@@ -124,11 +236,36 @@ impl JitoClient {
             println!(" [jito] {} confirmed: {:?}", trade_type, start_time.elapsed());
         }
 
-        Ok(())
+        Ok(signature)
     }
 
-    pub async fn send_transactions_impl(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, _wait_confirmation: bool) -> Result<()> {
+    pub async fn send_transactions_impl(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, _wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError> {
         let start_time = Instant::now();
+        self.send_bundle_impl(transactions).await?;
+        println!(" jito {} submitted: {:?}", trade_type, start_time.elapsed());
+
+        Ok(transactions.iter().filter_map(|tx| tx.signatures.first().copied()).collect())
+    }
+
+    /// POST the transactions to `sendBundle` and return the bundle UUID.
+    ///
+    /// Like a single send, every transaction must fit in a packet and stay under the fee ceiling,
+    /// and together they must tip at least `min_tip_lamports`; bundles of more than
+    /// `MAX_BUNDLE_TRANSACTIONS` are refused before posting.
+    pub async fn send_bundle_impl(&self, transactions: &[VersionedTransaction]) -> Result<BundleId, SwqosError> {
+        if transactions.is_empty() || transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(SwqosError::InvalidBundle { len: transactions.len(), max: MAX_BUNDLE_TRANSACTIONS });
+        }
+        for transaction in transactions {
+            validate_size(transaction)?;
+            self.ensure_fee_ceiling(transaction)?;
+        }
+        let min_lamports = self.min_tip_lamports();
+        let tipped: u64 = transactions.iter().map(|tx| tip_lamports(tx, tip_accounts())).sum();
+        if tipped < min_lamports {
+            return Err(SwqosError::MissingTip { provider: SwqosType::Jito, min_lamports });
+        }
+        self.options.acquire().await;
         let txs_base64 = transactions.iter().map(|tx| tx.to_base64_string()).collect::<Vec<String>>();
         let body = serde_json::json!({
             "jsonrpc": "2.0",
@@ -159,14 +296,158 @@ impl JitoClient {
             .text()
             .await?;
 
-        if let Ok(response_json) = serde_json::from_str::<serde_json::Value>(&response_text) {
-            if response_json.get("result").is_some() {
-                println!(" jito {} submitted: {:?}", trade_type, start_time.elapsed());
-            } else if let Some(_error) = response_json.get("error") {
-                eprintln!(" jito {} submission failed: {:?}", trade_type, _error);
-            }
+        let response_json = serde_json::from_str::<serde_json::Value>(&response_text)
+            .map_err(|_| SwqosError::ProviderRejected { provider: SwqosType::Jito, message: response_text.clone() })?;
+        if let Some(bundle_id) = response_json.get("result").and_then(|result| result.as_str()) {
+            return Ok(BundleId(bundle_id.to_string()));
         }
+        let message = response_json.get("error").map(|error| error.to_string()).unwrap_or(response_text);
+        eprintln!(" jito bundle submission failed: {:?}", message);
+        Err(SwqosError::ProviderRejected { provider: SwqosType::Jito, message })
+    }
+
+    /// Single `getBundleStatuses` lookup; an unknown bundle is reported as `Pending`
+    pub async fn get_bundle_status(&self, id: &BundleId) -> Result<BundleStatus, SwqosError> {
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "getBundleStatuses",
+            "params": [[id.0]],
+            "id": 1,
+        });
 
-        Ok(())
+        let endpoint = if self.auth_token.is_empty() {
+            format!("{}/api/v1/getBundleStatuses", self.endpoint)
+        } else {
+            format!("{}/api/v1/getBundleStatuses?uuid={}", self.endpoint, self.auth_token)
+        };
+        let response = if self.auth_token.is_empty() {
+            self.http_client.post(&endpoint)
+        } else {
+            self.http_client.post(&endpoint)
+                .header("x-jito-auth", &self.auth_token)
+        };
+        let response_json = response
+            .body(body.to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+
+        if let Some(error) = response_json.get("error") {
+            return Err(SwqosError::ProviderRejected { provider: SwqosType::Jito, message: error.to_string() });
+        }
+        Ok(parse_bundle_status(&response_json["result"]["value"][0]))
+    }
+}
+
+/// Jito rate-limits the block engine to about one request per second by default
+const BUNDLE_STATUS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Map one entry of the `getBundleStatuses` `value` array
+fn parse_bundle_status(value: &serde_json::Value) -> BundleStatus {
+    if value.is_null() {
+        return BundleStatus::Pending;
+    }
+    match value.get("err") {
+        Some(err) if !err.is_null() && err.get("Ok").is_none() => return BundleStatus::Failed(err.to_string()),
+        _ => {}
+    }
+    match (value.get("confirmation_status").and_then(|s| s.as_str()), value.get("slot").and_then(|s| s.as_u64())) {
+        (Some("confirmed") | Some("finalized"), Some(slot)) => BundleStatus::Landed(slot),
+        _ => BundleStatus::Pending,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bundle_status() {
+        assert_eq!(parse_bundle_status(&serde_json::Value::Null), BundleStatus::Pending);
+
+        let landed = json!({ "bundle_id": "abc", "slot": 242806119, "confirmation_status": "finalized", "err": { "Ok": null } });
+        assert_eq!(parse_bundle_status(&landed), BundleStatus::Landed(242806119));
+
+        let processed = json!({ "bundle_id": "abc", "slot": 242806119, "confirmation_status": "processed", "err": { "Ok": null } });
+        assert_eq!(parse_bundle_status(&processed), BundleStatus::Pending);
+
+        let failed = json!({ "bundle_id": "abc", "slot": 242806119, "confirmation_status": "confirmed", "err": { "Err": "BundleFailed" } });
+        assert!(matches!(parse_bundle_status(&failed), BundleStatus::Failed(_)));
+    }
+
+    #[test]
+    fn test_region_endpoints() {
+        assert_eq!(JitoRegion::Tokyo.endpoint(), "https://tokyo.mainnet.block-engine.jito.wtf");
+        assert_eq!(JitoRegion::SaltLakeCity.endpoint(), "https://slc.mainnet.block-engine.jito.wtf");
+        assert_eq!(JitoRegion::default().endpoint(), "https://mainnet.block-engine.jito.wtf");
+        let client = JitoClient::new("http://127.0.0.1:8899".to_string(), String::new(), String::new())
+            .with_region(JitoRegion::Frankfurt);
+        assert_eq!(client.endpoint, "https://frankfurt.mainnet.block-engine.jito.wtf");
+    }
+
+    #[test]
+    fn test_tip_instruction_pays_a_tip_account() {
+        use crate::swqos::common::tip_lamports;
+        use solana_hash::Hash;
+        use solana_sdk::{message::{Message, VersionedMessage}, signature::Keypair, signer::Signer};
+
+        let payer = Keypair::new();
+        let instruction = build_tip_instruction(&payer.pubkey(), 10_000);
+        assert!(tip_accounts().contains(&instruction.accounts[1].pubkey));
+
+        let message = Message::new_with_blockhash(&[instruction], Some(&payer.pubkey()), &Hash::default());
+        let transaction = VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[&payer]).unwrap();
+        assert_eq!(tip_lamports(&transaction, tip_accounts()), 10_000);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_bundle_is_checked_before_posting() {
+        use solana_hash::Hash;
+        use solana_sdk::{message::{Message, VersionedMessage}, signature::Keypair, signer::Signer};
+
+        // Nothing listens on the endpoint, every case has to fail before the request
+        let client = JitoClient::new("http://127.0.0.1:1".to_string(), "http://127.0.0.1:1".to_string(), String::new());
+        let payer = Keypair::new();
+        let sign = |instructions: &[Instruction]| {
+            let message = Message::new_with_blockhash(instructions, Some(&payer.pubkey()), &Hash::default());
+            VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[&payer]).unwrap()
+        };
+        let tipped = sign(&[build_tip_instruction(&payer.pubkey(), client.min_tip_lamports())]);
+        let untipped = sign(&[transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)]);
+
+        assert!(matches!(client.send_bundle_impl(&[]).await, Err(SwqosError::InvalidBundle { len: 0, max: 5 })));
+        let oversized = vec![tipped.clone(); MAX_BUNDLE_TRANSACTIONS + 1];
+        assert!(matches!(client.send_bundle_impl(&oversized).await, Err(SwqosError::InvalidBundle { len: 6, .. })));
+        assert!(matches!(
+            client.send_bundle_impl(&[untipped.clone(), untipped.clone()]).await,
+            Err(SwqosError::MissingTip { provider: SwqosType::Jito, .. })
+        ));
+        // One tipping transaction covers the bundle, so it gets as far as the request
+        assert!(matches!(client.send_bundle_impl(&[untipped, tipped]).await, Err(SwqosError::RpcSend(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unparsable_response_is_rejected() {
+        use crate::common::rpc_provider::serve_json_rpc_once;
+        use solana_hash::Hash;
+        use solana_sdk::{message::{Message, VersionedMessage}, signature::Keypair, signer::Signer};
+
+        let payer = Keypair::new();
+        let tip = build_tip_instruction(&payer.pubkey(), 10_000);
+        let message = Message::new_with_blockhash(&[tip], Some(&payer.pubkey()), &Hash::default());
+        let transaction = VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[&payer]).unwrap();
+
+        // A gateway error page and a reply without `result` are not submissions
+        for body in ["<html>502 Bad Gateway</html>", r#"{"jsonrpc":"2.0","id":1}"#] {
+            let (url, server) = serve_json_rpc_once(body.to_string()).await;
+            let client = JitoClient::new("http://127.0.0.1:1".to_string(), url, String::new());
+            assert!(matches!(
+                client.send_transaction(TradeType::Buy, &transaction, false).await,
+                Err(SwqosError::ProviderRejected { provider: SwqosType::Jito, message }) if message == body
+            ));
+            server.await.unwrap();
+        }
+    }
+}
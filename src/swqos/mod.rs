@@ -1,4 +1,11 @@
+pub mod circuit_breaker;
 pub mod common;
+pub mod dryrun;
+pub mod generic_relay;
+pub mod multi;
+pub mod observer;
+pub mod provider_options;
+pub mod rate_limiter;
 pub mod serialization;
 pub mod solana_rpc;
 pub mod jito;
@@ -14,12 +21,20 @@ pub mod stellium;
 pub mod lightspeed;
 pub mod soyas;
 pub mod speedlanding;
+pub mod heliussender;
 
-use std::sync::Arc;
+use std::{borrow::Cow, sync::Arc, time::Duration};
+
+use futures::future::join_all;
 
 use solana_commitment_config::CommitmentConfig;
+use solana_sdk::clock::Slot;
+use solana_sdk::native_token::sol_str_to_lamports;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
 use solana_sdk::transaction::VersionedTransaction;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 
 use anyhow::Result;
 
@@ -37,9 +52,30 @@ use crate::{
         SWQOS_ENDPOINTS_ASTRALANE,
         SWQOS_ENDPOINTS_STELLIUM,
         SWQOS_ENDPOINTS_SOYAS,
-        SWQOS_ENDPOINTS_SPEEDLANDING
+        SWQOS_ENDPOINTS_SPEEDLANDING,
+        SWQOS_ENDPOINTS_HELIUS_SENDER,
+        SWQOS_MIN_TIP_DEFAULT,
+        SWQOS_MIN_TIP_JITO,
+        SWQOS_MIN_TIP_NEXTBLOCK,
+        SWQOS_MIN_TIP_ZERO_SLOT,
+        SWQOS_MIN_TIP_TEMPORAL,
+        SWQOS_MIN_TIP_BLOXROUTE,
+        SWQOS_MIN_TIP_NODE1,
+        SWQOS_MIN_TIP_FLASHBLOCK,
+        SWQOS_MIN_TIP_BLOCKRAZOR,
+        SWQOS_MIN_TIP_ASTRALANE,
+        SWQOS_MIN_TIP_STELLIUM,
+        SWQOS_MIN_TIP_LIGHTSPEED,
+        SWQOS_MIN_TIP_SOYAS,
+        SWQOS_MIN_TIP_SPEEDLANDING,
+        SWQOS_MIN_TIP_HELIUS_SENDER
     },
     swqos::{
+        common::{
+            ensure_fee_ceiling, insert_tip, known_tip_accounts, tip_lamports, AutoTip, SwqosError,
+        },
+        provider_options::ProviderOptions,
+        dryrun::DryRunClient,
         bloxroute::BloxrouteClient,
         jito::JitoClient,
         nextblock::NextBlockClient,
@@ -54,6 +90,7 @@ use crate::{
         lightspeed::LightspeedClient,
         soyas::SoyasClient,
         speedlanding::SpeedlandingClient,
+        heliussender::HeliusSenderClient,
     }
 };
 
@@ -103,10 +140,43 @@ pub enum SwqosType {
     Lightspeed,
     Soyas,
     Speedlanding,
+    HeliusSender,
+    /// Never submits, see `DryRunClient`
+    DryRun,
+    /// A relay outside the built-in providers, see `GenericRelayClient`
+    GenericRelay,
     Default,
 }
 
 impl SwqosType {
+    /// Minimum tip in SOL the provider accepts
+    pub fn min_tip(&self) -> f64 {
+        match self {
+            SwqosType::Jito => SWQOS_MIN_TIP_JITO,
+            SwqosType::NextBlock => SWQOS_MIN_TIP_NEXTBLOCK,
+            SwqosType::ZeroSlot => SWQOS_MIN_TIP_ZERO_SLOT,
+            SwqosType::Temporal => SWQOS_MIN_TIP_TEMPORAL,
+            SwqosType::Bloxroute => SWQOS_MIN_TIP_BLOXROUTE,
+            SwqosType::Node1 => SWQOS_MIN_TIP_NODE1,
+            SwqosType::FlashBlock => SWQOS_MIN_TIP_FLASHBLOCK,
+            SwqosType::BlockRazor => SWQOS_MIN_TIP_BLOCKRAZOR,
+            SwqosType::Astralane => SWQOS_MIN_TIP_ASTRALANE,
+            SwqosType::Stellium => SWQOS_MIN_TIP_STELLIUM,
+            SwqosType::Lightspeed => SWQOS_MIN_TIP_LIGHTSPEED,
+            SwqosType::Soyas => SWQOS_MIN_TIP_SOYAS,
+            SwqosType::Speedlanding => SWQOS_MIN_TIP_SPEEDLANDING,
+            SwqosType::HeliusSender => SWQOS_MIN_TIP_HELIUS_SENDER,
+            SwqosType::DryRun => SWQOS_MIN_TIP_DEFAULT,
+            SwqosType::GenericRelay => SWQOS_MIN_TIP_DEFAULT,
+            SwqosType::Default => SWQOS_MIN_TIP_DEFAULT,
+        }
+    }
+
+    /// Public RPC, where a pending transaction is visible to searchers before it lands
+    pub fn is_public(&self) -> bool {
+        matches!(self, SwqosType::Default)
+    }
+
     pub fn values() -> Vec<Self> {
         vec![
             Self::Jito,
@@ -121,6 +191,10 @@ impl SwqosType {
             Self::Stellium,
             Self::Lightspeed,
             Self::Soyas,
+            Self::Speedlanding,
+            Self::HeliusSender,
+            Self::DryRun,
+            Self::GenericRelay,
             Self::Default,
         ]
     }
@@ -130,10 +204,149 @@ pub type SwqosClient = dyn SwqosClientTrait + Send + Sync + 'static;
 
 #[async_trait::async_trait]
 pub trait SwqosClientTrait {
-    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<()>;
-    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<()>;
+    /// Submit the transaction and return its signature (the first signature of the transaction)
+    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction, wait_confirmation: bool) -> Result<Signature, SwqosError>;
+    /// Submit the transactions and return their signatures in input order
+    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>, wait_confirmation: bool) -> Result<Vec<Signature>, SwqosError>;
+    /// A random tip account from the provider's list; errors if the provider takes no tips
     fn get_tip_account(&self) -> Result<String>;
+    /// All tip accounts published by the provider, empty if it takes no tips
+    fn get_tip_accounts(&self) -> Vec<String>;
     fn get_swqos_type(&self) -> SwqosType;
+    /// Measure the round-trip latency to the provider's submission endpoint
+    async fn health_check(&self) -> Result<Duration>;
+    /// Submit the transactions as one atomic bundle (all land in the same slot or none do)
+    async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<BundleId, SwqosError> {
+        let _ = transactions;
+        Err(SwqosError::Unsupported { provider: self.get_swqos_type(), operation: "send_bundle" })
+    }
+    /// Poll the bundle until it lands, fails, or `timeout` elapses (then `BundleStatus::Pending`)
+    async fn poll_bundle_status(&self, id: &BundleId, timeout: Duration) -> Result<BundleStatus, SwqosError> {
+        let _ = (id, timeout);
+        Err(SwqosError::Unsupported { provider: self.get_swqos_type(), operation: "poll_bundle_status" })
+    }
+    /// Smallest tip in lamports the provider accepts, 0 if it takes no tips
+    fn min_tip_lamports(&self) -> u64 {
+        sol_str_to_lamports(&self.get_swqos_type().min_tip().to_string()).unwrap_or(0)
+    }
+    /// Fail with `SwqosError::MissingTip` unless the transaction transfers at least `min_tip_lamports`
+    /// to one of `get_tip_accounts`; providers silently drop untipped transactions.
+    /// Providers without tip accounts accept anything.
+    fn ensure_tip(&self, transaction: &VersionedTransaction) -> Result<(), SwqosError> {
+        let tip_accounts: Vec<Pubkey> =
+            self.get_tip_accounts().iter().filter_map(|account| account.parse().ok()).collect();
+        if tip_accounts.is_empty() {
+            return Ok(());
+        }
+        let min_lamports = self.min_tip_lamports();
+        if tip_lamports(transaction, &tip_accounts) < min_lamports {
+            return Err(SwqosError::MissingTip { provider: self.get_swqos_type(), min_lamports });
+        }
+        Ok(())
+    }
+    /// `ensure_tip`, except that with `auto_tip` set an untipped transaction is not rejected: a
+    /// transfer of `auto_tip.lamports` (at least `min_tip_lamports`) from `auto_tip.payer` to one
+    /// of `get_tip_accounts` is appended and the transaction re-signed, see `common::insert_tip`.
+    /// The re-signed copy has a different signature than `transaction`.
+    ///
+    /// A transaction already tipping any provider (`common::known_tip_accounts` or
+    /// `get_tip_accounts`) is rejected rather than re-signed, since the original may be sent
+    /// through that provider too and both copies could land.
+    fn ensure_or_insert_tip<'a>(
+        &self,
+        transaction: &'a VersionedTransaction,
+        auto_tip: Option<&AutoTip>,
+    ) -> Result<Cow<'a, VersionedTransaction>, SwqosError> {
+        let missing = match self.ensure_tip(transaction) {
+            Ok(()) => return Ok(Cow::Borrowed(transaction)),
+            Err(missing @ SwqosError::MissingTip { .. }) => missing,
+            Err(e) => return Err(e),
+        };
+        let tip_accounts: Vec<Pubkey> =
+            self.get_tip_accounts().iter().filter_map(|account| account.parse().ok()).collect();
+        let tipped = tip_lamports(transaction, known_tip_accounts()) > 0
+            || tip_lamports(transaction, &tip_accounts) > 0;
+        if tipped {
+            return Err(missing);
+        }
+        let tip_account = self.get_tip_account().ok().and_then(|account| account.parse().ok());
+        let (Some(auto_tip), Some(tip_account)) = (auto_tip, tip_account) else {
+            return Err(missing);
+        };
+        let lamports = auto_tip.lamports.max(self.min_tip_lamports());
+        match insert_tip(transaction, &auto_tip.payer, &tip_account, lamports) {
+            Ok(tipped) => {
+                log::info!(" [{:?}] inserted a {} lamport tip", self.get_swqos_type(), lamports);
+                Ok(Cow::Owned(tipped))
+            }
+            Err(e) => {
+                log::warn!(" [{:?}] cannot insert a tip: {}", self.get_swqos_type(), e);
+                Err(missing)
+            }
+        }
+    }
+    /// Rate limit and auto-tip settings of a provider client, `None` for clients without them
+    fn provider_options(&self) -> Option<&ProviderOptions> {
+        None
+    }
+    /// Fail with `SwqosError::FeeCeilingExceeded` if the priority fee plus the tip to
+    /// `get_tip_accounts` exceed `common::set_max_fee_lamports`
+    fn ensure_fee_ceiling(&self, transaction: &VersionedTransaction) -> Result<(), SwqosError> {
+        let tip_accounts: Vec<Pubkey> =
+            self.get_tip_accounts().iter().filter_map(|account| account.parse().ok()).collect();
+        ensure_fee_ceiling(transaction, &tip_accounts)
+    }
+}
+
+impl SwqosClient {
+    /// Send and confirm the transaction on a new tokio task holding its own clone of the client,
+    /// so the caller can build the next trade right away and await the outcome later.
+    ///
+    /// Unlike `send_transaction(.., false)` the handle still resolves to the confirmation result.
+    pub fn spawn_send(
+        self: &Arc<Self>,
+        trade_type: TradeType,
+        transaction: VersionedTransaction,
+    ) -> JoinHandle<Result<Signature, SwqosError>> {
+        let client = Arc::clone(self);
+        tokio::spawn(async move { client.send_transaction(trade_type, &transaction, true).await })
+    }
+}
+
+/// Identifier returned by the block engine for a submitted bundle
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BundleId(pub String);
+
+impl std::fmt::Display for BundleId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleStatus {
+    /// The bundle landed in this slot
+    Landed(Slot),
+    /// The bundle has not been seen yet
+    Pending,
+    /// The bundle landed with an error
+    Failed(String),
+}
+
+/// Probe all clients concurrently and return the reachable ones sorted by latency, fastest first
+pub async fn rank_providers(clients: &[Arc<SwqosClient>]) -> Vec<(SwqosType, Duration)> {
+    let probes = clients.iter().map(|client| async move {
+        match client.health_check().await {
+            Ok(latency) => Some((client.get_swqos_type(), latency)),
+            Err(e) => {
+                log::warn!("{:?} health check failed: {}", client.get_swqos_type(), e);
+                None
+            }
+        }
+    });
+    let mut ranked: Vec<(SwqosType, Duration)> = join_all(probes).await.into_iter().flatten().collect();
+    ranked.sort_by_key(|(_, latency)| *latency);
+    ranked
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -154,6 +367,7 @@ pub enum SwqosConfig {
     /// Jito(uuid, region, custom_url)
     Jito(String, SwqosRegion, Option<String>),
     /// NextBlock(api_token, region, custom_url)
+    /// Listed in `SWQOS_BLACKLIST` by default, remove it there or build `NextBlockClient` directly
     NextBlock(String, SwqosRegion, Option<String>),
     /// Bloxroute(api_token, region, custom_url)
     Bloxroute(String, SwqosRegion, Option<String>),
@@ -180,6 +394,11 @@ pub enum SwqosConfig {
     /// To apply for an API key, please contact -> https://t.me/speedlanding_bot?start=0xzero
     /// Minimum tip: 0.001 SOL
     Speedlanding(String, SwqosRegion, Option<String>),
+    /// HeliusSender(api_key, region, custom_url), the API key may be empty
+    /// Minimum tip: 0.001 SOL
+    HeliusSender(String, SwqosRegion, Option<String>),
+    /// Build and sign but never submit, for tests
+    DryRun,
 }
 
 impl SwqosConfig {
@@ -199,6 +418,8 @@ impl SwqosConfig {
             SwqosConfig::Lightspeed(_, _, _) => SwqosType::Lightspeed,
             SwqosConfig::Soyas(_, _, _) => SwqosType::Soyas,
             SwqosConfig::Speedlanding(_, _, _) => SwqosType::Speedlanding,
+            SwqosConfig::HeliusSender(_, _, _) => SwqosType::HeliusSender,
+            SwqosConfig::DryRun => SwqosType::DryRun,
         }
     }
 
@@ -226,6 +447,9 @@ impl SwqosConfig {
             SwqosType::Lightspeed => "".to_string(), // Lightspeed requires custom URL with api_key
             SwqosType::Soyas => SWQOS_ENDPOINTS_SOYAS[region as usize].to_string(),
             SwqosType::Speedlanding => SWQOS_ENDPOINTS_SPEEDLANDING[region as usize].to_string(),
+            SwqosType::HeliusSender => SWQOS_ENDPOINTS_HELIUS_SENDER[region as usize].to_string(),
+            SwqosType::DryRun => "".to_string(),
+            SwqosType::GenericRelay => "".to_string(),
             SwqosType::Default => "".to_string(),
         }
     }
@@ -349,6 +573,16 @@ impl SwqosConfig {
                 ).await?;
                 Ok(Arc::new(speedlanding_client))
             },
+            SwqosConfig::HeliusSender(api_key, region, url) => {
+                let endpoint = SwqosConfig::get_endpoint(SwqosType::HeliusSender, region, url);
+                let helius_sender_client = HeliusSenderClient::new(
+                    rpc_url.clone(),
+                    endpoint.to_string(),
+                    api_key
+                );
+                Ok(Arc::new(helius_sender_client))
+            },
+            SwqosConfig::DryRun => Ok(Arc::new(DryRunClient::new())),
             SwqosConfig::Default(endpoint) => {
                 let rpc = SolanaRpcClient::new_with_commitment(
                     endpoint,
@@ -359,4 +593,57 @@ impl SwqosConfig {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{message::Message, signature::Keypair, signer::Signer};
+
+    #[test]
+    fn test_values_lists_every_variant() {
+        // Exhaustive, so a new variant does not compile until it is numbered here
+        let index = |swqos_type: SwqosType| match swqos_type {
+            SwqosType::Jito => 0,
+            SwqosType::NextBlock => 1,
+            SwqosType::ZeroSlot => 2,
+            SwqosType::Temporal => 3,
+            SwqosType::Bloxroute => 4,
+            SwqosType::Node1 => 5,
+            SwqosType::FlashBlock => 6,
+            SwqosType::BlockRazor => 7,
+            SwqosType::Astralane => 8,
+            SwqosType::Stellium => 9,
+            SwqosType::Lightspeed => 10,
+            SwqosType::Soyas => 11,
+            SwqosType::Speedlanding => 12,
+            SwqosType::HeliusSender => 13,
+            SwqosType::DryRun => 14,
+            SwqosType::GenericRelay => 15,
+            SwqosType::Default => 16,
+        };
+        let indices: Vec<usize> = SwqosType::values().into_iter().map(index).collect();
+        assert_eq!(indices, (0..17).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_spawn_send_resolves_to_signature() {
+        let payer = Keypair::new();
+        let instructions = [solana_system_interface::instruction::transfer(
+            &payer.pubkey(),
+            &Pubkey::new_unique(),
+            1,
+        )];
+        let message = Message::new(&instructions, Some(&payer.pubkey()));
+        let tx = VersionedTransaction::from(solana_sdk::transaction::Transaction::new(
+            &[&payer],
+            message,
+            solana_hash::Hash::default(),
+        ));
+        let signature = tx.signatures[0];
+
+        let client: Arc<SwqosClient> = Arc::new(DryRunClient::new());
+        let handle = client.spawn_send(TradeType::Buy, tx);
+        drop(client);
+        assert_eq!(handle.await.unwrap().unwrap(), signature);
+    }
+}
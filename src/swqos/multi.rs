@@ -0,0 +1,486 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::future::select_ok;
+use parking_lot::Mutex;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::swqos::common::SwqosError;
+use crate::swqos::{BundleId, BundleStatus, SwqosClient, SwqosClientTrait, SwqosType, TradeType};
+
+/// How `MultiSwqosClient` spreads a submission over its providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SwqosStrategy {
+    /// Send through every provider at once, the first success wins
+    #[default]
+    FanOut,
+    /// Try providers one at a time in configured order, moving on when one fails or does not
+    /// answer within `per_attempt_timeout`
+    Failover { per_attempt_timeout: Duration },
+}
+
+/// Which providers `MultiSwqosClient` may submit through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Privacy {
+    /// Every configured provider
+    #[default]
+    Any,
+    /// Only private lanes; public RPC providers (`SwqosType::is_public`) are skipped so a pending
+    /// transaction is never exposed to sandwich bots
+    PrivateOnly,
+}
+
+/// Signatures already handed to `MultiSwqosClient`, keyed on `transaction.signatures[0]`
+#[derive(Debug, Default)]
+pub struct SeenSignatures {
+    signatures: Mutex<HashSet<Signature>>,
+}
+
+impl SeenSignatures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `signature`, returns false if it was already recorded
+    pub fn insert(&self, signature: Signature) -> bool {
+        self.signatures.lock().insert(signature)
+    }
+
+    /// Forget `signature`, returns false if it was not recorded
+    pub fn remove(&self, signature: &Signature) -> bool {
+        self.signatures.lock().remove(signature)
+    }
+
+    pub fn contains(&self, signature: &Signature) -> bool {
+        self.signatures.lock().contains(signature)
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.lock().is_empty()
+    }
+
+    /// Forget every recorded signature
+    pub fn clear(&self) {
+        self.signatures.lock().clear();
+    }
+}
+
+/// Submits the same signed transaction through several SWQOS providers
+///
+/// With `SwqosStrategy::FanOut` the first provider to succeed wins and the remaining in-flight
+/// submissions are dropped. With `SwqosStrategy::Failover` providers are tried in order, so put the
+/// cheapest first. An error is only returned when every provider fails.
+///
+/// Providers set up `with_auto_tip` re-sign the transaction, so they are only accepted as the
+/// single lane of a trade type; with more lanes the submission fails with
+/// `SwqosError::Unsupported`.
+///
+/// `with_route` picks a different provider list per `TradeType`, e.g. buys through a private lane
+/// and sells through public RPC; `clients` serves the unmapped types, bundles and the tip methods.
+#[derive(Clone)]
+pub struct MultiSwqosClient {
+    pub clients: Vec<Arc<SwqosClient>>,
+    /// Ordered providers per trade type, replacing `clients` for that type
+    pub routes: HashMap<TradeType, Vec<Arc<SwqosClient>>>,
+    pub strategy: SwqosStrategy,
+    /// When set, a transaction whose signature was already submitted is not sent again
+    pub seen_signatures: Option<Arc<SeenSignatures>>,
+    pub privacy: Privacy,
+}
+
+impl MultiSwqosClient {
+    pub fn new(clients: Vec<Arc<SwqosClient>>) -> Self {
+        Self {
+            clients,
+            routes: HashMap::new(),
+            strategy: SwqosStrategy::default(),
+            seen_signatures: None,
+            privacy: Privacy::default(),
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: SwqosStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// With `Privacy::PrivateOnly`, submissions fail instead of falling back to public RPC when
+    /// no private provider is configured
+    pub fn with_privacy(mut self, privacy: Privacy) -> Self {
+        self.privacy = privacy;
+        self
+    }
+
+    /// Send `trade_type` transactions through `clients`, in order, instead of the default list.
+    ///
+    /// Transactions of that type must tip one of these providers, e.g. via
+    /// `clients_for(trade_type)[0].get_tip_account()`.
+    pub fn with_route(mut self, trade_type: TradeType, clients: Vec<Arc<SwqosClient>>) -> Self {
+        self.routes.insert(trade_type, clients);
+        self
+    }
+
+    /// Providers `trade_type` transactions are sent through, before the `privacy` filter
+    pub fn clients_for(&self, trade_type: TradeType) -> &[Arc<SwqosClient>] {
+        self.routes.get(&trade_type).unwrap_or(&self.clients)
+    }
+
+    /// Default providers allowed by `privacy`, in configured order
+    fn lanes(&self) -> impl Iterator<Item = &Arc<SwqosClient>> {
+        self.filter_private(&self.clients)
+    }
+
+    fn filter_private<'a>(&'a self, clients: &'a [Arc<SwqosClient>]) -> impl Iterator<Item = &'a Arc<SwqosClient>> {
+        clients.iter().filter(|client| self.privacy == Privacy::Any || !client.get_swqos_type().is_public())
+    }
+
+    /// Providers of `trade_type` (the default ones for `None`) allowed by `privacy`, or an error if
+    /// none is left
+    fn checked_lanes(&self, trade_type: Option<TradeType>) -> Result<Vec<&Arc<SwqosClient>>, SwqosError> {
+        let clients = trade_type.map_or(self.clients.as_slice(), |trade_type| self.clients_for(trade_type));
+        if clients.is_empty() {
+            return Err(SwqosError::RpcSend("MultiSwqosClient has no swqos clients configured".to_string()));
+        }
+        let lanes: Vec<_> = self.filter_private(clients).collect();
+        if lanes.is_empty() {
+            return Err(SwqosError::RpcSend(
+                "MultiSwqosClient is private-only but has no private swqos clients configured".to_string(),
+            ));
+        }
+        Ok(lanes)
+    }
+
+    /// Skip resubmitting signed transactions that already went out, to avoid paying twice when
+    /// retries hand the same transaction over again; the repeat fails with
+    /// `SwqosError::AlreadySubmitted`. A submission every provider failed is not recorded.
+    /// Call `clear_seen_signatures` periodically.
+    pub fn with_idempotency_guard(mut self) -> Self {
+        self.seen_signatures = Some(Arc::new(SeenSignatures::new()));
+        self
+    }
+
+    /// Forget the submitted signatures recorded by the idempotency guard
+    pub fn clear_seen_signatures(&self) {
+        if let Some(seen_signatures) = &self.seen_signatures {
+            seen_signatures.clear();
+        }
+    }
+
+    /// Try each provider in order, returning the first signature and otherwise the last error
+    async fn send_failover(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        wait_confirmation: bool,
+        per_attempt_timeout: Duration,
+    ) -> Result<Signature, SwqosError> {
+        let mut last_error = None;
+        for client in self.checked_lanes(Some(trade_type))? {
+            let attempt = client.send_transaction(trade_type, transaction, wait_confirmation);
+            match tokio::time::timeout(per_attempt_timeout, attempt).await {
+                Ok(Ok(signature)) => return Ok(signature),
+                Ok(Err(e)) => {
+                    log::warn!(" [multi] {:?} failed, trying next provider: {}", client.get_swqos_type(), e);
+                    last_error = Some(e);
+                }
+                Err(_) => {
+                    log::warn!(
+                        " [multi] {:?} did not answer within {:?}, trying next provider",
+                        client.get_swqos_type(),
+                        per_attempt_timeout
+                    );
+                    last_error = Some(SwqosError::RpcSend(format!(
+                        "{:?} timed out after {:?}",
+                        client.get_swqos_type(),
+                        per_attempt_timeout
+                    )));
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            SwqosError::RpcSend("MultiSwqosClient has no swqos clients configured".to_string())
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for MultiSwqosClient {
+    async fn send_transaction(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        wait_confirmation: bool,
+    ) -> Result<Signature, SwqosError> {
+        let lanes = self.checked_lanes(Some(trade_type))?;
+
+        // An auto-tipping lane submits its own re-signed copy, which could land next to the
+        // copies sent through the other lanes
+        if lanes.len() > 1 {
+            for client in &lanes {
+                if client.provider_options().is_some_and(|options| options.auto_tip.is_some()) {
+                    return Err(SwqosError::Unsupported {
+                        provider: client.get_swqos_type(),
+                        operation: "auto_tip alongside other lanes",
+                    });
+                }
+            }
+        }
+
+        // A signed transaction goes out once. The signature is reserved before sending so that
+        // concurrent duplicates are refused too, and released again if every lane fails.
+        let guard = match (&self.seen_signatures, transaction.signatures.first()) {
+            (Some(seen_signatures), Some(signature)) => {
+                if !seen_signatures.insert(*signature) {
+                    log::warn!(" [multi] {} already submitted, skipping duplicate submission", signature);
+                    return Err(SwqosError::AlreadySubmitted { signature: *signature });
+                }
+                Some((seen_signatures, signature))
+            }
+            _ => None,
+        };
+
+        let result = match self.strategy {
+            SwqosStrategy::FanOut => {
+                let submissions =
+                    lanes.iter().map(|client| client.send_transaction(trade_type, transaction, wait_confirmation));
+                select_ok(submissions).await.map(|(signature, _pending)| signature)
+            }
+            SwqosStrategy::Failover { per_attempt_timeout } => {
+                self.send_failover(trade_type, transaction, wait_confirmation, per_attempt_timeout).await
+            }
+        };
+        if let (Err(_), Some((seen_signatures, signature))) = (&result, guard) {
+            seen_signatures.remove(signature);
+        }
+        result
+    }
+
+    async fn send_transactions(
+        &self,
+        trade_type: TradeType,
+        transactions: &Vec<VersionedTransaction>,
+        wait_confirmation: bool,
+    ) -> Result<Vec<Signature>, SwqosError> {
+        let mut signatures = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            signatures.push(self.send_transaction(trade_type, transaction, wait_confirmation).await?);
+        }
+        Ok(signatures)
+    }
+
+    /// Submit the bundle through every provider that supports bundles, first acceptance wins
+    async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<BundleId, SwqosError> {
+        let submissions = self.checked_lanes(None)?.into_iter().map(|client| client.send_bundle(transactions));
+        let (bundle_id, _pending) = select_ok(submissions).await?;
+        Ok(bundle_id)
+    }
+
+    /// Ask every provider for the bundle status, first answer wins
+    async fn poll_bundle_status(&self, id: &BundleId, timeout: Duration) -> Result<BundleStatus, SwqosError> {
+        if self.clients.is_empty() {
+            return Err(SwqosError::RpcSend("MultiSwqosClient has no swqos clients configured".to_string()));
+        }
+
+        let polls = self.clients.iter().map(|client| client.poll_bundle_status(id, timeout));
+        let (status, _pending) = select_ok(polls).await?;
+        Ok(status)
+    }
+
+    /// The transaction is shared by all providers, so the tip goes to the first provider allowed
+    /// by `privacy`
+    fn get_tip_account(&self) -> Result<String> {
+        self.lanes()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("MultiSwqosClient has no swqos clients configured"))?
+            .get_tip_account()
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        self.lanes().next().map(|client| client.get_tip_accounts()).unwrap_or_default()
+    }
+
+    /// Highest minimum among the providers, so one tip amount satisfies every lane of the fan-out
+    fn min_tip_lamports(&self) -> u64 {
+        self.lanes().map(|client| client.min_tip_lamports()).max().unwrap_or(0)
+    }
+
+    /// The tip goes to the first provider, see `get_tip_account`
+    fn ensure_tip(&self, transaction: &VersionedTransaction) -> Result<(), SwqosError> {
+        self.lanes().next().map_or(Ok(()), |client| client.ensure_tip(transaction))
+    }
+
+    /// Reports the type of the first configured provider
+    fn get_swqos_type(&self) -> SwqosType {
+        self.clients.first().map(|client| client.get_swqos_type()).unwrap_or(SwqosType::Default)
+    }
+
+    /// Latency of the fastest healthy provider
+    async fn health_check(&self) -> Result<Duration> {
+        if self.clients.is_empty() {
+            return Err(anyhow::anyhow!("MultiSwqosClient has no swqos clients configured"));
+        }
+        let (latency, _pending) = select_ok(self.clients.iter().map(|client| client.health_check())).await?;
+        Ok(latency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swqos::dryrun::DryRunClient;
+    use crate::swqos::nextblock::NextBlockClient;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::{v0, VersionedMessage};
+    use solana_sdk::signer::{keypair::Keypair, Signer};
+
+    /// Provider that never answers in time
+    struct StalledClient;
+
+    #[async_trait::async_trait]
+    impl SwqosClientTrait for StalledClient {
+        async fn send_transaction(&self, _: TradeType, _: &VersionedTransaction, _: bool) -> Result<Signature, SwqosError> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Err(SwqosError::RpcSend("stalled".to_string()))
+        }
+
+        async fn send_transactions(&self, _: TradeType, _: &Vec<VersionedTransaction>, _: bool) -> Result<Vec<Signature>, SwqosError> {
+            Err(SwqosError::RpcSend("stalled".to_string()))
+        }
+
+        fn get_tip_account(&self) -> Result<String> {
+            Err(anyhow::anyhow!("no tips"))
+        }
+
+        fn get_tip_accounts(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn get_swqos_type(&self) -> SwqosType {
+            SwqosType::Default
+        }
+
+        async fn health_check(&self) -> Result<Duration> {
+            Ok(Duration::ZERO)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover_moves_on_after_timeout() {
+        let payer = Keypair::new();
+        let message = v0::Message::try_compile(&payer.pubkey(), &[], &[], Hash::default()).unwrap();
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        let client = MultiSwqosClient::new(vec![Arc::new(StalledClient), Arc::new(DryRunClient::new())])
+            .with_strategy(SwqosStrategy::Failover { per_attempt_timeout: Duration::from_millis(50) });
+        let signature = client.send_transaction(TradeType::Buy, &transaction, false).await.unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_guard_skips_resubmission() {
+        let payer = Keypair::new();
+        let message = v0::Message::try_compile(&payer.pubkey(), &[], &[], Hash::default()).unwrap();
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        // A real second submission would hang on StalledClient
+        let client = MultiSwqosClient::new(vec![Arc::new(DryRunClient::new())]).with_idempotency_guard();
+        client.send_transaction(TradeType::Buy, &transaction, false).await.unwrap();
+        let client = MultiSwqosClient { clients: vec![Arc::new(StalledClient)], ..client };
+        let result = client.send_transaction(TradeType::Buy, &transaction, false).await;
+        assert!(matches!(result, Err(SwqosError::AlreadySubmitted { signature }) if signature == transaction.signatures[0]));
+
+        client.clear_seen_signatures();
+        assert!(client.seen_signatures.as_ref().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_guard_allows_retry_after_every_lane_failed() {
+        let payer = Keypair::new();
+        let message = v0::Message::try_compile(&payer.pubkey(), &[], &[], Hash::default()).unwrap();
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        let client = MultiSwqosClient::new(vec![Arc::new(StalledClient), Arc::new(StalledClient)])
+            .with_strategy(SwqosStrategy::Failover { per_attempt_timeout: Duration::from_millis(20) })
+            .with_idempotency_guard();
+        assert!(client.send_transaction(TradeType::Buy, &transaction, false).await.is_err());
+        assert!(client.seen_signatures.as_ref().unwrap().is_empty());
+
+        let client = MultiSwqosClient { clients: vec![Arc::new(DryRunClient::new())], ..client };
+        let signature = client.send_transaction(TradeType::Buy, &transaction, false).await.unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+        assert!(client.seen_signatures.as_ref().unwrap().contains(&signature));
+    }
+
+    #[tokio::test]
+    async fn test_auto_tipping_lanes_are_not_fanned_out() {
+        let payer = Arc::new(Keypair::new());
+        let message = v0::Message::try_compile(&payer.pubkey(), &[], &[], Hash::default()).unwrap();
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        let rpc_url = "http://127.0.0.1:8899".to_string();
+        let lane = || {
+            NextBlockClient::new(rpc_url.clone(), String::new(), String::new())
+                .with_auto_tip(payer.clone(), 1_000_000)
+        };
+        let client = MultiSwqosClient::new(vec![Arc::new(lane()), Arc::new(lane())]);
+        let result = client.send_transaction(TradeType::Buy, &transaction, false).await;
+        assert!(matches!(result, Err(SwqosError::Unsupported { provider: SwqosType::NextBlock, .. })));
+
+        // A transaction tipping another provider is not re-signed with a second tip
+        let tip = crate::swqos::jito::build_tip_instruction(&payer.pubkey(), 1_000_000);
+        let message = v0::Message::try_compile(&payer.pubkey(), &[tip], &[], Hash::default()).unwrap();
+        let tipped = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+        let nextblock = lane();
+        let auto_tip = nextblock.options.auto_tip.as_ref();
+        let result = nextblock.ensure_or_insert_tip(&tipped, auto_tip);
+        assert!(matches!(result, Err(SwqosError::MissingTip { .. })));
+        assert!(nextblock.ensure_or_insert_tip(&transaction, auto_tip).is_ok());
+    }
+
+    #[test]
+    fn test_min_tip_lamports_is_max_of_providers() {
+        let nextblock = NextBlockClient::new("http://127.0.0.1:8899".to_string(), String::new(), String::new());
+        let client = MultiSwqosClient::new(vec![Arc::new(DryRunClient::new()), Arc::new(nextblock)]);
+        assert_eq!(client.min_tip_lamports(), 1_000_000);
+        assert_eq!(MultiSwqosClient::new(vec![]).min_tip_lamports(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_route_picks_providers_by_trade_type() {
+        let payer = Keypair::new();
+        let message = v0::Message::try_compile(&payer.pubkey(), &[], &[], Hash::default()).unwrap();
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        let client = MultiSwqosClient::new(vec![Arc::new(StalledClient)])
+            .with_strategy(SwqosStrategy::Failover { per_attempt_timeout: Duration::from_millis(50) })
+            .with_route(TradeType::Buy, vec![Arc::new(DryRunClient::new())]);
+        assert_eq!(client.clients_for(TradeType::Buy)[0].get_swqos_type(), SwqosType::DryRun);
+        let signature = client.send_transaction(TradeType::Buy, &transaction, false).await.unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+        // Sells are unmapped and go to the stalled default provider
+        assert!(client.send_transaction(TradeType::Sell, &transaction, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_private_only_skips_public_rpc() {
+        let payer = Keypair::new();
+        let message = v0::Message::try_compile(&payer.pubkey(), &[], &[], Hash::default()).unwrap();
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        // StalledClient reports itself as public RPC, so only the dry run is used
+        let client = MultiSwqosClient::new(vec![Arc::new(StalledClient), Arc::new(DryRunClient::new())])
+            .with_privacy(Privacy::PrivateOnly);
+        let signature = client.send_transaction(TradeType::Buy, &transaction, false).await.unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+
+        let client = MultiSwqosClient::new(vec![Arc::new(StalledClient)]).with_privacy(Privacy::PrivateOnly);
+        assert!(client.send_transaction(TradeType::Buy, &transaction, false).await.is_err());
+    }
+}
@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use solana_sdk::signature::Signature;
+
+use crate::swqos::{common::SwqosError, SwqosType};
+
+/// Hooks called by the swqos clients around each submission, e.g. to export metrics.
+///
+/// All methods default to no-ops so implementors only override what they need.
+/// They are called inline on the send path and should return quickly.
+pub trait TradeObserver: Send + Sync {
+    /// The provider accepted the transaction; `elapsed` is the submission round-trip
+    fn on_submitted(&self, signature: &Signature, provider: SwqosType, elapsed: Duration) {
+        let _ = (signature, provider, elapsed);
+    }
+
+    /// The transaction was confirmed `elapsed` after it was submitted
+    fn on_confirmed(&self, signature: &Signature, elapsed: Duration) {
+        let _ = (signature, elapsed);
+    }
+
+    /// Submission or confirmation failed `elapsed` after the send started
+    fn on_failed(&self, signature: &Signature, error: &SwqosError, elapsed: Duration) {
+        let _ = (signature, error, elapsed);
+    }
+}
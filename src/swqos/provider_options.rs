@@ -0,0 +1,345 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use solana_commitment_config::CommitmentLevel;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use tokio_util::sync::CancellationToken;
+
+use crate::common::rpc_provider::RpcProvider;
+use crate::swqos::common::{
+    poll_transaction_confirmation, validate_size, with_cancellation, AutoTip, PollBackoff,
+    SwqosError, DEFAULT_CONFIRMATION_COMMITMENT, DEFAULT_CONFIRMATION_POLL_BACKOFF,
+    DEFAULT_CONFIRMATION_TIMEOUT,
+};
+use crate::swqos::observer::TradeObserver;
+use crate::swqos::rate_limiter::RateLimiter;
+use crate::swqos::{SwqosClientTrait, SwqosType, TradeType};
+
+/// Settings shared by every SWQOS provider client and `SolRpcClient`, set through the `with_*`
+/// builders that `impl_provider_builders!` adds to the client.
+#[derive(Clone)]
+pub struct ProviderOptions {
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Tip inserted into untipped transactions, see `with_auto_tip`
+    pub auto_tip: Option<AutoTip>,
+    /// Commitment level confirmation waits for, `trade_type_commitments` overrides it per trade
+    /// type
+    pub confirmation_commitment: CommitmentLevel,
+    /// Give up waiting for confirmation after this long
+    pub confirmation_timeout: Duration,
+    /// Spacing of confirmation polls, exponential with jitter
+    pub confirmation_poll_backoff: PollBackoff,
+    pub trade_type_commitments: HashMap<TradeType, CommitmentLevel>,
+    /// Stops confirmation waits when it fires, see `with_cancellation_token`
+    pub cancel: Option<CancellationToken>,
+    /// Told about submissions, confirmations and failures, see `with_observer`
+    pub observer: Option<Arc<dyn TradeObserver>>,
+}
+
+impl Default for ProviderOptions {
+    fn default() -> Self {
+        Self {
+            rate_limiter: None,
+            auto_tip: None,
+            confirmation_commitment: DEFAULT_CONFIRMATION_COMMITMENT,
+            confirmation_timeout: DEFAULT_CONFIRMATION_TIMEOUT,
+            confirmation_poll_backoff: DEFAULT_CONFIRMATION_POLL_BACKOFF,
+            trade_type_commitments: HashMap::new(),
+            cancel: None,
+            observer: None,
+        }
+    }
+}
+
+impl ProviderOptions {
+    /// Checks a transaction goes through before `client` submits it: the tip (inserted with
+    /// `auto_tip` set), the size limit and the fee ceiling. Then waits for a rate limit permit.
+    pub async fn prepare<'a, C>(
+        &self,
+        client: &C,
+        transaction: &'a VersionedTransaction,
+    ) -> Result<Cow<'a, VersionedTransaction>, SwqosError>
+    where
+        C: SwqosClientTrait + Sync + ?Sized,
+    {
+        let transaction = client.ensure_or_insert_tip(transaction, self.auto_tip.as_ref())?;
+        validate_size(&transaction)?;
+        client.ensure_fee_ceiling(&transaction)?;
+        self.acquire().await;
+        Ok(transaction)
+    }
+
+    /// Wait for a rate limit permit, returns right away without `rate_limiter`
+    pub async fn acquire(&self) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+    }
+
+    /// Commitment a `trade_type` submission is confirmed at
+    pub fn confirmation_commitment(&self, trade_type: TradeType) -> CommitmentLevel {
+        self.trade_type_commitments
+            .get(&trade_type)
+            .copied()
+            .unwrap_or(self.confirmation_commitment)
+    }
+
+    /// Tell `observer` that `provider` accepted the transaction `elapsed` after the send started
+    pub fn on_submitted(&self, provider: SwqosType, signature: &Signature, elapsed: Duration) {
+        if let Some(observer) = &self.observer {
+            observer.on_submitted(signature, provider, elapsed);
+        }
+    }
+
+    /// Run a provider's whole send of `transaction`, telling `observer` if it fails
+    pub async fn observe_failures<T>(
+        &self,
+        transaction: &VersionedTransaction,
+        send: impl Future<Output = Result<T, SwqosError>>,
+    ) -> Result<T, SwqosError> {
+        let send_start = Instant::now();
+        let result = send.await;
+        if let (Some(observer), Err(error)) = (&self.observer, &result) {
+            // Errors raised after submission name the submitted, possibly auto-tipped, signature
+            let signature = match error {
+                SwqosError::ConfirmationTimeout { signature, .. }
+                | SwqosError::TransactionDropped { signature, .. }
+                | SwqosError::Cancelled { signature } => *signature,
+                _ => transaction.signatures.first().copied().unwrap_or_default(),
+            };
+            observer.on_failed(&signature, error, send_start.elapsed());
+        }
+        result
+    }
+
+    /// Poll `rpc` for the confirmation of a submitted transaction with these settings, see
+    /// `poll_transaction_confirmation`; fails with `SwqosError::Cancelled` once `cancel` fires.
+    /// A confirmation that was waited for is reported to `observer`.
+    pub async fn confirm(
+        &self,
+        rpc: &dyn RpcProvider,
+        trade_type: TradeType,
+        signature: Signature,
+        wait_confirmation: bool,
+    ) -> Result<Signature, SwqosError> {
+        let poll = poll_transaction_confirmation(
+            rpc,
+            signature,
+            wait_confirmation,
+            self.confirmation_commitment(trade_type),
+            self.confirmation_timeout,
+            self.confirmation_poll_backoff,
+        );
+        if !wait_confirmation {
+            return poll.await;
+        }
+        self.confirm_with(signature, poll).await
+    }
+
+    /// Await `wait`, a client's own wait for `signature`'s confirmation, like `confirm` does its
+    /// poll: fails with `SwqosError::Cancelled` once `cancel` fires and reports the confirmation to
+    /// `observer`
+    pub async fn confirm_with<T>(
+        &self,
+        signature: Signature,
+        wait: impl Future<Output = Result<T, SwqosError>>,
+    ) -> Result<T, SwqosError> {
+        let submitted_at = Instant::now();
+        let confirmed = with_cancellation(signature, self.cancel.as_ref(), wait).await?;
+        if let Some(observer) = &self.observer {
+            observer.on_confirmed(&signature, submitted_at.elapsed());
+        }
+        Ok(confirmed)
+    }
+}
+
+/// Adds the `ProviderOptions` builders to a provider client with an `options` field
+macro_rules! impl_provider_builders {
+    ($client:ty) => {
+        impl $client {
+            /// Limit submissions to `requests_per_second`, excess sends wait for a permit
+            pub fn with_rate_limit(mut self, requests_per_second: u32) -> Self {
+                let rate_limiter = $crate::swqos::rate_limiter::RateLimiter::new(requests_per_second);
+                self.options.rate_limiter = Some(std::sync::Arc::new(rate_limiter));
+                self
+            }
+
+            /// Instead of rejecting a transaction without a tip, append a `lamports` tip paid by
+            /// `payer` and re-sign it before submitting. The submitted transaction then has a
+            /// different signature than the one passed in, the returned signature is the
+            /// submitted one.
+            pub fn with_auto_tip(
+                mut self,
+                payer: std::sync::Arc<solana_sdk::signature::Keypair>,
+                lamports: u64,
+            ) -> Self {
+                self.options.auto_tip = Some($crate::swqos::common::AutoTip { payer, lamports });
+                self
+            }
+
+            /// Wait for `commitment` when confirming, unless `with_trade_type_commitment` overrides
+            /// it
+            pub fn with_confirmation_commitment(
+                mut self,
+                commitment: solana_commitment_config::CommitmentLevel,
+            ) -> Self {
+                self.options.confirmation_commitment = commitment;
+                self
+            }
+
+            /// Give up waiting for confirmation after `timeout`
+            pub fn with_confirmation_timeout(mut self, timeout: std::time::Duration) -> Self {
+                self.options.confirmation_timeout = timeout;
+                self
+            }
+
+            /// Space confirmation polls by `backoff`
+            pub fn with_confirmation_poll_backoff(
+                mut self,
+                backoff: $crate::swqos::common::PollBackoff,
+            ) -> Self {
+                self.options.confirmation_poll_backoff = backoff;
+                self
+            }
+
+            /// Confirm `trade_type` sends at `commitment`, e.g. `Processed` for snipes and
+            /// `Finalized` for sweeps
+            pub fn with_trade_type_commitment(
+                mut self,
+                trade_type: $crate::swqos::TradeType,
+                commitment: solana_commitment_config::CommitmentLevel,
+            ) -> Self {
+                self.options.trade_type_commitments.insert(trade_type, commitment);
+                self
+            }
+
+            /// Stop waiting for confirmations once `cancel` fires, e.g. on SIGTERM; the in-flight
+            /// transactions may still land
+            pub fn with_cancellation_token(
+                mut self,
+                cancel: tokio_util::sync::CancellationToken,
+            ) -> Self {
+                self.options.cancel = Some(cancel);
+                self
+            }
+
+            /// Report submissions, confirmations and failures to `observer`
+            pub fn with_observer(
+                mut self,
+                observer: std::sync::Arc<dyn $crate::swqos::observer::TradeObserver>,
+            ) -> Self {
+                self.options.observer = Some(observer);
+                self
+            }
+        }
+    };
+}
+
+pub(crate) use impl_provider_builders;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::rpc_provider::MockRpc;
+    use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+
+    #[tokio::test]
+    async fn test_confirm_uses_trade_type_commitment_and_timeout() {
+        let signature = Signature::new_unique();
+        let mock = MockRpc::new();
+        mock.push_signature_status(
+            signature,
+            Some(TransactionStatus {
+                slot: 1,
+                confirmations: Some(0),
+                status: Ok(()),
+                err: None,
+                confirmation_status: Some(TransactionConfirmationStatus::Processed),
+            }),
+        );
+
+        let mut options = ProviderOptions {
+            confirmation_timeout: Duration::from_millis(50),
+            ..ProviderOptions::default()
+        };
+        options.trade_type_commitments.insert(TradeType::Buy, CommitmentLevel::Processed);
+        let confirmed = options.confirm(&mock, TradeType::Buy, signature, true).await;
+        assert_eq!(confirmed.unwrap(), signature);
+        let result = options.confirm(&mock, TradeType::Sell, signature, true).await;
+        assert!(matches!(result, Err(SwqosError::ConfirmationTimeout { .. })));
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: parking_lot::Mutex<Vec<&'static str>>,
+    }
+
+    impl TradeObserver for RecordingObserver {
+        fn on_submitted(&self, _: &Signature, provider: SwqosType, _: Duration) {
+            assert_eq!(provider, SwqosType::Jito);
+            self.events.lock().push("submitted");
+        }
+
+        fn on_confirmed(&self, _: &Signature, _: Duration) {
+            self.events.lock().push("confirmed");
+        }
+
+        fn on_failed(&self, _: &Signature, _: &SwqosError, _: Duration) {
+            self.events.lock().push("failed");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer_sees_submission_confirmation_and_failure() {
+        use solana_sdk::message::{Message, VersionedMessage};
+        use solana_sdk::signer::{keypair::Keypair, Signer};
+
+        let payer = Keypair::new();
+        let message = VersionedMessage::Legacy(Message::new(&[], Some(&payer.pubkey())));
+        let transaction = VersionedTransaction::try_new(message, &[&payer]).unwrap();
+        let signature = transaction.signatures[0];
+        let mock = MockRpc::new();
+        mock.push_signature_status(
+            signature,
+            Some(TransactionStatus {
+                slot: 1,
+                confirmations: Some(1),
+                status: Ok(()),
+                err: None,
+                confirmation_status: Some(TransactionConfirmationStatus::Confirmed),
+            }),
+        );
+
+        let observer = Arc::new(RecordingObserver::default());
+        let options =
+            ProviderOptions { observer: Some(observer.clone()), ..ProviderOptions::default() };
+        let send = async {
+            options.on_submitted(SwqosType::Jito, &signature, Duration::ZERO);
+            options.confirm(&mock, TradeType::Buy, signature, true).await
+        };
+        options.observe_failures(&transaction, send).await.unwrap();
+        let message = "busy".to_string();
+        let rejected = SwqosError::ProviderRejected { provider: SwqosType::Jito, message };
+        let rejected = async { Err::<Signature, _>(rejected) };
+        assert!(options.observe_failures(&transaction, rejected).await.is_err());
+        assert_eq!(*observer.events.lock(), ["submitted", "confirmed", "failed"]);
+    }
+
+    #[tokio::test]
+    async fn test_confirm_stops_when_cancelled() {
+        let signature = Signature::new_unique();
+        let mock = MockRpc::new();
+        mock.push_signature_status(signature, None);
+
+        let cancel = CancellationToken::new();
+        let options =
+            ProviderOptions { cancel: Some(cancel.clone()), ..ProviderOptions::default() };
+        cancel.cancel();
+        let result = options.confirm(&mock, TradeType::Buy, signature, true).await;
+        assert!(matches!(result, Err(SwqosError::Cancelled { signature: s }) if s == signature));
+    }
+}
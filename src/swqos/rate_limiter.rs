@@ -0,0 +1,75 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Token bucket limiting how many requests a swqos client sends per second.
+///
+/// The bucket holds up to one second worth of permits, so short bursts up to the configured rate
+/// go out immediately and anything beyond that waits for the bucket to refill instead of failing.
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_second: u32,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: u32) -> Self {
+        let requests_per_second = requests_per_second.max(1);
+        Self {
+            requests_per_second,
+            state: Mutex::new(BucketState { tokens: requests_per_second as f64, last_refill: Instant::now() }),
+        }
+    }
+
+    pub fn requests_per_second(&self) -> u32 {
+        self.requests_per_second
+    }
+
+    /// Wait until a permit is available and take it
+    pub async fn acquire(&self) {
+        loop {
+            match self.try_acquire_at(Instant::now()) {
+                Ok(()) => return,
+                Err(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+
+    /// Take a permit if one is available, otherwise return how long until the next one is
+    fn try_acquire_at(&self, now: Instant) -> Result<(), Duration> {
+        let rate = self.requests_per_second as f64;
+        let mut state = self.state.lock();
+        let elapsed = now.saturating_duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * rate).min(rate);
+        state.last_refill = now;
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - state.tokens) / rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills_at_configured_rate() {
+        let limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        assert!(limiter.try_acquire_at(start).is_ok());
+        assert!(limiter.try_acquire_at(start).is_ok());
+        let wait = limiter.try_acquire_at(start).unwrap_err();
+        assert!(wait <= Duration::from_millis(500));
+        assert!(limiter.try_acquire_at(start + Duration::from_millis(500)).is_ok());
+        assert!(limiter.try_acquire_at(start + Duration::from_millis(500)).is_err());
+    }
+}
@@ -1,118 +1,1644 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use solana_client::rpc_config::RpcSendTransactionConfig;
-use solana_commitment_config::CommitmentLevel;
-use solana_sdk::message::VersionedMessage;
-use solana_sdk::transaction::VersionedTransaction;
-use solana_transaction_status::UiTransactionEncoding;
-use tracing::{error, info};
+use futures::future::{join_all, try_join_all};
+use futures::Stream;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig};
+use solana_commitment_config::{CommitmentConfig, CommitmentLevel};
+use solana_sdk::clock::Slot;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::{v0, AddressLookupTableAccount, VersionedMessage};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_system_interface::instruction::{advance_nonce_account, transfer};
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_sdk::message::compiled_instruction::CompiledInstruction;
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta, UiInstruction,
+    UiTransactionEncoding,
+};
+use tracing::{field, info_span, Instrument};
 
+use crate::swqos::provider_options::{impl_provider_builders, ProviderOptions};
 use crate::swqos::SwqosClientTrait;
 use crate::{
-    common::SolanaRpcClient,
-    swqos::{common::poll_transaction_confirmation, SwqosType, TradeType},
+    common::{address_lookup::fetch_address_lookup_table_account, rpc_provider::RpcProvider},
+    swqos::{
+        common::{
+            commitment_rank, confirm_many, confirm_stream,
+            is_already_processed, poll_transaction_landed_slot, poll_transaction_progress,
+            subscribe_transaction_landed_slot, validate_size, verify_landed_slot, ConfirmResult,
+            ConfirmationStrategy, RpcErrorKind, SendProgress, SwqosError,
+        },
+        SwqosType, TradeType,
+    },
 };
 use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// How `SolRpcClient` waits for a submitted transaction to be confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfirmationMethod {
+    /// Poll `getSignatureStatuses`, spaced by `ProviderOptions::confirmation_poll_backoff`
+    #[default]
+    Poll,
+    /// Await a `signatureSubscribe` notification on the RPC websocket, polling if the subscription fails
+    WebsocketSubscribe,
+}
+
+/// How much of a transaction is logged when its confirmation fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiagnosticLevel {
+    /// Only the signature and the error
+    None,
+    /// Signature and the program ID of every instruction
+    #[default]
+    Summary,
+    /// Every instruction with its resolved accounts and raw data bytes
+    Full,
+}
+
+/// Settings used to build the `RpcSendTransactionConfig` for each submission
+#[derive(Debug, Clone, Copy)]
+pub struct SendConfig {
+    /// Skip the preflight simulation on the RPC node
+    pub skip_preflight: bool,
+    /// How many times the RPC node should retry forwarding to the leader
+    pub max_retries: Option<usize>,
+    /// Commitment level used for the preflight simulation
+    pub preflight_commitment: Option<CommitmentLevel>,
+    /// Reject the submission if the RPC node has not reached this slot, see
+    /// `SolRpcClient::send_transaction_with_min_context_slot` to set it per send
+    pub min_context_slot: Option<u64>,
+    /// Polling or websocket subscription
+    pub confirmation_method: ConfirmationMethod,
+    /// Return on first confirmation, or re-check after extra slots to catch reorgs
+    pub confirmation_strategy: ConfirmationStrategy,
+    /// Submit batches one transaction at a time instead of concurrently,
+    /// for RPC endpoints that rate-limit bursts
+    pub sequential_batch: bool,
+    /// Check the recent blockhash with `isBlockhashValid` before submitting and fail with
+    /// `SwqosError::BlockhashExpired`; leave off for durable nonce transactions
+    pub check_blockhash: bool,
+    /// Transaction details logged on a confirmation failure
+    pub diagnostic_level: DiagnosticLevel,
+    /// Wire encoding of the `sendTransaction` payload, `Base64` or `Base58`
+    pub encoding: UiTransactionEncoding,
+    /// Submit every transaction this many times concurrently to the same endpoint; helps it land
+    /// under heavy congestion, but each copy counts against the endpoint's rate limit and is billed
+    /// by endpoints that charge per request. Only one confirmation is awaited. 0 is treated as 1
+    pub duplicate_submits: u8,
+    /// Simulate every transaction before submitting it and fail with
+    /// `SwqosError::SimulationRevert` if it would fail, at the cost of one extra round trip
+    pub pre_simulate: bool,
+}
+
+impl Default for SendConfig {
+    fn default() -> Self {
+        Self {
+            skip_preflight: true,
+            max_retries: Some(3),
+            preflight_commitment: Some(CommitmentLevel::Processed),
+            min_context_slot: Some(0),
+            confirmation_method: ConfirmationMethod::Poll,
+            confirmation_strategy: ConfirmationStrategy::FirstSeen,
+            sequential_batch: false,
+            check_blockhash: false,
+            diagnostic_level: DiagnosticLevel::Summary,
+            encoding: UiTransactionEncoding::Base64,
+            duplicate_submits: 1,
+            pre_simulate: false,
+        }
+    }
+}
+
+/// Outcome of `SolRpcClient::simulate`
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    /// Compute units the transaction consumed, if reported by the node
+    pub units_consumed: Option<u64>,
+    /// Program logs emitted during the simulation
+    pub logs: Vec<String>,
+    /// Error the transaction would fail with, `None` if it would succeed
+    pub err: Option<TransactionError>,
+}
+
+/// Fee-bump resubmission settings for `SolRpcClient::send_transaction_with_retry`
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total number of submissions, including the first one; rebroadcasts of a dropped
+    /// transaction until its blockhash expires are not counted
+    pub max_attempts: u32,
+    /// Factor applied to the compute unit price on every new attempt (geometric, e.g. 1.5)
+    pub fee_multiplier: f64,
+    /// Pause before resubmitting after a transient RPC rejection
+    pub transient_retry_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, fee_multiplier: 1.5, transient_retry_delay: Duration::from_millis(200) }
+    }
+}
+
+/// The submission that landed through `SolRpcClient::send_transaction_with_retry`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryOutcome {
+    pub signature: Signature,
+    /// 1-based attempt number that was confirmed
+    pub attempt: u32,
+    /// Compute unit price (micro-lamports) of the confirmed attempt
+    pub unit_price: u64,
+}
 
 #[derive(Clone)]
 pub struct SolRpcClient {
-    pub rpc_client: Arc<SolanaRpcClient>,
+    /// `SolanaRpcClient` in production, `MockRpc` in tests
+    pub rpc_client: Arc<dyn RpcProvider>,
+    pub send_config: SendConfig,
+    /// Websocket endpoint for `ConfirmationMethod::WebsocketSubscribe`, derived from the RPC url if unset
+    pub ws_url: Option<String>,
+    /// Rate limit, confirmation and observer settings, see `ProviderOptions`; `auto_tip` is
+    /// ignored, plain RPC submissions carry no tip
+    pub options: ProviderOptions,
 }
 
-fn print_versioned_transaction_instructions(tx: &VersionedTransaction) {
-    match &tx.message {
-        VersionedMessage::V0(message) => {
-            log::error!("Transaction Version: V0");
-            for (i, instruction) in message.instructions.iter().enumerate() {
-                // Get the program_id from the account keys using the program_id_index
-                let program_id = &message.account_keys[instruction.program_id_index as usize];
-                
-                log::error!("Instruction {}:", i);
-                log::error!("  Program ID: {}", program_id);
-                log::error!("  Account Indices: {:?}", instruction.accounts);
-                log::error!("  Data (bytes): {:?}", instruction.data); 
+impl_provider_builders!(SolRpcClient);
+
+/// One instruction of a transaction with its account indices resolved to pubkeys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+/// Resolve every compiled instruction against the static account keys only.
+/// Fails if an instruction references an address lookup table entry, see `decode_instructions_with_lookups`.
+pub fn decode_instructions(tx: &VersionedTransaction) -> Result<Vec<DecodedInstruction>> {
+    decode_loaded_instructions(tx, &[])
+}
+
+/// Resolve every compiled instruction, fetching the address lookup tables referenced by a V0 message
+pub async fn decode_instructions_with_lookups(
+    rpc: &dyn RpcProvider,
+    tx: &VersionedTransaction,
+) -> Result<Vec<DecodedInstruction>> {
+    let mut lookup_tables = Vec::new();
+    for lookup in tx.message.address_table_lookups().unwrap_or_default() {
+        lookup_tables.push(fetch_address_lookup_table_account(rpc, &lookup.account_key).await?);
+    }
+    decode_loaded_instructions(tx, &lookup_tables)
+}
+
+/// Resolve every compiled instruction using already fetched lookup tables.
+///
+/// Loaded addresses follow the runtime order: static keys, then the writable entries of every
+/// lookup, then the readonly entries of every lookup.
+pub fn decode_loaded_instructions(
+    tx: &VersionedTransaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Vec<DecodedInstruction>> {
+    let account_keys = loaded_account_keys(tx, lookup_tables)?;
+    tx.message
+        .instructions()
+        .iter()
+        .map(|instruction| decode_compiled(&account_keys, instruction))
+        .collect()
+}
+
+fn loaded_account_keys(
+    tx: &VersionedTransaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Vec<Pubkey>> {
+    let mut account_keys = tx.message.static_account_keys().to_vec();
+    if let Some(lookups) = tx.message.address_table_lookups() {
+        let table = |key: &Pubkey| {
+            lookup_tables
+                .iter()
+                .find(|table| table.key == *key)
+                .ok_or_else(|| anyhow::anyhow!("Address lookup table {} not provided", key))
+        };
+        let entry = |table: &AddressLookupTableAccount, index: u8| {
+            table.addresses.get(index as usize).copied().ok_or_else(|| {
+                anyhow::anyhow!("Index {} out of range for address lookup table {}", index, table.key)
+            })
+        };
+        for lookup in lookups {
+            let table = table(&lookup.account_key)?;
+            for index in &lookup.writable_indexes {
+                account_keys.push(entry(table, *index)?);
             }
-        },
-        VersionedMessage::Legacy(message) => {
-            log::error!("Transaction Version: Legacy");
-            for (i, instruction) in message.instructions.iter().enumerate() {
-                // In legacy messages, the program_id is directly available
-                let program_id = &message.account_keys[instruction.program_id_index as usize];
+        }
+        for lookup in lookups {
+            let table = table(&lookup.account_key)?;
+            for index in &lookup.readonly_indexes {
+                account_keys.push(entry(table, *index)?);
+            }
+        }
+    }
+    Ok(account_keys)
+}
+
+fn decode_compiled(
+    account_keys: &[Pubkey],
+    instruction: &CompiledInstruction,
+) -> Result<DecodedInstruction> {
+    let resolve = |index: u8| {
+        account_keys.get(index as usize).copied().ok_or_else(|| {
+            anyhow::anyhow!("Account index {} out of range ({} loaded keys)", index, account_keys.len())
+        })
+    };
+    Ok(DecodedInstruction {
+        program_id: resolve(instruction.program_id_index)?,
+        accounts: instruction.accounts.iter().map(|index| resolve(*index)).collect::<Result<_>>()?,
+        data: instruction.data.clone(),
+    })
+}
+
+/// Attempts `fetch_decoded_transaction` makes before giving up on a transaction the RPC node has
+/// not indexed yet
+const FETCH_TRANSACTION_ATTEMPTS: usize = 5;
+const FETCH_TRANSACTION_RETRY_DELAY: Duration = Duration::from_millis(500);
 
+/// A confirmed transaction with its top-level and inner (CPI) instructions decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedTx {
+    pub signature: Signature,
+    pub slot: u64,
+    pub instructions: Vec<DecodedInstruction>,
+    /// `inner_instructions[i]` are the instructions invoked by `instructions[i]`, in execution
+    /// order, e.g. the token transfers that carry a swap's actual fill
+    pub inner_instructions: Vec<Vec<DecodedInstruction>>,
+}
+
+/// Fetch a confirmed transaction and decode all of its instructions, inner ones included.
+///
+/// Lookup table entries are resolved from the addresses the transaction actually loaded, so
+/// tables closed or extended since are not a problem. A transaction the node has not indexed yet
+/// is fetched again up to `FETCH_TRANSACTION_ATTEMPTS` times.
+pub async fn fetch_decoded_transaction(
+    rpc: &dyn RpcProvider,
+    signature: &Signature,
+) -> Result<DecodedTx> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        max_supported_transaction_version: Some(0),
+        commitment: Some(CommitmentConfig::confirmed()),
+    };
+    let mut attempt = 1;
+    let transaction = loop {
+        // A transaction that is not available yet comes back as a null result, which the client
+        // reports as a deserialization error, so every error is retried
+        match rpc.get_transaction_with_config(signature, config).await {
+            Ok(transaction) => break transaction,
+            Err(e) if attempt >= FETCH_TRANSACTION_ATTEMPTS => {
+                return Err(anyhow::anyhow!("Failed to fetch transaction {}: {}", signature, e));
+            }
+            Err(_) => {
+                attempt += 1;
+                tokio::time::sleep(FETCH_TRANSACTION_RETRY_DELAY).await;
+            }
+        }
+    };
+    decode_confirmed_transaction(*signature, &transaction)
+}
+
+fn decode_confirmed_transaction(
+    signature: Signature,
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<DecodedTx> {
+    let tx = transaction
+        .transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| anyhow::anyhow!("Transaction {} could not be decoded", signature))?;
+    let meta = transaction
+        .transaction
+        .meta
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Transaction {} has no status metadata", signature))?;
+
+    let mut account_keys = tx.message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        for address in loaded.writable.iter().chain(&loaded.readonly) {
+            account_keys.push(address.parse()?);
+        }
+    }
+    let instructions = tx
+        .message
+        .instructions()
+        .iter()
+        .map(|instruction| decode_compiled(&account_keys, instruction))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut inner_instructions = vec![Vec::new(); instructions.len()];
+    if let OptionSerializer::Some(inner) = &meta.inner_instructions {
+        for group in inner {
+            let decoded = inner_instructions.get_mut(group.index as usize).ok_or_else(|| {
+                anyhow::anyhow!("Inner instructions of missing instruction {}", group.index)
+            })?;
+            for instruction in &group.instructions {
+                let UiInstruction::Compiled(compiled) = instruction else {
+                    return Err(anyhow::anyhow!("Inner instruction is not compiled"));
+                };
+                let data = bs58::decode(&compiled.data).into_vec()?;
+                let compiled = CompiledInstruction {
+                    program_id_index: compiled.program_id_index,
+                    accounts: compiled.accounts.clone(),
+                    data,
+                };
+                decoded.push(decode_compiled(&account_keys, &compiled)?);
+            }
+        }
+    }
+
+    Ok(DecodedTx { signature, slot: transaction.slot, instructions, inner_instructions })
+}
+
+pub(crate) fn print_versioned_transaction_instructions(tx: &VersionedTransaction, level: DiagnosticLevel) {
+    match level {
+        DiagnosticLevel::None => {}
+        DiagnosticLevel::Summary => log_instruction_programs(tx),
+        DiagnosticLevel::Full => {
+            let version = match &tx.message {
+                VersionedMessage::V0(_) => "V0",
+                VersionedMessage::Legacy(_) => "Legacy",
+            };
+            log::error!("Transaction Version: {}", version);
+            log_decoded_instructions(decode_instructions(tx));
+        }
+    }
+}
+
+/// Program IDs of the instructions in order; programs are never loaded from lookup tables,
+/// so the static keys are enough
+fn instruction_programs(tx: &VersionedTransaction) -> Vec<Pubkey> {
+    let account_keys = tx.message.static_account_keys();
+    tx.message
+        .instructions()
+        .iter()
+        .filter_map(|instruction| account_keys.get(instruction.program_id_index as usize).copied())
+        .collect()
+}
+
+fn log_instruction_programs(tx: &VersionedTransaction) {
+    let signature = tx.signatures.first().copied().unwrap_or_default();
+    log::error!(" signature: {:?}, programs: {:?}", signature, instruction_programs(tx));
+}
+
+fn log_decoded_instructions(decoded: Result<Vec<DecodedInstruction>>) {
+    match decoded {
+        Ok(instructions) => {
+            for (i, instruction) in instructions.iter().enumerate() {
                 log::error!("Instruction {}:", i);
-                log::error!("  Program ID: {}", program_id);
-                log::error!("  Account Indices: {:?}", instruction.accounts);
+                log::error!("  Program ID: {}", instruction.program_id);
+                log::error!("  Accounts: {:?}", instruction.accounts);
                 log::error!("  Data (bytes): {:?}", instruction.data);
             }
-        },
+        }
+        Err(e) => log::error!("Failed to decode instructions: {}", e),
     }
 }
 
 #[async_trait::async_trait]
 impl SwqosClientTrait for SolRpcClient {
+    /// Plain RPC submission takes no tip
+    fn min_tip_lamports(&self) -> u64 {
+        0
+    }
+
+    fn ensure_tip(&self, _transaction: &VersionedTransaction) -> Result<(), SwqosError> {
+        Ok(())
+    }
+
     async fn send_transaction(
         &self,
         trade_type: TradeType,
         transaction: &VersionedTransaction,
         wait_confirmation: bool,
-    ) -> Result<()> {
-        let signature = self
-            .rpc_client
-            .send_transaction_with_config(
-                transaction,
-                RpcSendTransactionConfig {
-                    skip_preflight: true,
-                    preflight_commitment: Some(CommitmentLevel::Processed),
-                    encoding: Some(UiTransactionEncoding::Base64),
-                    max_retries: Some(3),
-                    min_context_slot: Some(0),
-                },
+    ) -> Result<Signature, SwqosError> {
+        self.send_transaction_with_min_context_slot(
+            trade_type,
+            transaction,
+            wait_confirmation,
+            self.send_config.min_context_slot,
+        )
+        .await
+    }
+
+    async fn send_transactions(
+        &self,
+        trade_type: TradeType,
+        transactions: &Vec<VersionedTransaction>,
+        wait_confirmation: bool,
+    ) -> Result<Vec<Signature>, SwqosError> {
+        if self.send_config.sequential_batch {
+            let mut signatures = Vec::with_capacity(transactions.len());
+            for transaction in transactions {
+                signatures.push(self.send_transaction(trade_type, transaction, wait_confirmation).await?);
+            }
+            return Ok(signatures);
+        }
+
+        // try_join_all keeps the input order and fails fast on the first error
+        try_join_all(
+            transactions
+                .iter()
+                .map(|transaction| self.send_transaction(trade_type, transaction, wait_confirmation)),
+        )
+        .await
+    }
+
+    /// Plain RPC submissions carry no tip
+    fn get_tip_account(&self) -> Result<String> {
+        Err(anyhow::anyhow!("SolRpcClient has no tip accounts"))
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn get_swqos_type(&self) -> SwqosType {
+        SwqosType::Default
+    }
+
+    fn provider_options(&self) -> Option<&ProviderOptions> {
+        Some(&self.options)
+    }
+
+    async fn health_check(&self) -> Result<Duration> {
+        let start = Instant::now();
+        self.rpc_client.get_health().await?;
+        Ok(start.elapsed())
+    }
+}
+
+impl SolRpcClient {
+    pub fn new(rpc_client: Arc<dyn RpcProvider>) -> Self {
+        Self {
+            rpc_client,
+            send_config: SendConfig::default(),
+            ws_url: None,
+            options: ProviderOptions::default(),
+        }
+    }
+
+    /// Websocket endpoint used for `ConfirmationMethod::WebsocketSubscribe`
+    pub fn with_websocket_url(mut self, ws_url: String) -> Self {
+        self.ws_url = Some(ws_url);
+        self
+    }
+
+    fn websocket_url(&self) -> String {
+        if let Some(ws_url) = &self.ws_url {
+            return ws_url.clone();
+        }
+        let rpc_url = self.rpc_client.url();
+        if let Some(rest) = rpc_url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            rpc_url
+        }
+    }
+
+    /// Confirm a burst of signatures with batched `getSignatureStatuses` polls, see `common::confirm_many`
+    pub async fn confirm_many(&self, signatures: &[Signature], timeout: Duration) -> Vec<Result<(), SwqosError>> {
+        confirm_many(
+            &self.rpc_client,
+            signatures,
+            self.options.confirmation_commitment,
+            timeout,
+            self.options.confirmation_poll_backoff,
+        )
+        .await
+    }
+
+    /// Confirm a basket of signatures over one websocket connection, yielding each as it confirms
+    /// or times out, see `common::confirm_stream`
+    pub fn confirm_stream(&self, signatures: Vec<Signature>) -> impl Stream<Item = (Signature, ConfirmResult)> {
+        confirm_stream(
+            &self.websocket_url(),
+            signatures,
+            CommitmentConfig { commitment: self.options.confirmation_commitment },
+            self.options.confirmation_timeout,
+        )
+    }
+
+    /// Like `send_transactions`, but returns one outcome per input transaction so a failure does
+    /// not hide which of the others landed. Submits concurrently unless `sequential_batch` is set.
+    pub async fn send_transactions_partial(
+        &self,
+        trade_type: TradeType,
+        transactions: &[VersionedTransaction],
+        wait_confirmation: bool,
+    ) -> Vec<Result<Signature, SwqosError>> {
+        if self.send_config.sequential_batch {
+            let mut outcomes = Vec::with_capacity(transactions.len());
+            for transaction in transactions {
+                outcomes.push(self.send_transaction(trade_type, transaction, wait_confirmation).await);
+            }
+            return outcomes;
+        }
+
+        join_all(
+            transactions
+                .iter()
+                .map(|transaction| self.send_transaction(trade_type, transaction, wait_confirmation)),
+        )
+        .await
+    }
+
+    /// Wait for confirmation using the configured `ConfirmationMethod` through
+    /// `ProviderOptions::confirm_with`, so it stops once cancelled and is reported to the observer.
+    ///
+    /// Returns the slot the transaction landed in, `None` without `wait_confirmation`.
+    async fn confirm_transaction(
+        &self,
+        signature: Signature,
+        commitment: CommitmentLevel,
+        wait_confirmation: bool,
+    ) -> Result<Option<Slot>, SwqosError> {
+        if !wait_confirmation {
+            return Ok(None);
+        }
+        let wait = self.wait_for_confirmation(signature, commitment);
+        self.options.confirm_with(signature, wait).await.map(Some)
+    }
+
+    async fn wait_for_confirmation(
+        &self,
+        signature: Signature,
+        commitment: CommitmentLevel,
+    ) -> Result<Slot, SwqosError> {
+        let landed_slot = self.wait_for_commitment(signature, commitment).await?;
+        match self.send_config.confirmation_strategy {
+            ConfirmationStrategy::ReorgSafe { extra_slots } => {
+                verify_landed_slot(
+                    &self.rpc_client,
+                    signature,
+                    extra_slots,
+                    self.options.confirmation_timeout,
+                    self.options.confirmation_poll_backoff,
+                )
+                .await
+            }
+            ConfirmationStrategy::FirstSeen => Ok(landed_slot),
+        }
+    }
+
+    async fn wait_for_commitment(
+        &self,
+        signature: Signature,
+        commitment: CommitmentLevel,
+    ) -> Result<Slot, SwqosError> {
+        if self.send_config.confirmation_method == ConfirmationMethod::WebsocketSubscribe {
+            match subscribe_transaction_landed_slot(
+                &self.websocket_url(),
+                signature,
+                CommitmentConfig { commitment },
+                self.options.confirmation_timeout,
             )
-            .await?;
+            .await
+            {
+                Err(SwqosError::RpcSend(e)) => {
+                    log::warn!(" [rpc] signature subscription failed, falling back to polling: {}", e);
+                }
+                result => return result,
+            }
+        }
+
+        poll_transaction_landed_slot(
+            &self.rpc_client,
+            signature,
+            commitment,
+            self.options.confirmation_timeout,
+            self.options.confirmation_poll_backoff,
+        )
+        .await
+    }
+
+    /// Override the send settings, e.g. `SendConfig { max_retries: Some(0), ..Default::default() }`
+    pub fn with_send_config(mut self, send_config: SendConfig) -> Self {
+        self.send_config = send_config;
+        self
+    }
+
+    /// Transaction details logged when a confirmation fails, `DiagnosticLevel::Full` dumps the
+    /// raw instruction data
+    pub fn with_diagnostic_level(mut self, diagnostic_level: DiagnosticLevel) -> Self {
+        self.send_config.diagnostic_level = diagnostic_level;
+        self
+    }
+
+    /// Encode submissions as `encoding`, for providers that only accept (or are faster with)
+    /// `Base58`
+    pub fn with_encoding(mut self, encoding: UiTransactionEncoding) -> Self {
+        self.send_config.encoding = encoding;
+        self
+    }
+
+    /// Submit each transaction `duplicate_submits` times, see `SendConfig::duplicate_submits`
+    pub fn with_duplicate_submits(mut self, duplicate_submits: u8) -> Self {
+        self.send_config.duplicate_submits = duplicate_submits;
+        self
+    }
+
+    /// Simulate before every submission, see `SendConfig::pre_simulate`
+    pub fn with_pre_simulate(mut self, pre_simulate: bool) -> Self {
+        self.send_config.pre_simulate = pre_simulate;
+        self
+    }
+
+    /// Submit `send_transactions` batches one at a time instead of concurrently
+    pub fn with_sequential_batch(mut self, sequential_batch: bool) -> Self {
+        self.send_config.sequential_batch = sequential_batch;
+        self
+    }
+
+    /// Like `send_transaction`, but the RPC node rejects the submission if it has not yet processed
+    /// `min_context_slot`, e.g. the slot a pool creation was observed at, so a lagging node cannot
+    /// accept the transaction against a stale view.
+    ///
+    /// Runs in a `swqos.send` span recording the signature once known and the total elapsed
+    /// milliseconds; the confirmation wait gets a child `swqos.confirm` span.
+    pub async fn send_transaction_with_min_context_slot(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        wait_confirmation: bool,
+        min_context_slot: Option<u64>,
+    ) -> Result<Signature, SwqosError> {
+        self.send_instrumented(trade_type, transaction, wait_confirmation, min_context_slot)
+            .await
+            .map(|(signature, _)| signature)
+    }
+
+    /// Send and wait for confirmation like `send_transaction(.., true)`, also returning the slot
+    /// the transaction landed in, e.g. to know when state read at that slot reflects the trade.
+    ///
+    /// With `ConfirmationStrategy::ReorgSafe` the slot is the one observed after the extra slots
+    /// passed, so a transaction re-included on another fork reports where it ended up.
+    pub async fn send_transaction_with_slot(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+    ) -> Result<(Signature, Slot), SwqosError> {
+        let min_context_slot = self.send_config.min_context_slot;
+        let (signature, slot) =
+            self.send_instrumented(trade_type, transaction, true, min_context_slot).await?;
+        // Always set once confirmation was awaited
+        Ok((signature, slot.unwrap_or_default()))
+    }
+
+    /// Send like `send_transaction(.., true)`, yielding each stage as it happens: `Submitted`, a
+    /// `Seen` for every commitment level passed below the target, then `Confirmed` or `Failed`.
+    ///
+    /// Confirmation always polls, whatever the `ConfirmationMethod`, so the intermediate levels
+    /// can be observed. Dropping the stream stops the polling.
+    pub fn send_with_progress(
+        &self,
+        trade_type: TradeType,
+        transaction: VersionedTransaction,
+    ) -> impl Stream<Item = SendProgress> {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let client = self.clone();
+        tokio::spawn(async move {
+            let min_context_slot = client.send_config.min_context_slot;
+            let sent = client
+                .send_transaction_with_min_context_slot(trade_type, &transaction, false, min_context_slot)
+                .await;
+            let signature = match sent {
+                Ok(signature) => signature,
+                Err(error) => {
+                    let _ = sender.send(SendProgress::Failed { error, at: Instant::now() });
+                    return;
+                }
+            };
+            let submitted_at = Instant::now();
+            let _ = sender.send(SendProgress::Submitted { signature, at: submitted_at });
+
+            let commitment = client.options.confirmation_commitment(trade_type);
+            let progress = sender.clone();
+            let on_seen = move |level, slot| {
+                if commitment_rank(level) < commitment_rank(commitment) {
+                    let seen = SendProgress::Seen { commitment: level, slot, at: Instant::now() };
+                    let _ = progress.send(seen);
+                }
+            };
+            let poll = poll_transaction_progress(
+                &client.rpc_client,
+                signature,
+                commitment,
+                client.options.confirmation_timeout,
+                client.options.confirmation_poll_backoff,
+                on_seen,
+            );
+            let confirmed = tokio::select! {
+                confirmed = client.options.confirm_with(signature, poll) => confirmed,
+                _ = sender.closed() => return,
+            };
+            let event = match confirmed {
+                Ok(slot) => SendProgress::Confirmed { signature, slot, at: Instant::now() },
+                Err(error) => {
+                    if let Some(observer) = &client.options.observer {
+                        observer.on_failed(&signature, &error, submitted_at.elapsed());
+                    }
+                    SendProgress::Failed { error, at: Instant::now() }
+                }
+            };
+            let _ = sender.send(event);
+        });
+        futures::stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|event| (event, receiver))
+        })
+    }
+
+    async fn send_instrumented(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        wait_confirmation: bool,
+        min_context_slot: Option<u64>,
+    ) -> Result<(Signature, Option<Slot>), SwqosError> {
+        let span = info_span!(
+            "swqos.send",
+            provider = ?self.get_swqos_type(),
+            trade_type = ?trade_type,
+            signature = field::Empty,
+            elapsed_ms = field::Empty,
+        );
+        let start = Instant::now();
+        let send = self.send_and_confirm(trade_type, transaction, wait_confirmation, min_context_slot);
+        let result =
+            self.options.observe_failures(transaction, send).instrument(span.clone()).await;
+        span.record("elapsed_ms", start.elapsed().as_millis() as u64);
+        result
+    }
+
+    async fn send_and_confirm(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        wait_confirmation: bool,
+        min_context_slot: Option<u64>,
+    ) -> Result<(Signature, Option<Slot>), SwqosError> {
+        validate_size(transaction)?;
+        self.ensure_fee_ceiling(transaction)?;
+        if self.send_config.pre_simulate {
+            let simulation =
+                self.simulate(transaction).await.map_err(|e| SwqosError::RpcSend(e.to_string()))?;
+            if let Some(err) = simulation.err {
+                log::warn!(" [rpc] {} simulation failed, not submitted: {}", trade_type, err);
+                return Err(SwqosError::SimulationRevert { err, logs: simulation.logs });
+            }
+        }
+        let copies = self.send_config.duplicate_submits.max(1);
+        for _ in 0..copies {
+            self.options.acquire().await;
+        }
+        if self.send_config.check_blockhash {
+            self.ensure_blockhash_valid(transaction).await?;
+        }
+        let send_start = Instant::now();
+        let config = RpcSendTransactionConfig {
+            skip_preflight: self.send_config.skip_preflight,
+            preflight_commitment: self.send_config.preflight_commitment,
+            encoding: Some(self.send_config.encoding),
+            max_retries: self.send_config.max_retries,
+            min_context_slot,
+        };
+        // Every copy carries the same signature, one accepted copy is enough
+        let sends =
+            (0..copies).map(|_| self.rpc_client.send_transaction_with_config(transaction, config));
+        let mut results: Vec<_> = join_all(sends)
+            .await
+            .into_iter()
+            .map(|sent| match sent {
+                // A resubmission of a transaction that already landed, confirm the original
+                Err(e) if is_already_processed(&e) => {
+                    let signature = transaction.signatures.first().copied().unwrap_or_default();
+                    log::info!(" [rpc] {} already processed: {:?}", trade_type, signature);
+                    Ok(signature)
+                }
+                sent => sent,
+            })
+            .collect();
+        let accepted = results.iter().position(|result| result.is_ok()).unwrap_or(0);
+        let signature = match results.swap_remove(accepted) {
+            Ok(signature) => signature,
+            Err(e) => return Err(SwqosError::from(e)),
+        };
+        tracing::Span::current().record("signature", field::display(signature));
+        self.options.on_submitted(SwqosType::Default, &signature, send_start.elapsed());
 
         let start_time = Instant::now();
-        match poll_transaction_confirmation(&self.rpc_client, signature, wait_confirmation).await {
-            Ok(_) => (),
+        let confirm_span =
+            info_span!("swqos.confirm", signature = %signature, elapsed_ms = field::Empty);
+        let commitment = self.options.confirmation_commitment(trade_type);
+        let confirmed = self
+            .confirm_transaction(signature, commitment, wait_confirmation)
+            .instrument(confirm_span.clone())
+            .await;
+        confirm_span.record("elapsed_ms", start_time.elapsed().as_millis() as u64);
+        let landed_slot = match confirmed {
+            Ok(landed_slot) => landed_slot,
             Err(e) => {
-                log::error!(" signature: {:?}", signature);
                 log::error!(" [rpc] {} confirmation failed: {:?}", trade_type, start_time.elapsed());
-                // log::error!("{}", transaction);
-                print_versioned_transaction_instructions(transaction);
+                match self.send_config.diagnostic_level {
+                    DiagnosticLevel::None => log::error!(" signature: {:?}", signature),
+                    DiagnosticLevel::Summary => log_instruction_programs(transaction),
+                    DiagnosticLevel::Full => {
+                        log::error!(" signature: {:?}", signature);
+                        log_decoded_instructions(
+                            decode_instructions_with_lookups(&self.rpc_client, transaction).await,
+                        );
+                    }
+                }
                 log::error!("RPC transaction error: {}", e);
                 return Err(e);
             }
-        }
+        };
         if wait_confirmation {
             log::info!(" signature: {:?}", signature);
             log::info!(" [rpc] {} confirmed: {:?}", trade_type, start_time.elapsed());
         }
 
+        Ok((signature, landed_slot))
+    }
+
+    /// Slot the RPC node has reached at the preflight commitment
+    pub async fn current_slot(&self) -> Result<u64> {
+        let commitment = self.send_config.preflight_commitment.unwrap_or(CommitmentLevel::Processed);
+        Ok(self.rpc_client.get_slot_with_commitment(CommitmentConfig { commitment }).await?)
+    }
+
+    /// Fail with `SwqosError::BlockhashExpired` if the transaction's recent blockhash can no longer land
+    async fn ensure_blockhash_valid(&self, transaction: &VersionedTransaction) -> Result<(), SwqosError> {
+        let blockhash = *transaction.message.recent_blockhash();
+        let commitment = self.send_config.preflight_commitment.unwrap_or(CommitmentLevel::Processed);
+        if !self.rpc_client.is_blockhash_valid(&blockhash, CommitmentConfig { commitment }).await? {
+            return Err(SwqosError::BlockhashExpired { blockhash });
+        }
         Ok(())
     }
 
-    async fn send_transactions(
+    /// Whether `message`'s recent blockhash can still land, asked with `isBlockhashValid`.
+    ///
+    /// This is the one expiry check of the retry path: it works from the signed message alone,
+    /// without the `last_valid_block_height` returned when the blockhash was fetched. A failed
+    /// lookup counts as valid, so the signed transaction is kept rather than risk a second copy
+    /// landing.
+    async fn blockhash_still_valid(&self, message: &VersionedMessage) -> bool {
+        let commitment = self.send_config.preflight_commitment.unwrap_or(CommitmentLevel::Processed);
+        self.rpc_client
+            .is_blockhash_valid(message.recent_blockhash(), CommitmentConfig { commitment })
+            .await
+            .unwrap_or(true)
+    }
+
+    /// Resubmit the dropped `transaction` unchanged until its recent blockhash expires; identical
+    /// bytes carry the same signature, so the copies can land at most once.
+    ///
+    /// Returns the signature once it lands, or `None` when the blockhash expired and a last wait
+    /// saw nothing, i.e. it can be re-signed without a second copy landing next to it.
+    async fn rebroadcast_until_expired(
         &self,
         trade_type: TradeType,
-        transactions: &Vec<VersionedTransaction>,
+        transaction: &VersionedTransaction,
+    ) -> Result<Option<Signature>, SwqosError> {
+        let signature = transaction.signatures.first().copied().unwrap_or_default();
+        loop {
+            let expired = !self.blockhash_still_valid(&transaction.message).await;
+            let result = if expired {
+                // Can no longer land, waiting once more tells whether an earlier copy did
+                let commitment = self.options.confirmation_commitment(trade_type);
+                self.confirm_transaction(signature, commitment, true).await.map(|_| signature)
+            } else {
+                log::warn!(" [rpc] {} dropped, rebroadcasting {}", trade_type, signature);
+                self.send_transaction(trade_type, transaction, true).await
+            };
+            match result {
+                Ok(signature) => return Ok(Some(signature)),
+                Err(SwqosError::TransactionDropped { .. }) if expired => return Ok(None),
+                Err(SwqosError::TransactionDropped { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Rebuild the transaction with the latest blockhash and sign it again with `signers`
+    pub async fn resign_with_fresh_blockhash(
+        &self,
+        transaction: &VersionedTransaction,
+        signers: &[&Keypair],
+    ) -> Result<VersionedTransaction> {
+        let blockhash = self.rpc_client.get_latest_blockhash().await?;
+        resign_with_blockhash(transaction, blockhash, signers)
+    }
+
+    /// Submit a transaction built and signed elsewhere, given as base64 of its wire format, through
+    /// `send_transaction`, so it gets the same retries and confirmation handling.
+    ///
+    /// `trade_type` picks the confirmation commitment like for any other send, see
+    /// `with_trade_type_commitment`, and labels the logs.
+    ///
+    /// Fails before anything is submitted if the string does not decode to a transaction or a
+    /// signature is missing or does not verify.
+    pub async fn send_raw(
+        &self,
+        trade_type: TradeType,
+        base64_tx: &str,
         wait_confirmation: bool,
-    ) -> Result<()> {
-        for transaction in transactions {
-            self.send_transaction(trade_type, transaction, wait_confirmation).await?;
+    ) -> Result<Signature> {
+        let transaction = decode_raw_transaction(base64_tx)?;
+        Ok(self.send_transaction(trade_type, &transaction, wait_confirmation).await?)
+    }
+
+    /// Simulate the transaction against the current bank without submitting it
+    pub async fn simulate(&self, tx: &VersionedTransaction) -> Result<SimulationResult> {
+        let result = self
+            .rpc_client
+            .simulate_transaction_with_config(
+                tx,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: false,
+                    commitment: self
+                        .send_config
+                        .preflight_commitment
+                        .map(|commitment| CommitmentConfig { commitment }),
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    accounts: None,
+                    min_context_slot: None,
+                    inner_instructions: false,
+                },
+            )
+            .await?
+            .value;
+
+        Ok(SimulationResult {
+            units_consumed: result.units_consumed,
+            logs: result.logs.unwrap_or_default(),
+            err: result.err.map(Into::into),
+        })
+    }
+
+    /// Send and wait for confirmation; if the transaction is dropped, raise its
+    /// `SetComputeUnitPrice` by `policy.fee_multiplier`, re-sign with `signers` and resubmit.
+    ///
+    /// Only `SwqosError::TransactionDropped` and transient `SwqosError::RpcRejected` errors trigger
+    /// a retry: any other error may mean the previous attempt can still land or cannot be fixed by
+    /// resubmitting, so it is returned as is. A transient rejection resubmits the same bytes.
+    ///
+    /// A re-signed copy has a new signature, so it must never be valid next to the dropped one.
+    /// With a durable nonce the copies are exclusive, only one can advance the nonce. Otherwise
+    /// the dropped transaction is rebroadcast unchanged until its blockhash expires and only then
+    /// re-signed with a fresh blockhash; this can take the full blockhash lifetime, about a minute.
+    pub async fn send_transaction_with_retry(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        signers: &[&Keypair],
+        policy: RetryPolicy,
+    ) -> Result<RetryOutcome> {
+        let (price_index, base_price) = find_compute_unit_price(&transaction.message)
+            .ok_or_else(|| anyhow::anyhow!("Transaction has no SetComputeUnitPrice instruction"))?;
+
+        let mut transaction = transaction.clone();
+        let mut unit_price = base_price;
+        let mut attempt = 1;
+        let mut bumps = 0;
+        loop {
+            match self.send_transaction(trade_type, &transaction, true).await {
+                Ok(signature) => return Ok(RetryOutcome { signature, attempt, unit_price }),
+                Err(SwqosError::TransactionDropped { .. }) if attempt < policy.max_attempts => {}
+                Err(SwqosError::RpcRejected { kind: RpcErrorKind::Transient, message })
+                    if attempt < policy.max_attempts =>
+                {
+                    log::warn!(" [rpc] {} rejected transiently, retrying: {}", trade_type, message);
+                    tokio::time::sleep(policy.transient_retry_delay).await;
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            }
+
+            let mut message = transaction.message.clone();
+            if advances_nonce(&message).is_none() {
+                if let Some(signature) = self.rebroadcast_until_expired(trade_type, &transaction).await? {
+                    return Ok(RetryOutcome { signature, attempt, unit_price });
+                }
+                let blockhash = self.rpc_client.get_latest_blockhash().await?;
+                log::warn!(" [rpc] {} blockhash expired, re-signing with {}", trade_type, blockhash);
+                message.set_recent_blockhash(blockhash);
+            }
+            bumps += 1;
+            attempt += 1;
+            unit_price = (base_price as f64 * policy.fee_multiplier.powi(bumps)).ceil() as u64;
+            log::warn!(" [rpc] {} dropped, resubmitting attempt {} with cu price {}", trade_type, attempt, unit_price);
+            set_instruction_data(
+                &mut message,
+                price_index,
+                ComputeBudgetInstruction::set_compute_unit_price(unit_price).data,
+            );
+            transaction = VersionedTransaction::try_new(message, signers)?;
         }
-        Ok(())
     }
 
-    fn get_tip_account(&self) -> Result<String> {
-        Ok("".to_string())
+    /// Best-effort cancel of an in-flight `original`: submit a conflicting no-op from `payer` at
+    /// compute unit price `unit_price`, see `build_cancel_transaction`, and wait for it.
+    ///
+    /// Solana has no true cancel. Unless `original` uses a durable nonce advanced by `payer`, both
+    /// transactions stay valid until the blockhash expires and the original can still land; the
+    /// replacement only outbids it for the payer's write lock. Returns whether the replacement
+    /// confirmed, `false` if it was dropped, timed out or failed on-chain, which usually means the
+    /// original landed first.
+    pub async fn cancel_replace(
+        &self,
+        trade_type: TradeType,
+        original: &VersionedTransaction,
+        payer: &Keypair,
+        unit_price: u64,
+    ) -> Result<bool> {
+        let replacement = build_cancel_transaction(original, payer, unit_price)?;
+        log::warn!(" [rpc] {} cancel-replacing {} with cu price {}", trade_type, original.signatures[0], unit_price);
+        match self.send_transaction(trade_type, &replacement, true).await {
+            Ok(_) => Ok(true),
+            Err(
+                SwqosError::TransactionDropped { .. }
+                | SwqosError::ConfirmationTimeout { .. }
+                | SwqosError::TransactionFailed(_),
+            ) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
     }
+}
 
-    fn get_swqos_type(&self) -> SwqosType {
-        SwqosType::Default
+/// Compute units requested by the cancel transaction, enough for a nonce advance and a transfer
+const CANCEL_COMPUTE_UNIT_LIMIT: u32 = 10_000;
+
+/// A no-op that conflicts with `original`: a zero-lamport self-transfer by `payer` on the same
+/// recent blockhash (or nonce) at compute unit price `unit_price`.
+///
+/// If `original` starts with an advance of a durable nonce whose authority is `payer`, the
+/// replacement advances the same nonce, so at most one of the two can ever land. Fails if
+/// `unit_price` does not outbid the price of `original`.
+pub fn build_cancel_transaction(
+    original: &VersionedTransaction,
+    payer: &Keypair,
+    unit_price: u64,
+) -> Result<VersionedTransaction> {
+    if let Some((_, original_price)) = find_compute_unit_price(&original.message) {
+        if unit_price <= original_price {
+            return Err(anyhow::anyhow!(
+                "Cancel cu price {} does not outbid the original's {}",
+                unit_price,
+                original_price
+            ));
+        }
     }
+
+    let mut instructions = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(CANCEL_COMPUTE_UNIT_LIMIT),
+        ComputeBudgetInstruction::set_compute_unit_price(unit_price),
+    ];
+    if let Some(nonce_account) = advanced_nonce_account(&original.message, &payer.pubkey()) {
+        // A nonce advance must be the first instruction
+        instructions.insert(0, advance_nonce_account(&nonce_account, &payer.pubkey()));
+    }
+    instructions.push(transfer(&payer.pubkey(), &payer.pubkey(), 0));
+
+    let blockhash = *original.message.recent_blockhash();
+    let message = v0::Message::try_compile(&payer.pubkey(), &instructions, &[], blockhash)?;
+    Ok(VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer])?)
 }
 
-impl SolRpcClient {
-    pub fn new(rpc_client: Arc<SolanaRpcClient>) -> Self {
-        Self { rpc_client }
+/// Nonce account advanced by the first instruction of the message, if `authority` may advance it
+fn advanced_nonce_account(message: &VersionedMessage, authority: &Pubkey) -> Option<Pubkey> {
+    let (nonce_account, nonce_authority) = advances_nonce(message)?;
+    (nonce_authority == *authority).then_some(nonce_account)
+}
+
+/// Nonce account and authority of the `AdvanceNonceAccount` a durable-nonce message starts with
+fn advances_nonce(message: &VersionedMessage) -> Option<(Pubkey, Pubkey)> {
+    let account_keys = message.static_account_keys();
+    let instruction = message.instructions().first()?;
+    let program_id = account_keys.get(instruction.program_id_index as usize)?;
+    // SystemInstruction::AdvanceNonceAccount: u32 discriminator 4, accounts [nonce, sysvar, authority]
+    if *program_id != solana_system_interface::program::ID || instruction.data != [4, 0, 0, 0] {
+        return None;
+    }
+    let nonce_account = account_keys.get(*instruction.accounts.first()? as usize)?;
+    let nonce_authority = account_keys.get(*instruction.accounts.get(2)? as usize)?;
+    Some((*nonce_account, *nonce_authority))
+}
+
+/// Copy of `transaction` using `blockhash` as its recent blockhash, signed by `signers`
+pub fn resign_with_blockhash(
+    transaction: &VersionedTransaction,
+    blockhash: Hash,
+    signers: &[&Keypair],
+) -> Result<VersionedTransaction> {
+    let mut message = transaction.message.clone();
+    message.set_recent_blockhash(blockhash);
+    Ok(VersionedTransaction::try_new(message, signers)?)
+}
+
+/// Index and value of the `SetComputeUnitPrice` instruction in the message
+fn find_compute_unit_price(message: &VersionedMessage) -> Option<(usize, u64)> {
+    let account_keys = message.static_account_keys();
+    message.instructions().iter().enumerate().find_map(|(i, instruction)| {
+        let program_id = account_keys.get(instruction.program_id_index as usize)?;
+        let data = &instruction.data;
+        // SetComputeUnitPrice: discriminator 3 followed by a little-endian u64
+        if *program_id != solana_compute_budget_interface::id() || data.len() != 9 || data[0] != 3 {
+            return None;
+        }
+        Some((i, u64::from_le_bytes(data[1..9].try_into().ok()?)))
+    })
+}
+
+fn set_instruction_data(message: &mut VersionedMessage, index: usize, data: Vec<u8>) {
+    match message {
+        VersionedMessage::Legacy(message) => message.instructions[index].data = data,
+        VersionedMessage::V0(message) => message.instructions[index].data = data,
+    }
+}
+
+/// Decode a base64 wire-format transaction and check that every required signature verifies
+fn decode_raw_transaction(base64_tx: &str) -> Result<VersionedTransaction> {
+    let bytes = STANDARD
+        .decode(base64_tx.trim())
+        .map_err(|e| anyhow::anyhow!("Raw transaction is not valid base64: {}", e))?;
+    let transaction: VersionedTransaction = bincode::deserialize(&bytes)
+        .map_err(|e| anyhow::anyhow!("Raw transaction does not decode to a transaction: {}", e))?;
+    let required = transaction.message.header().num_required_signatures as usize;
+    if transaction.signatures.len() != required {
+        return Err(anyhow::anyhow!(
+            "Raw transaction has {} signatures, its message requires {}",
+            transaction.signatures.len(),
+            required
+        ));
+    }
+    if let Some(index) = transaction.verify_with_results().iter().position(|ok| !ok) {
+        return Err(anyhow::anyhow!(
+            "Raw transaction signature {} does not verify for {}",
+            index,
+            transaction.message.static_account_keys()[index]
+        ));
+    }
+    Ok(transaction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::rpc_provider::MockRpc;
+    use crate::swqos::common::{PollBackoff, DEFAULT_CONFIRMATION_COMMITMENT};
+    use solana_sdk::message::Message;
+    use solana_sdk::signer::Signer;
+    use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+
+    /// A payer and its signed 1 lamport transfer
+    fn signed_transfer() -> (Keypair, VersionedTransaction) {
+        let payer = Keypair::new();
+        let instructions = [transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)];
+        let message = VersionedMessage::Legacy(Message::new(&instructions, Some(&payer.pubkey())));
+        let tx = VersionedTransaction::try_new(message, &[&payer]).unwrap();
+        (payer, tx)
+    }
+
+    /// A client sending through the returned `MockRpc`
+    fn mock_client() -> (Arc<MockRpc>, SolRpcClient) {
+        let mock = Arc::new(MockRpc::new());
+        (mock.clone(), SolRpcClient::new(mock))
+    }
+
+    /// Status of a transaction that landed in `slot` and reached `confirmation_status`
+    fn landed(slot: Slot, confirmation_status: TransactionConfirmationStatus) -> TransactionStatus {
+        TransactionStatus {
+            slot,
+            confirmations: Some(1),
+            status: Ok(()),
+            err: None,
+            confirmation_status: Some(confirmation_status),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_against_mock_rpc() {
+        use solana_client::client_error::{ClientError, ClientErrorKind};
+
+        let (_, tx) = signed_transfer();
+        let signature = tx.signatures[0];
+
+        let (mock, client) = mock_client();
+        mock.push_send_result(Err(ClientError::from(ClientErrorKind::Io(std::io::Error::other("reset")))));
+        assert!(matches!(
+            client.send_transaction(TradeType::Buy, &tx, false).await,
+            Err(SwqosError::RpcRejected { kind: RpcErrorKind::Transient, .. })
+        ));
+
+        mock.push_signature_status(signature, None);
+        mock.push_signature_status(
+            signature,
+            Some(landed(1, TransactionConfirmationStatus::Confirmed)),
+        );
+        assert_eq!(client.send_transaction(TradeType::Buy, &tx, true).await.unwrap(), signature);
+        assert_eq!(mock.sent().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_observer_sees_each_stage_once() {
+        use crate::swqos::observer::TradeObserver;
+        use solana_client::client_error::{ClientError, ClientErrorKind};
+
+        #[derive(Default)]
+        struct Stages(parking_lot::Mutex<Vec<&'static str>>);
+        impl TradeObserver for Stages {
+            fn on_submitted(&self, _: &Signature, provider: SwqosType, _: Duration) {
+                assert_eq!(provider, SwqosType::Default);
+                self.0.lock().push("submitted");
+            }
+            fn on_confirmed(&self, _: &Signature, _: Duration) {
+                self.0.lock().push("confirmed");
+            }
+            fn on_failed(&self, _: &Signature, _: &SwqosError, _: Duration) {
+                self.0.lock().push("failed");
+            }
+        }
+
+        let (_, tx) = signed_transfer();
+        let stages = Arc::new(Stages::default());
+        let (mock, client) = mock_client();
+        let client = client.with_observer(stages.clone());
+        mock.push_signature_status(tx.signatures[0], Some(landed(1, TransactionConfirmationStatus::Confirmed)));
+        client.send_transaction(TradeType::Buy, &tx, true).await.unwrap();
+        mock.push_send_result(Err(ClientError::from(ClientErrorKind::Custom("rejected".to_string()))));
+        assert!(client.send_transaction(TradeType::Buy, &tx, true).await.is_err());
+        assert_eq!(*stages.0.lock(), ["submitted", "confirmed", "failed"]);
+    }
+
+    #[tokio::test]
+    async fn test_pre_simulate_aborts_before_submitting() {
+        use solana_sdk::instruction::InstructionError;
+
+        let (_, tx) = signed_transfer();
+
+        let (mock, client) = mock_client();
+        let client = client.with_pre_simulate(true);
+        mock.set_simulation(
+            serde_json::from_value(serde_json::json!({
+                "err": { "InstructionError": [0, { "Custom": 6004 }] },
+                "logs": ["Program log: Error: ExceededSlippage"],
+            }))
+            .unwrap(),
+        );
+        match client.send_transaction(TradeType::Buy, &tx, false).await {
+            Err(SwqosError::SimulationRevert { err, logs }) => {
+                let slippage = InstructionError::Custom(6004);
+                assert_eq!(err, TransactionError::InstructionError(0, slippage));
+                assert_eq!(logs, vec!["Program log: Error: ExceededSlippage".to_string()]);
+            }
+            other => panic!("expected a simulation revert, got {:?}", other),
+        }
+        assert!(mock.sent().is_empty());
+
+        let succeeded = serde_json::json!({ "err": null, "logs": [] });
+        mock.set_simulation(serde_json::from_value(succeeded).unwrap());
+        let signature = client.send_transaction(TradeType::Buy, &tx, false).await.unwrap();
+        assert_eq!(signature, tx.signatures[0]);
+        assert_eq!(mock.sent().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_commitment_follows_trade_type() {
+        let (_, tx) = signed_transfer();
+
+        let (mock, client) = mock_client();
+        let client = client
+            .with_trade_type_commitment(TradeType::Buy, CommitmentLevel::Processed)
+            .with_trade_type_commitment(TradeType::Sell, CommitmentLevel::Finalized)
+            .with_confirmation_timeout(Duration::from_millis(50));
+        assert_eq!(client.options.confirmation_commitment(TradeType::Sell), CommitmentLevel::Finalized);
+        assert_eq!(client.options.confirmation_commitment(TradeType::Create), DEFAULT_CONFIRMATION_COMMITMENT);
+
+        mock.push_signature_status(
+            tx.signatures[0],
+            Some(landed(1, TransactionConfirmationStatus::Processed)),
+        );
+        // Processed is enough for a buy
+        assert_eq!(client.send_transaction(TradeType::Buy, &tx, true).await.unwrap(), tx.signatures[0]);
+        // but not for a raw sweep sent as a sell
+        let raw = STANDARD.encode(bincode::serialize(&tx).unwrap());
+        let result = client.send_raw(TradeType::Sell, &raw, true).await;
+        assert!(matches!(result.unwrap_err().downcast_ref(), Some(SwqosError::ConfirmationTimeout { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_validates_before_submitting() {
+        let (_, tx) = signed_transfer();
+        let encode = |tx: &VersionedTransaction| STANDARD.encode(bincode::serialize(tx).unwrap());
+
+        let (mock, client) = mock_client();
+        assert!(client.send_raw(TradeType::Sell, "not base64!", false).await.is_err());
+        let mut forged = tx.clone();
+        forged.signatures[0] = Signature::default();
+        assert!(client.send_raw(TradeType::Sell, &encode(&forged), false).await.is_err());
+        assert!(mock.sent().is_empty());
+
+        assert_eq!(client.send_raw(TradeType::Sell, &encode(&tx), false).await.unwrap(), tx.signatures[0]);
+        assert_eq!(mock.sent(), vec![tx]);
+    }
+
+    #[test]
+    fn test_cancel_transaction_conflicts_with_original() {
+        let payer = Keypair::new();
+        let nonce_account = Pubkey::new_unique();
+        let nonce = Hash::new_unique();
+        let instructions = [
+            advance_nonce_account(&nonce_account, &payer.pubkey()),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+        ];
+        let message = v0::Message::try_compile(&payer.pubkey(), &instructions, &[], nonce).unwrap();
+        let original = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        assert!(build_cancel_transaction(&original, &payer, 1_000).is_err());
+        let cancel = build_cancel_transaction(&original, &payer, 2_000).unwrap();
+        assert_eq!(*cancel.message.recent_blockhash(), nonce);
+        assert_eq!(advanced_nonce_account(&cancel.message, &payer.pubkey()), Some(nonce_account));
+        assert_eq!(find_compute_unit_price(&cancel.message), Some((2, 2_000)));
+        assert_ne!(cancel.signatures[0], original.signatures[0]);
+    }
+
+    #[test]
+    fn test_decode_instructions_resolves_accounts() {
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            solana_system_interface::instruction::transfer(&payer.pubkey(), &recipient, 42),
+        ];
+        let message = VersionedMessage::Legacy(Message::new(&instructions, Some(&payer.pubkey())));
+        let tx = VersionedTransaction { signatures: vec![Signature::default()], message };
+
+        let decoded = decode_instructions(&tx).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].program_id, solana_compute_budget_interface::id());
+        assert_eq!(decoded[1].program_id, instructions[1].program_id);
+        assert_eq!(decoded[1].accounts, vec![payer.pubkey(), recipient]);
+        assert_eq!(decoded[1].data, instructions[1].data);
+    }
+
+    #[test]
+    fn test_decode_loaded_instructions_orders_lookup_addresses() {
+        use solana_sdk::message::compiled_instruction::CompiledInstruction;
+        use solana_sdk::message::v0::{self, MessageAddressTableLookup};
+        use solana_sdk::message::MessageHeader;
+
+        let payer = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: (0..4).map(|_| Pubkey::new_unique()).collect(),
+        };
+        let message = v0::Message {
+            header: MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 1 },
+            account_keys: vec![payer, program],
+            recent_blockhash: Default::default(),
+            // static keys 0..2, writable ALT entry -> 2, readonly ALT entries -> 3, 4
+            instructions: vec![CompiledInstruction { program_id_index: 1, accounts: vec![0, 2, 3, 4], data: vec![7] }],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: table.key,
+                writable_indexes: vec![3],
+                readonly_indexes: vec![0, 1],
+            }],
+        };
+        let tx = VersionedTransaction { signatures: vec![Signature::default()], message: VersionedMessage::V0(message) };
+
+        let decoded = decode_loaded_instructions(&tx, std::slice::from_ref(&table)).unwrap();
+        assert_eq!(decoded[0].program_id, program);
+        assert_eq!(decoded[0].accounts, vec![payer, table.addresses[3], table.addresses[0], table.addresses[1]]);
+
+        // Without the table the lookup indices are out of range instead of panicking
+        assert!(decode_instructions(&tx).is_err());
+        // The summary only needs the static keys
+        assert_eq!(instruction_programs(&tx), vec![program]);
+    }
+
+    #[tokio::test]
+    async fn test_retry_resigns_only_after_blockhash_expires() {
+        let payer = Keypair::new();
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            solana_system_interface::instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1),
+        ];
+        let message = VersionedMessage::Legacy(Message::new(&instructions, Some(&payer.pubkey())));
+        let tx = VersionedTransaction::try_new(message, &[&payer]).unwrap();
+        let timeout = Duration::from_millis(200);
+        let backoff = PollBackoff { initial: Duration::from_millis(10), max: Duration::from_millis(20) };
+        let policy = RetryPolicy { max_attempts: 2, ..RetryPolicy::default() };
+
+        // While the blockhash is valid the dropped bytes are rebroadcast, never re-signed
+        let (mock, client) = mock_client();
+        let client = client.with_confirmation_timeout(timeout).with_confirmation_poll_backoff(backoff);
+        let (lands, signature) = (mock.clone(), tx.signatures[0]);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            lands.push_signature_status(
+                signature,
+                Some(landed(1, TransactionConfirmationStatus::Confirmed)),
+            );
+        });
+        let outcome = client.send_transaction_with_retry(TradeType::Buy, &tx, &[&payer], policy).await.unwrap();
+        assert_eq!((outcome.signature, outcome.attempt, outcome.unit_price), (signature, 1, 1_000));
+        assert!(mock.sent().iter().all(|sent| sent.signatures == tx.signatures));
+
+        // Once it expired, one more wait sees nothing and a fresh blockhash is signed with a bump
+        let (mock, client) = mock_client();
+        let client = client.with_confirmation_timeout(timeout).with_confirmation_poll_backoff(backoff);
+        let fresh = Hash::new_unique();
+        mock.set_blockhash_valid(false);
+        mock.set_latest_blockhash(fresh);
+        let result = client.send_transaction_with_retry(TradeType::Buy, &tx, &[&payer], policy).await;
+        assert!(matches!(result, Err(e) if e.to_string().contains("dropped")));
+        let sent = mock.sent();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(*sent[1].message.recent_blockhash(), fresh);
+        assert_eq!(find_compute_unit_price(&sent[1].message).map(|(_, price)| price), Some(1_500));
+    }
+
+    #[test]
+    fn test_compute_unit_price_bump() {
+        let payer = Keypair::new();
+        let instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+        ];
+        let mut message =
+            VersionedMessage::Legacy(Message::new(&instructions, Some(&payer.pubkey())));
+        assert_eq!(find_compute_unit_price(&message), Some((1, 1_000)));
+
+        set_instruction_data(&mut message, 1, ComputeBudgetInstruction::set_compute_unit_price(1_500).data);
+        assert_eq!(find_compute_unit_price(&message), Some((1, 1_500)));
+    }
+
+    #[test]
+    fn test_resign_with_blockhash() {
+        let (payer, tx) = signed_transfer();
+
+        let blockhash = Hash::new_unique();
+        let resigned = resign_with_blockhash(&tx, blockhash, &[&payer]).unwrap();
+        assert_eq!(*resigned.message.recent_blockhash(), blockhash);
+        assert_ne!(resigned.signatures[0], tx.signatures[0]);
+        assert!(resigned.verify_with_results().iter().all(|ok| *ok));
+    }
+
+    #[test]
+    fn test_decode_confirmed_transaction_with_inner_instructions() {
+        use serde_json::json;
+        use solana_sdk::instruction::{AccountMeta, Instruction};
+
+        let payer = Keypair::new();
+        let program = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: vec![pool] };
+        let instruction =
+            Instruction::new_with_bytes(program, &[7], vec![AccountMeta::new(pool, false)]);
+        let message =
+            v0::Message::try_compile(&payer.pubkey(), &[instruction], &[table], Hash::default())
+                .unwrap();
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+        let encoded = STANDARD.encode(bincode::serialize(&tx).unwrap());
+
+        // Keys: payer, program, then the loaded pool; the CPI moves from the pool back to the payer
+        let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(json!({
+            "slot": 42,
+            "blockTime": null,
+            "transaction": [encoded, "base64"],
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5_000,
+                "preBalances": [0, 0, 0],
+                "postBalances": [0, 0, 0],
+                "innerInstructions": [{
+                    "index": 0,
+                    "instructions": [{
+                        "programIdIndex": 1,
+                        "accounts": [2, 0],
+                        "data": bs58::encode([3u8, 4]).into_string(),
+                        "stackHeight": 2,
+                    }],
+                }],
+                "loadedAddresses": { "writable": [pool.to_string()], "readonly": [] },
+            },
+        }))
+        .unwrap();
+
+        let decoded = decode_confirmed_transaction(tx.signatures[0], &transaction).unwrap();
+        assert_eq!(decoded.slot, 42);
+        assert_eq!(decoded.instructions[0].accounts, vec![pool]);
+        assert_eq!(
+            decoded.inner_instructions,
+            vec![vec![DecodedInstruction {
+                program_id: program,
+                accounts: vec![pool, payer.pubkey()],
+                data: vec![3, 4],
+            }]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_submits_need_one_acceptance() {
+        use solana_client::client_error::{ClientError, ClientErrorKind};
+
+        let (_, tx) = signed_transfer();
+
+        let (mock, client) = mock_client();
+        let client = client.with_duplicate_submits(3);
+        let reset = || Err(ClientError::from(ClientErrorKind::Io(std::io::Error::other("reset"))));
+        mock.push_send_result(reset());
+        mock.push_send_result(reset());
+        let signature = client.send_transaction(TradeType::Buy, &tx, false).await.unwrap();
+        assert_eq!(signature, tx.signatures[0]);
+        assert_eq!(mock.sent().len(), 3);
+
+        for _ in 0..3 {
+            mock.push_send_result(reset());
+        }
+        assert!(client.send_transaction(TradeType::Buy, &tx, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_already_processed_counts_as_sent() {
+        use solana_client::client_error::{ClientError, ClientErrorKind};
+
+        let (_, tx) = signed_transfer();
+
+        let (mock, client) = mock_client();
+        mock.push_send_result(Err(ClientError::from(ClientErrorKind::TransactionError(
+            TransactionError::AlreadyProcessed,
+        ))));
+        let signature = client.send_transaction(TradeType::Buy, &tx, false).await.unwrap();
+        assert_eq!(signature, tx.signatures[0]);
+    }
+
+    #[tokio::test]
+    async fn test_send_returns_landed_slot() {
+        let (_, tx) = signed_transfer();
+
+        let (mock, client) = mock_client();
+        let send_config = SendConfig { min_context_slot: Some(4_200), ..SendConfig::default() };
+        let client = client.with_send_config(send_config);
+        mock.push_signature_status(
+            tx.signatures[0],
+            Some(landed(4_242, TransactionConfirmationStatus::Confirmed)),
+        );
+        let sent = client.send_transaction_with_slot(TradeType::Buy, &tx).await.unwrap();
+        assert_eq!(sent, (tx.signatures[0], 4_242));
+        assert_eq!(mock.sent_configs()[0].min_context_slot, Some(4_200));
+    }
+
+    #[tokio::test]
+    async fn test_send_with_progress_reports_each_level() {
+        use futures::StreamExt;
+
+        let (_, tx) = signed_transfer();
+        let signature = tx.signatures[0];
+
+        let (mock, client) = mock_client();
+        let send_config = SendConfig { min_context_slot: Some(5), ..SendConfig::default() };
+        let client = client.with_send_config(send_config);
+        mock.push_signature_status(signature, None);
+        for (slot, level) in [
+            (7, TransactionConfirmationStatus::Processed),
+            (7, TransactionConfirmationStatus::Processed),
+            (8, TransactionConfirmationStatus::Confirmed),
+        ] {
+            mock.push_signature_status(signature, Some(landed(slot, level)));
+        }
+
+        let events: Vec<_> = client.send_with_progress(TradeType::Buy, tx).collect().await;
+        assert_eq!(events.len(), 3);
+        let SendProgress::Submitted { signature: submitted, .. } = events[0] else {
+            panic!("unexpected first event {:?}", events[0]);
+        };
+        assert_eq!(submitted, signature);
+        assert!(matches!(
+            events[1],
+            SendProgress::Seen { commitment: CommitmentLevel::Processed, slot: 7, .. }
+        ));
+        assert!(matches!(events[2], SendProgress::Confirmed { slot: 8, .. }));
+        assert!(events[2].is_terminal());
+        assert_eq!(mock.sent_configs()[0].min_context_slot, Some(5));
     }
 }
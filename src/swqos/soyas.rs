@@ -5,9 +5,8 @@ use quinn::{
     crypto::rustls::QuicClientConfig, ClientConfig, Connection, Endpoint, IdleTimeout,
     TransportConfig,
 };
-use rand::seq::IndexedRandom as _;
 use solana_client::rpc_client::SerializableTransaction;
-use solana_sdk::{signature::Keypair, transaction::VersionedTransaction};
+use solana_sdk::{signature::{Keypair, Signature}, transaction::VersionedTransaction};
 use solana_tls_utils::{new_dummy_x509_certificate, SkipServerVerification};
 use std::time::Instant;
 use std::{
@@ -18,8 +17,9 @@ use std::{
 use tokio::sync::Mutex;
 
 use crate::common::SolanaRpcClient;
-use crate::swqos::common::poll_transaction_confirmation;
+use crate::swqos::common::{choose_tip_account, SwqosError};
 use crate::swqos::SwqosClientTrait;
+use crate::swqos::provider_options::{impl_provider_builders, ProviderOptions};
 use crate::{
     constants::swqos::SOYAS_TIP_ACCOUNTS,
     swqos::{SwqosType, TradeType},
@@ -37,8 +37,12 @@ pub struct SoyasClient {
     addr: SocketAddr,
     connection: ArcSwap<Connection>,
     reconnect: Mutex<()>,
+    /// Rate limit, auto-tip, confirmation and observer settings, see `ProviderOptions`
+    pub options: ProviderOptions,
 }
 
+impl_provider_builders!(SoyasClient);
+
 impl SoyasClient {
     pub async fn new(rpc_url: String, endpoint_string: String, api_key: String) -> Result<Self> {
         let rpc_client = SolanaRpcClient::new(rpc_url);
@@ -75,6 +79,7 @@ impl SoyasClient {
             addr,
             connection: ArcSwap::from_pointee(connection),
             reconnect: Mutex::new(()),
+            options: ProviderOptions::default(),
         })
     }
 
@@ -94,16 +99,15 @@ impl SoyasClient {
         stream.finish()?;
         Ok(())
     }
-}
 
-#[async_trait::async_trait]
-impl SwqosClientTrait for SoyasClient {
-    async fn send_transaction(
+    async fn submit(
         &self,
         trade_type: TradeType,
         transaction: &VersionedTransaction,
         wait_confirmation: bool,
-    ) -> Result<()> {
+    ) -> Result<Signature, SwqosError> {
+        let transaction = self.options.prepare(self, transaction).await?;
+        let transaction = transaction.as_ref();
         let start_time = Instant::now();
         let signature = transaction.get_signature();
         let serialized_tx = bincode::serialize(transaction)?;
@@ -117,7 +121,8 @@ impl SwqosClientTrait for SoyasClient {
                 return Err(e.into());
             }
         }
-        match poll_transaction_confirmation(&self.rpc_client, *signature, wait_confirmation).await {
+        self.options.on_submitted(self.get_swqos_type(), signature, start_time.elapsed());
+        match self.options.confirm(&self.rpc_client, trade_type, *signature, wait_confirmation).await {
             Ok(_) => (),
             Err(e) => {
                 println!(" signature: {:?}", signature);
@@ -129,7 +134,20 @@ impl SwqosClientTrait for SoyasClient {
             println!(" signature: {:?}", signature);
             println!(" [soyas] {} confirmed: {:?}", trade_type, start_time.elapsed());
         }
-        Ok(())
+        Ok(*signature)
+    }
+}
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for SoyasClient {
+    async fn send_transaction(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+        wait_confirmation: bool,
+    ) -> Result<Signature, SwqosError> {
+        let submit = self.submit(trade_type, transaction, wait_confirmation);
+        self.options.observe_failures(transaction, submit).await
     }
 
     async fn send_transactions(
@@ -137,22 +155,32 @@ impl SwqosClientTrait for SoyasClient {
         trade_type: TradeType,
         transactions: &Vec<VersionedTransaction>,
         wait_confirmation: bool,
-    ) -> Result<()> {
+    ) -> Result<Vec<Signature>, SwqosError> {
+        let mut signatures = Vec::with_capacity(transactions.len());
         for transaction in transactions {
-            self.send_transaction(trade_type, transaction, wait_confirmation).await?;
+            signatures.push(self.send_transaction(trade_type, transaction, wait_confirmation).await?);
         }
-        Ok(())
+        Ok(signatures)
     }
 
     fn get_tip_account(&self) -> Result<String> {
-        let tip_account = *SOYAS_TIP_ACCOUNTS
-            .choose(&mut rand::rng())
-            .or_else(|| SOYAS_TIP_ACCOUNTS.first())
-            .unwrap();
-        Ok(tip_account.to_string())
+        choose_tip_account(SOYAS_TIP_ACCOUNTS)
+    }
+
+    fn get_tip_accounts(&self) -> Vec<String> {
+        SOYAS_TIP_ACCOUNTS.iter().map(|account| account.to_string()).collect()
     }
 
     fn get_swqos_type(&self) -> SwqosType {
         SwqosType::Soyas
     }
+
+    fn provider_options(&self) -> Option<&ProviderOptions> {
+        Some(&self.options)
+    }
+
+    /// QUIC keeps a live RTT estimate for the connection, no extra request needed
+    async fn health_check(&self) -> Result<Duration> {
+        Ok(self.connection.load().rtt())
+    }
 }
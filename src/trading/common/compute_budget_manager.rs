@@ -1,9 +1,21 @@
+use anyhow::{anyhow, Result};
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
 use smallvec::SmallVec;
 use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
 use solana_compute_budget_interface::ComputeBudgetInstruction;
 
+use crate::common::rpc_provider::RpcProvider;
+pub use crate::swqos::common::MAX_COMPUTE_UNIT_LIMIT;
+use crate::swqos::solana_rpc::SolRpcClient;
+
+/// Margin added on top of the simulated consumption by `auto_units`
+pub const AUTO_UNITS_MARGIN_PERCENT: u64 = 10;
+
 /// Cache key containing all parameters for compute budget instructions
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct ComputeBudgetCacheKey {
@@ -51,3 +63,76 @@ pub fn compute_budget_instructions(
 
     insts
 }
+
+/// Compute unit limit and price instructions, `None` requests the maximum limit
+pub fn build_compute_budget(units_limit: Option<u32>, micro_lamports: u64) -> [Instruction; 2] {
+    [
+        ComputeBudgetInstruction::set_compute_unit_limit(
+            units_limit.unwrap_or(MAX_COMPUTE_UNIT_LIMIT),
+        ),
+        ComputeBudgetInstruction::set_compute_unit_price(micro_lamports),
+    ]
+}
+
+/// Measure the compute units `instructions` consume by simulating them, plus
+/// `AUTO_UNITS_MARGIN_PERCENT`.
+///
+/// `instructions` must not contain compute budget instructions of their own. The simulation runs
+/// without signature verification against the latest blockhash, and fails if the instructions
+/// would fail on-chain.
+pub async fn auto_units(
+    client: &SolRpcClient,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> Result<u32> {
+    let mut simulated = Vec::with_capacity(instructions.len() + 1);
+    simulated.push(ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT));
+    simulated.extend_from_slice(instructions);
+
+    let blockhash = client.rpc_client.get_latest_blockhash().await?;
+    let message = VersionedMessage::V0(v0::Message::try_compile(payer, &simulated, &[], blockhash)?);
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default(); message.header().num_required_signatures as usize],
+        message,
+    };
+
+    let result = client.simulate(&transaction).await?;
+    if let Some(err) = result.err {
+        return Err(anyhow!("Simulation failed: {}, logs: {:?}", err, result.logs));
+    }
+    let units_consumed = result
+        .units_consumed
+        .ok_or_else(|| anyhow!("Simulation did not report consumed compute units"))?;
+    Ok(units_with_margin(units_consumed))
+}
+
+/// `build_compute_budget` with the limit measured by `auto_units`
+pub async fn build_compute_budget_auto(
+    client: &SolRpcClient,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    micro_lamports: u64,
+) -> Result<[Instruction; 2]> {
+    let units = auto_units(client, payer, instructions).await?;
+    Ok(build_compute_budget(Some(units), micro_lamports))
+}
+
+fn units_with_margin(units_consumed: u64) -> u32 {
+    let units = units_consumed.saturating_mul(100 + AUTO_UNITS_MARGIN_PERCENT).div_ceil(100);
+    units.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_units_with_margin_is_capped() {
+        assert_eq!(units_with_margin(100_000), 110_000);
+        assert_eq!(units_with_margin(1_399_000), MAX_COMPUTE_UNIT_LIMIT);
+        assert_eq!(
+            build_compute_budget(None, 1)[0],
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)
+        );
+    }
+}
@@ -1,8 +1,18 @@
+use anyhow::anyhow;
 use solana_hash::Hash;
-use solana_sdk::{instruction::Instruction, signature::Keypair, signer::Signer};
-use solana_system_interface::instruction::advance_nonce_account;
+use solana_sdk::{
+    instruction::Instruction,
+    message::{v0, AddressLookupTableAccount, VersionedMessage},
+    signature::Keypair,
+    signer::Signer,
+    transaction::VersionedTransaction,
+};
+use solana_sdk::pubkey::Pubkey;
+use solana_system_interface::instruction::{advance_nonce_account, create_nonce_account};
 
 use crate::common::nonce_cache::DurableNonceInfo;
+use crate::common::seed::rent_exempt_lamports;
+use crate::common::SolanaRpcClient;
 
 /// Add nonce advance instruction to the instruction set
 ///
@@ -38,3 +48,75 @@ pub fn get_transaction_blockhash(
         recent_blockhash.unwrap()
     }
 }
+
+/// Build a transaction that stays valid until the nonce is advanced, for pre-signing and sending
+/// long after the usual blockhash lifetime
+///
+/// Prepends the advance-nonce instruction (with `payer` as nonce authority) and signs against the
+/// nonce stored in the account, as read by `fetch_nonce`. The nonce must be re-read once a
+/// transaction using it has landed.
+pub fn build_durable_transaction(
+    payer: &Keypair,
+    durable_nonce: &DurableNonceInfo,
+    instructions: &[Instruction],
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction, anyhow::Error> {
+    let (Some(nonce_account), Some(nonce)) =
+        (durable_nonce.nonce_account, durable_nonce.current_nonce)
+    else {
+        return Err(anyhow!("Durable nonce account or current nonce is not set"));
+    };
+
+    let mut full_instructions = Vec::with_capacity(instructions.len() + 1);
+    full_instructions.push(advance_nonce_account(&nonce_account, &payer.pubkey()));
+    full_instructions.extend_from_slice(instructions);
+
+    let message = v0::Message::try_compile(
+        &payer.pubkey(),
+        &full_instructions,
+        address_lookup_table_accounts,
+        nonce,
+    )?;
+    Ok(VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer])?)
+}
+
+/// Instructions creating `nonce_account` under `authority`, funded by `payer` with exactly the
+/// current rent exemption for a nonce account
+///
+/// `nonce_account` must also sign the transaction.
+pub async fn create_nonce_account_instructions(
+    rpc: &SolanaRpcClient,
+    payer: &Pubkey,
+    nonce_account: &Pubkey,
+    authority: &Pubkey,
+) -> Result<Vec<Instruction>, anyhow::Error> {
+    let lamports = rent_exempt_lamports(rpc, solana_nonce::state::State::size()).await?;
+    Ok(create_nonce_account(payer, nonce_account, authority, lamports))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_build_durable_transaction_advances_nonce_first() {
+        let payer = Keypair::new();
+        let nonce_account = Pubkey::new_unique();
+        let durable_nonce = DurableNonceInfo {
+            nonce_account: Some(nonce_account),
+            current_nonce: Some(Hash::new_unique()),
+        };
+        let swap = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]);
+
+        let transaction =
+            build_durable_transaction(&payer, &durable_nonce, &[swap], &[]).unwrap();
+        let message = &transaction.message;
+        assert_eq!(*message.recent_blockhash(), durable_nonce.current_nonce.unwrap());
+        let first = &message.instructions()[0];
+        let keys = message.static_account_keys();
+        assert_eq!(keys[first.program_id_index as usize], solana_system_interface::program::id());
+        assert_eq!(keys[first.accounts[0] as usize], nonce_account);
+        assert_eq!(message.instructions().len(), 2);
+    }
+}
@@ -2,9 +2,12 @@ use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer, transaction
 use solana_system_interface::instruction::transfer;
 
 use crate::common::{
-    fast_fn::get_associated_token_address_with_program_id_fast, spl_token::close_account,
+    fast_fn::get_associated_token_address_with_program_id_fast,
+    seed::{rent_exempt_lamports, TOKEN_ACCOUNT_SIZE},
+    spl_token::close_account,
     SolanaRpcClient,
 };
+use crate::swqos::common::SwqosError;
 use anyhow::anyhow;
 
 /// Get the balances of two tokens in the pool
@@ -79,6 +82,30 @@ pub async fn get_sol_balance(
     Ok(balance)
 }
 
+/// Check that `payer` holds enough SOL for a buy of `sol_amount` lamports plus `estimated_fee`
+/// (base and priority fee, tip included) and, when `needs_ata`, the rent of the token account.
+///
+/// Fails with `SwqosError::InsufficientFunds` before anything is signed or submitted.
+pub async fn check_affordability(
+    rpc: &SolanaRpcClient,
+    payer: &Pubkey,
+    sol_amount: u64,
+    estimated_fee: u64,
+    needs_ata: bool,
+) -> Result<(), anyhow::Error> {
+    let ata_rent = if needs_ata {
+        rent_exempt_lamports(rpc, TOKEN_ACCOUNT_SIZE).await?
+    } else {
+        0
+    };
+    let needed = sol_amount.saturating_add(estimated_fee).saturating_add(ata_rent);
+    let available = get_sol_balance(rpc, payer).await?;
+    if available < needed {
+        return Err(SwqosError::InsufficientFunds { needed, available }.into());
+    }
+    Ok(())
+}
+
 pub async fn transfer_sol(
     rpc: &SolanaRpcClient,
     payer: &Keypair,
@@ -13,24 +13,8 @@ use std::{str::FromStr, sync::Arc, time::Instant};
 use crate::{
     common::nonce_cache::DurableNonceInfo,
     common::{GasFeeStrategy, SolanaRpcClient},
-    swqos::{SwqosClient, SwqosType, TradeType},
+    swqos::{common::SwqosError, SwqosClient, SwqosType, TradeType},
     trading::{common::build_transaction, MiddlewareManager},
-    constants::swqos::{
-        SWQOS_MIN_TIP_DEFAULT,
-        SWQOS_MIN_TIP_JITO,
-        SWQOS_MIN_TIP_NEXTBLOCK,
-        SWQOS_MIN_TIP_ZERO_SLOT,
-        SWQOS_MIN_TIP_TEMPORAL,
-        SWQOS_MIN_TIP_BLOXROUTE,
-        SWQOS_MIN_TIP_NODE1,
-        SWQOS_MIN_TIP_FLASHBLOCK,
-        SWQOS_MIN_TIP_BLOCKRAZOR,
-        SWQOS_MIN_TIP_ASTRALANE,
-        SWQOS_MIN_TIP_STELLIUM,
-        SWQOS_MIN_TIP_LIGHTSPEED,
-        SWQOS_MIN_TIP_SOYAS,
-        SWQOS_MIN_TIP_SPEEDLANDING
-    },
 };
 
 #[repr(align(64))]
@@ -44,27 +28,30 @@ struct TaskResult {
 }
 
 /// Check if an error indicates the transaction landed on-chain (vs network/timeout error)
-fn is_landed_error(error: &anyhow::Error) -> bool {
-    use crate::swqos::common::TradeError;
-
-    // If it's a TradeError with a non-zero code, the tx landed but failed on-chain
-    if let Some(trade_error) = error.downcast_ref::<TradeError>() {
-        // Code 500 with "timed out" message means tx never landed
-        if trade_error.code == 500 && trade_error.message.contains("timed out") {
-            return false;
-        }
-        // Any other TradeError means the tx landed (e.g., ExceededSlippage = 6004)
-        return trade_error.code > 0;
-    }
-
-    // Check error message for timeout indication
-    let msg = error.to_string();
-    if msg.contains("timed out") || msg.contains("timeout") {
-        return false;
+fn is_landed_error(error: &SwqosError) -> bool {
+    match error {
+        // The tx landed but failed on-chain (e.g., ExceededSlippage = 6004)
+        SwqosError::TransactionFailed(trade_error) => trade_error.code > 0,
+        // Rejected, dropped or timed out: the tx was never executed
+        SwqosError::RpcSend(_)
+        | SwqosError::RpcRejected { .. }
+        | SwqosError::Serialization(_)
+        | SwqosError::ConfirmationTimeout { .. }
+        | SwqosError::TransactionDropped { .. }
+        | SwqosError::ProviderRejected { .. }
+        | SwqosError::Unsupported { .. }
+        | SwqosError::BlockhashExpired { .. }
+        | SwqosError::Cancelled { .. }
+        | SwqosError::MissingTip { .. }
+        | SwqosError::TransactionTooLarge { .. }
+        | SwqosError::ReorgDropped { .. }
+        | SwqosError::InsufficientFunds { .. }
+        | SwqosError::FeeCeilingExceeded { .. }
+        | SwqosError::CircuitOpen { .. }
+        | SwqosError::SimulationRevert { .. }
+        | SwqosError::AlreadySubmitted { .. }
+        | SwqosError::InvalidBundle { .. } => false,
     }
-
-    // Assume other errors might indicate landed tx (be conservative)
-    false
 }
 
 struct ResultCollector {
@@ -246,22 +233,7 @@ pub async fn execute_parallel(
                 .filter(|config| {
                     // 当需要 tip 且不是 Default 时，按 provider 最低小费进行筛选
                     if with_tip && !matches!(config.0, SwqosType::Default) {
-                        let min_tip = match config.0 {
-                            SwqosType::Jito => SWQOS_MIN_TIP_JITO,
-                            SwqosType::NextBlock => SWQOS_MIN_TIP_NEXTBLOCK,
-                            SwqosType::ZeroSlot => SWQOS_MIN_TIP_ZERO_SLOT,
-                            SwqosType::Temporal => SWQOS_MIN_TIP_TEMPORAL,
-                            SwqosType::Bloxroute => SWQOS_MIN_TIP_BLOXROUTE,
-                            SwqosType::Node1 => SWQOS_MIN_TIP_NODE1,
-                            SwqosType::FlashBlock => SWQOS_MIN_TIP_FLASHBLOCK,
-                            SwqosType::BlockRazor => SWQOS_MIN_TIP_BLOCKRAZOR,
-                            SwqosType::Astralane => SWQOS_MIN_TIP_ASTRALANE,
-                            SwqosType::Stellium => SWQOS_MIN_TIP_STELLIUM,
-                            SwqosType::Lightspeed => SWQOS_MIN_TIP_LIGHTSPEED,
-                            SwqosType::Soyas => SWQOS_MIN_TIP_SOYAS,
-                            SwqosType::Speedlanding => SWQOS_MIN_TIP_SPEEDLANDING,
-                            SwqosType::Default => SWQOS_MIN_TIP_DEFAULT,
-                        };
+                        let min_tip = config.0.min_tip();
                         if config.2.tip < min_tip {
                             println!(
                                 "⚠️ Config filtered: {:?} tip {} is below minimum required tip {}",
@@ -296,8 +268,12 @@ pub async fn execute_parallel(
         let instructions = instructions.clone();
         let middleware_manager = middleware_manager.clone();
         let swqos_type = swqos_client.get_swqos_type();
-        let tip_account_str = swqos_client.get_tip_account()?;
-        let tip_account = Arc::new(Pubkey::from_str(&tip_account_str).unwrap_or_default());
+        // Default (plain RPC) never tips and has no tip account
+        let tip_account = if swqos_type == SwqosType::Default {
+            Arc::new(Pubkey::default())
+        } else {
+            Arc::new(Pubkey::from_str(&swqos_client.get_tip_account()?)?)
+        };
         let collector = collector.clone();
 
         let tip = gas_fee_strategy_config.2.tip;
@@ -360,14 +336,14 @@ pub async fn execute_parallel(
                 )
                 .await
             {
-                Ok(()) => {
+                Ok(_) => {
                     landed_on_chain = true;  // Success means tx confirmed on-chain
                     true
                 }
                 Err(e) => {
                     // Check if this error indicates the tx landed but failed (e.g., ExceededSlippage)
                     landed_on_chain = is_landed_error(&e);
-                    err = Some(e);
+                    err = Some(e.into());
                     // Send transaction failed
                     false
                 }
@@ -1,3 +1,4 @@
+use crate::common::account_cache::account_cache;
 use crate::common::bonding_curve::BondingCurveAccount;
 use crate::common::nonce_cache::DurableNonceInfo;
 use crate::common::spl_associated_token_account::get_associated_token_address_with_program_id;
@@ -72,6 +73,80 @@ pub struct SwapParams {
     /// When Some(false), uses regular buy instruction where slippage is applied to SOL/quote input.
     /// This option only applies to PumpFun and PumpSwap DEXes; it is ignored for other DEXes.
     pub use_exact_sol_amount: Option<bool>,
+    /// Referral token account credited with the referral share of the trading fee.
+    /// Only Meteora DAMM v2 honors it (it must hold the pool's fee token), see
+    /// `DexType::supports_referral`; `TradingClient` rejects it for the other DEXes.
+    pub referral: Option<Pubkey>,
+}
+
+/// Protocol-agnostic trade parameters for `DexProtocol`
+#[derive(Clone)]
+pub struct TradeParams {
+    /// Token mint to trade
+    pub mint: Pubkey,
+    /// Lamports to spend when buying, token amount when selling
+    pub amount: u64,
+    /// Slippage tolerance in basis points
+    pub slippage_bps: u16,
+    /// Wallet that pays and signs
+    pub payer: Arc<Keypair>,
+    /// Output expected when the trade was decided, `check_slippage` fails once the pool has moved
+    /// more than `slippage_bps` away from it
+    pub reference_out: Option<u64>,
+}
+
+impl TradeParams {
+    pub fn new(mint: Pubkey, amount: u64, slippage_bps: u16, payer: Arc<Keypair>) -> Self {
+        Self { mint, amount, slippage_bps, payer, reference_out: None }
+    }
+
+    pub fn with_reference_out(mut self, reference_out: u64) -> Self {
+        self.reference_out = Some(reference_out);
+        self
+    }
+}
+
+impl SwapParams {
+    /// 仅用于构建指令的参数，不涉及提交：无 swqos、无中间件、不等待确认
+    pub(crate) fn for_instructions(
+        payer: &Keypair,
+        trade_type: TradeType,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        input_amount: u64,
+        slippage_basis_points: u64,
+        protocol_params: DexParamEnum,
+    ) -> Self {
+        Self {
+            rpc: None,
+            payer: Arc::new(payer.insecure_clone()),
+            trade_type,
+            input_mint,
+            input_token_program: None,
+            output_mint,
+            output_token_program: None,
+            input_amount: Some(input_amount),
+            slippage_basis_points: Some(slippage_basis_points),
+            address_lookup_table_account: None,
+            recent_blockhash: None,
+            wait_transaction_confirmed: false,
+            protocol_params,
+            open_seed_optimize: false,
+            swqos_clients: Vec::new(),
+            middleware_manager: None,
+            durable_nonce: None,
+            with_tip: false,
+            create_input_mint_ata: false,
+            close_input_mint_ata: false,
+            create_output_mint_ata: false,
+            close_output_mint_ata: false,
+            fixed_output_amount: None,
+            gas_fee_strategy: GasFeeStrategy::new(),
+            simulate: false,
+            use_exact_sol_amount: None,
+            referral: None,
+        }
+    }
 }
 
 impl std::fmt::Debug for SwapParams {
@@ -177,7 +252,7 @@ impl PumpFunParams {
     ) -> Result<Self, anyhow::Error> {
         let account =
             crate::instruction::utils::pumpfun::fetch_bonding_curve_account(rpc, mint).await?;
-        let mint_account = rpc.get_account(&mint).await?;
+        let mint_account = account_cache().get_or_fetch(rpc, mint).await?;
         let bonding_curve = BondingCurveAccount {
             discriminator: 0,
             account: account.1,
@@ -495,7 +570,7 @@ impl BonkParams {
         .unwrap();
         let pool_data =
             crate::instruction::utils::bonk::fetch_pool_state(rpc, &pool_address).await?;
-        let token_account = rpc.get_account(&pool_data.base_mint).await?;
+        let token_account = account_cache().get_or_fetch(rpc, &pool_data.base_mint).await?;
         let platform_associated_account =
             crate::instruction::utils::bonk::get_platform_associated_account(
                 &pool_data.platform_config,
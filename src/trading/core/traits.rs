@@ -1,6 +1,9 @@
+use crate::common::SolanaRpcClient;
+use crate::trading::core::params::TradeParams;
+use crate::trading::factory::DexType;
 use crate::trading::SwapParams;
 use anyhow::Result;
-use solana_sdk::{instruction::Instruction, signature::Signature};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signature};
 
 /// 交易执行器trait - 定义了所有交易协议都需要实现的核心方法
 #[async_trait::async_trait]
@@ -23,3 +26,30 @@ pub trait InstructionBuilder: Send + Sync {
     /// 构建卖出指令
     async fn build_sell_instructions(&self, params: &SwapParams) -> Result<Vec<Instruction>>;
 }
+
+/// 统一的协议接口 - 策略可以持有 `Box<dyn DexProtocol>`，切换交易场所时无需区分协议
+#[async_trait::async_trait]
+pub trait DexProtocol: Send + Sync {
+    /// 交易场所，用于日志
+    fn venue(&self) -> DexType;
+
+    /// 用 `params.amount` 个 lamports 买入 `params.mint`
+    async fn build_buy(
+        &self,
+        rpc: &SolanaRpcClient,
+        params: &TradeParams,
+    ) -> Result<Vec<Instruction>>;
+
+    /// 卖出 `params.amount` 个 `params.mint` 换回 SOL
+    async fn build_sell(
+        &self,
+        rpc: &SolanaRpcClient,
+        params: &TradeParams,
+    ) -> Result<Vec<Instruction>>;
+
+    /// 当前价格：一个完整代币值多少计价币（SOL 交易对即 SOL）
+    async fn get_price(&self, rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<f64>;
+
+    /// 当前储备 `(SOL 储备, 代币储备)`，按恒定乘积计算成交量
+    async fn get_reserves(&self, rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<(u64, u64)>;
+}
@@ -19,6 +19,13 @@ pub enum DexType {
     MeteoraDammV2,
 }
 
+impl DexType {
+    /// 是否支持在 swap 指令中附带推荐人账户（`SwapParams::referral`），目前只有 Meteora DAMM v2
+    pub fn supports_referral(&self) -> bool {
+        matches!(self, DexType::MeteoraDammV2)
+    }
+}
+
 /// 交易工厂 - 用于创建不同协议的交易执行器
 pub struct TradeFactory;
 
@@ -0,0 +1,57 @@
+use crate::instruction::pumpfun::BondingCurveState;
+
+/// Bounds on the SOL liquidity of a launch, to skip likely rugs below `min_sol` and tokens that
+/// already pumped above `max_sol`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiquidityFilter {
+    /// Lowest accepted SOL reserve in lamports, inclusive
+    pub min_sol: u64,
+    /// Highest accepted SOL reserve in lamports, inclusive
+    pub max_sol: u64,
+}
+
+impl LiquidityFilter {
+    pub fn new(min_sol: u64, max_sol: u64) -> Self {
+        Self { min_sol, max_sol }
+    }
+
+    /// Whether a pool holding `sol_reserve` lamports on its SOL side passes
+    pub fn passes_reserves(&self, sol_reserve: u64) -> bool {
+        (self.min_sol..=self.max_sol).contains(&sol_reserve)
+    }
+
+    /// Whether a bonding curve passes, judged by the SOL actually deposited into it; the virtual
+    /// reserves start at the same amount for every launch. A completed curve never passes, it
+    /// cannot be bought on anymore.
+    pub fn passes_bonding_curve(&self, curve: &BondingCurveState) -> bool {
+        !curve.complete && self.passes_reserves(curve.real_sol_reserves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+
+    #[test]
+    fn test_liquidity_filter_bounds() {
+        let filter = LiquidityFilter::new(LAMPORTS_PER_SOL, 20 * LAMPORTS_PER_SOL);
+        let curve = |real_sol_reserves, complete| BondingCurveState {
+            address: Pubkey::new_unique(),
+            virtual_sol_reserves: 30 * LAMPORTS_PER_SOL + real_sol_reserves,
+            virtual_token_reserves: 1_073_000_000_000_000,
+            real_sol_reserves,
+            real_token_reserves: 793_100_000_000_000,
+            complete,
+            creator: Pubkey::default(),
+        };
+
+        // A creator that bought almost nothing
+        assert!(!filter.passes_bonding_curve(&curve(LAMPORTS_PER_SOL / 10, false)));
+        assert!(filter.passes_bonding_curve(&curve(LAMPORTS_PER_SOL, false)));
+        assert!(filter.passes_bonding_curve(&curve(20 * LAMPORTS_PER_SOL, false)));
+        assert!(!filter.passes_bonding_curve(&curve(40 * LAMPORTS_PER_SOL, false)));
+        assert!(!filter.passes_bonding_curve(&curve(5 * LAMPORTS_PER_SOL, true)));
+        assert!(filter.passes_reserves(5 * LAMPORTS_PER_SOL));
+    }
+}
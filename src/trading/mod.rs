@@ -1,9 +1,23 @@
 pub mod common;
 pub mod core;
 pub mod factory;
+pub mod liquidity_filter;
 pub mod middleware;
+pub mod position_tracker;
+pub mod route;
+pub mod slippage;
+pub mod tip_strategy;
+pub mod trade_builder;
+pub mod trade_result;
 
-pub use core::params::SwapParams;
-pub use core::traits::InstructionBuilder;
+pub use core::params::{SwapParams, TradeParams};
+pub use core::traits::{DexProtocol, InstructionBuilder};
 pub use factory::TradeFactory;
+pub use liquidity_filter::LiquidityFilter;
 pub use middleware::{InstructionMiddleware, MiddlewareManager};
+pub use position_tracker::{Position, PositionTracker};
+pub use route::{build_route, RouteHop};
+pub use slippage::{check_slippage, ExpectedFill};
+pub use tip_strategy::{TipScaler, TipStrategy};
+pub use trade_builder::TradeBuilder;
+pub use trade_result::{execute_multi_wallet, execute_trade, fetch_trade_result, TradeResult};
@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use solana_commitment_config::CommitmentConfig;
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+
+use crate::common::SolanaRpcClient;
+use crate::events::pumpfun::{parse_pumpfun_trades, TradeEvent};
+
+/// Aggregated fills of one mint, using average-cost accounting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    /// Tokens bought minus tokens sold
+    pub net_tokens: i128,
+    /// Lamports spent on buys
+    pub sol_spent: u64,
+    /// Lamports received from sells
+    pub sol_received: u64,
+    /// Tokens currently held according to the fills
+    pub holdings: u64,
+    /// Lamports paid for the tokens still held
+    pub cost_basis: u64,
+    /// Lamports gained (or lost if negative) on the tokens sold
+    pub realized_pnl: i128,
+}
+
+impl Position {
+    fn apply(&mut self, event: &TradeEvent) {
+        if event.is_buy {
+            self.net_tokens += event.token_amount as i128;
+            self.sol_spent = self.sol_spent.saturating_add(event.sol_amount);
+            self.holdings = self.holdings.saturating_add(event.token_amount);
+            self.cost_basis = self.cost_basis.saturating_add(event.sol_amount);
+        } else {
+            self.net_tokens -= event.token_amount as i128;
+            self.sol_received = self.sol_received.saturating_add(event.sol_amount);
+            // Tokens bought before tracking started have no known cost
+            let sold = event.token_amount.min(self.holdings);
+            let cost = if self.holdings == 0 {
+                0
+            } else {
+                (self.cost_basis as u128 * sold as u128 / self.holdings as u128) as u64
+            };
+            self.holdings -= sold;
+            self.cost_basis -= cost;
+            self.realized_pnl += event.sol_amount as i128 - cost as i128;
+        }
+    }
+}
+
+/// Reconciles a wallet's Pump.fun positions from the trade events of confirmed transactions
+///
+/// Each signature is only counted once, so overlapping batches can be fed to `reconcile`.
+#[derive(Debug, Clone)]
+pub struct PositionTracker {
+    owner: Pubkey,
+    positions: HashMap<Pubkey, Position>,
+    seen_signatures: HashSet<Signature>,
+}
+
+impl PositionTracker {
+    /// Track the fills of `owner`; trades of other wallets in the same transactions are ignored
+    pub fn new(owner: Pubkey) -> Self {
+        Self { owner, positions: HashMap::new(), seen_signatures: HashSet::new() }
+    }
+
+    /// Fetch each confirmed transaction and apply its trade events, returning the number of
+    /// fills applied. Signatures already reconciled are skipped.
+    pub async fn reconcile(&mut self, rpc: &SolanaRpcClient, signatures: &[Signature]) -> Result<usize> {
+        let mut applied = 0;
+        for signature in signatures {
+            if self.seen_signatures.contains(signature) {
+                continue;
+            }
+            let transaction = rpc
+                .get_transaction_with_config(
+                    signature,
+                    RpcTransactionConfig {
+                        encoding: Some(UiTransactionEncoding::Json),
+                        max_supported_transaction_version: Some(0),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    },
+                )
+                .await?;
+            let logs = match transaction.transaction.meta {
+                // Failed transactions still log, but their trades were rolled back
+                Some(meta) if meta.err.is_none() => match meta.log_messages {
+                    OptionSerializer::Some(logs) => logs,
+                    _ => Vec::new(),
+                },
+                _ => Vec::new(),
+            };
+            applied += self.record_logs(&logs);
+            self.seen_signatures.insert(*signature);
+        }
+        Ok(applied)
+    }
+
+    /// Apply the owner's trade events found in one transaction's logs
+    pub fn record_logs(&mut self, logs: &[String]) -> usize {
+        let mut applied = 0;
+        for event in parse_pumpfun_trades(logs) {
+            if self.record(&event) {
+                applied += 1;
+            }
+        }
+        applied
+    }
+
+    /// Apply one fill, returns false if it belongs to another wallet
+    pub fn record(&mut self, event: &TradeEvent) -> bool {
+        if event.user != self.owner {
+            return false;
+        }
+        self.positions.entry(event.mint).or_default().apply(event);
+        true
+    }
+
+    pub fn position(&self, mint: &Pubkey) -> Option<&Position> {
+        self.positions.get(mint)
+    }
+
+    pub fn positions(&self) -> &HashMap<Pubkey, Position> {
+        &self.positions
+    }
+
+    /// Realized profit in lamports on the tokens of `mint` sold so far
+    pub fn realized_pnl(&self, mint: &Pubkey) -> i128 {
+        self.positions.get(mint).map(|position| position.realized_pnl).unwrap_or(0)
+    }
+
+    /// Average entry price in SOL per whole token of the tokens still held, `None` if none are
+    pub fn average_entry(&self, mint: &Pubkey) -> Option<f64> {
+        let position = self.positions.get(mint).filter(|position| position.holdings > 0)?;
+        Some(crate::utils::price::pumpfun::price_token_in_sol(position.cost_basis, position.holdings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(owner: Pubkey, mint: Pubkey, is_buy: bool, sol_amount: u64, token_amount: u64) -> TradeEvent {
+        TradeEvent {
+            mint,
+            sol_amount,
+            token_amount,
+            is_buy,
+            user: owner,
+            timestamp: 0,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+            real_sol_reserves: 0,
+            real_token_reserves: 0,
+        }
+    }
+
+    #[test]
+    fn test_average_cost_pnl() {
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut tracker = PositionTracker::new(owner);
+
+        assert!(tracker.record(&fill(owner, mint, true, 1_000_000_000, 1_000_000)));
+        assert!(tracker.record(&fill(owner, mint, true, 3_000_000_000, 1_000_000)));
+        assert!(!tracker.record(&fill(Pubkey::new_unique(), mint, true, 1, 1)));
+        // 2 SOL per token on average
+        assert_eq!(tracker.average_entry(&mint), Some(2.0));
+
+        assert!(tracker.record(&fill(owner, mint, false, 3_000_000_000, 1_000_000)));
+        assert_eq!(tracker.realized_pnl(&mint), 1_000_000_000);
+        assert_eq!(tracker.average_entry(&mint), Some(2.0));
+
+        let position = tracker.position(&mint).unwrap();
+        assert_eq!(position.net_tokens, 1_000_000);
+        assert_eq!(position.sol_spent, 4_000_000_000);
+        assert_eq!(position.sol_received, 3_000_000_000);
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::{anyhow, Result};
+use solana_hash::Hash;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+
+use crate::common::fast_fn::{
+    create_associated_token_account_idempotent_fast,
+    get_associated_token_address_with_program_id_fast,
+};
+use crate::common::token_program::{detect_mint, TransferFee};
+use crate::common::SolanaRpcClient;
+use crate::constants::WSOL_TOKEN_ACCOUNT;
+use crate::instruction::utils::raydium_cpmm::{accounts, SWAP_BASE_IN_DISCRIMINATOR};
+use crate::swqos::common::{SwqosError, MAX_TRANSACTION_SIZE};
+use crate::trading::common::{close_wsol, handle_wsol};
+use crate::trading::core::params::RaydiumCpmmParams;
+use crate::trading::factory::DexType;
+use crate::utils::calc::raydium_cpmm::compute_swap_amount;
+
+/// Bytes kept free in a routed transaction for the compute budget and tip instructions
+const ROUTE_SIZE_MARGIN: usize = 150;
+
+/// One swap of a route, trading all of `input_mint` it receives for `output_mint` in `pool`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteHop {
+    /// Must be `DexType::RaydiumCpmm`, the only venue `build_route` supports
+    pub dex_type: DexType,
+    pub pool: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    /// Minimum output of this hop, `None` to derive it from the reserves, `slippage_bps` and the
+    /// Token-2022 transfer fees of both mints
+    pub min_out: Option<u64>,
+}
+
+impl RouteHop {
+    pub fn raydium_cpmm(pool: Pubkey, input_mint: Pubkey, output_mint: Pubkey) -> Self {
+        Self { dex_type: DexType::RaydiumCpmm, pool, input_mint, output_mint, min_out: None }
+    }
+
+    pub fn with_min_out(mut self, min_out: u64) -> Self {
+        self.min_out = Some(min_out);
+        self
+    }
+}
+
+/// Swap `amount_in` of the first hop's input through every hop in one atomic transaction, e.g.
+/// SOL -> USDC -> TOKEN.
+///
+/// Only Raydium CPMM pools can be routed through: they pair arbitrary mints and take an exact
+/// input, so one hop's output can feed the next. A hop with any other `DexType` is rejected;
+/// trade other venues one leg at a time with `TradingClient::buy` and `TradingClient::sell`.
+///
+/// Each hop spends the previous hop's minimum output, so whatever a hop returns above its minimum
+/// stays in the intermediate token account. The last hop's minimum protects the whole route: if
+/// any hop falls short, the transaction fails. A WSOL input is wrapped first and a WSOL output
+/// unwrapped last.
+///
+/// Fails with `SwqosError::TransactionTooLarge` if the route would not fit in one transaction next
+/// to a compute budget and tip; compile it against an address lookup table instead, see
+/// `common::address_lookup::compile_with_lookup_table`.
+pub async fn build_route(
+    rpc: &SolanaRpcClient,
+    hops: &[RouteHop],
+    payer: &Pubkey,
+    amount_in: u64,
+    slippage_bps: u16,
+) -> Result<Vec<Instruction>> {
+    let mut pools = Vec::with_capacity(hops.len());
+    for hop in hops {
+        if hop.dex_type != DexType::RaydiumCpmm {
+            return Err(anyhow!(
+                "{:?} pools cannot be routed through, only RaydiumCpmm",
+                hop.dex_type
+            ));
+        }
+        pools.push(RaydiumCpmmParams::from_pool_address_by_rpc(rpc, &hop.pool).await?);
+    }
+    let mut transfer_fees = Vec::with_capacity(hops.len() + 1);
+    let last_output = hops.last().map(|hop| &hop.output_mint);
+    let mints = hops.iter().map(|hop| &hop.input_mint).chain(last_output);
+    for mint in mints {
+        transfer_fees.push(detect_mint(rpc, mint).await?.transfer_fee);
+    }
+    route_instructions(hops, &pools, &transfer_fees, payer, amount_in, slippage_bps)
+}
+
+/// `transfer_fees` holds the fee of every mint along the route, the first hop's input mint
+/// followed by each hop's output mint
+fn route_instructions(
+    hops: &[RouteHop],
+    pools: &[RaydiumCpmmParams],
+    transfer_fees: &[Option<TransferFee>],
+    payer: &Pubkey,
+    amount_in: u64,
+    slippage_bps: u16,
+) -> Result<Vec<Instruction>> {
+    let (Some(first), Some(last)) = (hops.first(), hops.last()) else {
+        return Err(anyhow!("Route has no hops"));
+    };
+    if let Some(i) = hops.windows(2).position(|pair| pair[0].output_mint != pair[1].input_mint) {
+        return Err(anyhow!(
+            "Hop {} outputs {} but hop {} takes {}",
+            i,
+            hops[i].output_mint,
+            i + 1,
+            hops[i + 1].input_mint
+        ));
+    }
+
+    let mut instructions = Vec::with_capacity(2 * hops.len() + 4);
+    if first.input_mint == WSOL_TOKEN_ACCOUNT {
+        instructions.extend(handle_wsol(payer, amount_in));
+    }
+    let mut hop_amount_in = amount_in;
+    for (i, (hop, pool)) in hops.iter().zip(pools).enumerate() {
+        let is_base_in = hop.input_mint == pool.base_mint;
+        if !(is_base_in && hop.output_mint == pool.quote_mint
+            || hop.input_mint == pool.quote_mint && hop.output_mint == pool.base_mint)
+        {
+            return Err(anyhow!(
+                "Pool {} does not pair {} with {}",
+                hop.pool,
+                hop.input_mint,
+                hop.output_mint
+            ));
+        }
+        let (input_vault, output_vault, input_program, output_program) = if is_base_in {
+            (pool.base_vault, pool.quote_vault, pool.base_token_program, pool.quote_token_program)
+        } else {
+            (pool.quote_vault, pool.base_vault, pool.quote_token_program, pool.base_token_program)
+        };
+        // The pool receives the input after its transfer fee, and the program checks the minimum
+        // against what arrives after the output's fee
+        let after_fee = |fee: Option<&Option<TransferFee>>, amount| {
+            fee.copied().flatten().map_or(amount, |fee| fee.amount_after_fee(amount))
+        };
+        let min_out = hop.min_out.unwrap_or_else(|| {
+            let (base, quote) = (pool.base_reserve, pool.quote_reserve);
+            let amount_in = after_fee(transfer_fees.get(i), hop_amount_in);
+            let min_out =
+                compute_swap_amount(base, quote, is_base_in, amount_in, slippage_bps as u64)
+                    .min_amount_out;
+            after_fee(transfer_fees.get(i + 1), min_out)
+        });
+        if min_out == 0 {
+            return Err(anyhow!("Hop through {} returns nothing for {}", hop.pool, hop_amount_in));
+        }
+
+        let ata =
+            |mint, program| get_associated_token_address_with_program_id_fast(payer, mint, program);
+        let input_token_account = ata(&hop.input_mint, &input_program);
+        let output_token_account = ata(&hop.output_mint, &output_program);
+        instructions.extend(create_associated_token_account_idempotent_fast(
+            payer,
+            payer,
+            &hop.output_mint,
+            &output_program,
+        ));
+        let accounts = vec![
+            AccountMeta::new(*payer, true),
+            accounts::AUTHORITY_META,
+            AccountMeta::new_readonly(pool.amm_config, false),
+            AccountMeta::new(pool.pool_state, false),
+            AccountMeta::new(input_token_account, false),
+            AccountMeta::new(output_token_account, false),
+            AccountMeta::new(input_vault, false),
+            AccountMeta::new(output_vault, false),
+            AccountMeta::new_readonly(input_program, false),
+            AccountMeta::new_readonly(output_program, false),
+            AccountMeta::new_readonly(hop.input_mint, false),
+            AccountMeta::new_readonly(hop.output_mint, false),
+            AccountMeta::new(pool.observation_state, false),
+        ];
+        let mut data = [0u8; 24];
+        data[..8].copy_from_slice(SWAP_BASE_IN_DISCRIMINATOR);
+        data[8..16].copy_from_slice(&hop_amount_in.to_le_bytes());
+        data[16..24].copy_from_slice(&min_out.to_le_bytes());
+        instructions.push(Instruction::new_with_bytes(accounts::RAYDIUM_CPMM, &data, accounts));
+        hop_amount_in = min_out;
+    }
+    if first.input_mint == WSOL_TOKEN_ACCOUNT || last.output_mint == WSOL_TOKEN_ACCOUNT {
+        instructions.extend(close_wsol(payer));
+    }
+
+    let size = route_size(payer, &instructions)?;
+    if size + ROUTE_SIZE_MARGIN > MAX_TRANSACTION_SIZE {
+        return Err(SwqosError::TransactionTooLarge { size: size + ROUTE_SIZE_MARGIN }.into());
+    }
+    Ok(instructions)
+}
+
+/// Serialized size of a transaction holding only the route
+fn route_size(payer: &Pubkey, instructions: &[Instruction]) -> Result<usize> {
+    let message = v0::Message::try_compile(payer, instructions, &[], Hash::default())?;
+    let signatures = vec![Signature::default(); message.header.num_required_signatures as usize];
+    let transaction = VersionedTransaction { signatures, message: VersionedMessage::V0(message) };
+    Ok(bincode::serialized_size(&transaction)? as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{TOKEN_PROGRAM, USDC_TOKEN_ACCOUNT};
+
+    fn pool(base_mint: Pubkey, quote_mint: Pubkey) -> RaydiumCpmmParams {
+        RaydiumCpmmParams {
+            pool_state: Pubkey::new_unique(),
+            amm_config: Pubkey::new_unique(),
+            base_mint,
+            quote_mint,
+            base_reserve: 1_000_000_000_000,
+            quote_reserve: 1_000_000_000_000,
+            base_vault: Pubkey::new_unique(),
+            quote_vault: Pubkey::new_unique(),
+            base_token_program: TOKEN_PROGRAM,
+            quote_token_program: TOKEN_PROGRAM,
+            observation_state: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn test_route_chains_min_out() {
+        let payer = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let pools = [pool(WSOL_TOKEN_ACCOUNT, USDC_TOKEN_ACCOUNT), pool(token, USDC_TOKEN_ACCOUNT)];
+        let hops = [
+            RouteHop::raydium_cpmm(pools[0].pool_state, WSOL_TOKEN_ACCOUNT, USDC_TOKEN_ACCOUNT)
+                .with_min_out(900),
+            RouteHop::raydium_cpmm(pools[1].pool_state, USDC_TOKEN_ACCOUNT, token)
+                .with_min_out(800),
+        ];
+
+        let instructions = route_instructions(&hops, &pools, &[], &payer, 1_000, 100).unwrap();
+        let swaps: Vec<&Instruction> = instructions
+            .iter()
+            .filter(|instruction| instruction.program_id == accounts::RAYDIUM_CPMM)
+            .collect();
+        assert_eq!(swaps.len(), 2);
+        // The second hop spends the first hop's minimum
+        assert_eq!(swaps[1].data[8..16], 900u64.to_le_bytes());
+        assert_eq!(swaps[1].data[16..24], 800u64.to_le_bytes());
+
+        let broken = [hops[0], RouteHop { input_mint: token, ..hops[1] }];
+        assert!(route_instructions(&broken, &pools, &[], &payer, 1_000, 100).is_err());
+    }
+
+    #[test]
+    fn test_derived_min_out_includes_transfer_fees() {
+        let payer = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let pools = [pool(WSOL_TOKEN_ACCOUNT, token)];
+        let hops = [RouteHop::raydium_cpmm(pools[0].pool_state, WSOL_TOKEN_ACCOUNT, token)];
+        let min_out = |transfer_fees: &[Option<TransferFee>]| {
+            let instructions =
+                route_instructions(&hops, &pools, transfer_fees, &payer, 1_000_000, 100).unwrap();
+            let swap = instructions
+                .iter()
+                .find(|instruction| instruction.program_id == accounts::RAYDIUM_CPMM)
+                .unwrap();
+            u64::from_le_bytes(swap.data[16..24].try_into().unwrap())
+        };
+
+        let fee = TransferFee { epoch: 0, maximum_fee: u64::MAX, transfer_fee_basis_points: 250 };
+        let without_fee = min_out(&[None, None]);
+        assert_eq!(min_out(&[]), without_fee);
+        assert_eq!(min_out(&[None, Some(fee)]), fee.amount_after_fee(without_fee));
+        assert!(min_out(&[Some(fee), None]) < without_fee);
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Result};
+
+use crate::common::SolanaRpcClient;
+use crate::swqos::TradeType;
+use crate::trading::core::params::TradeParams;
+use crate::trading::core::traits::DexProtocol;
+use crate::utils::calc::common::{calculate_with_slippage_sell, constant_product_amount_out};
+
+/// Fill of a trade at the current reserves, before protocol fees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedFill {
+    /// Output at the current reserves
+    pub expected_out: u64,
+    /// `expected_out` less `slippage_bps`, the minimum the instruction enforces
+    pub min_out: u64,
+    /// How far `expected_out` falls short of the output at the spot price
+    pub price_impact_bps: u64,
+}
+
+/// Read the live reserves and compute the fill of `params` before building the transaction.
+///
+/// Fails if the output has dropped more than `params.slippage_bps` below `params.reference_out`,
+/// i.e. the price already moved past the tolerance since the trade was decided, so no fee is paid
+/// on a transaction that would revert. Without a reference only the fill is computed.
+pub async fn check_slippage(
+    rpc: &SolanaRpcClient,
+    protocol: &dyn DexProtocol,
+    params: &TradeParams,
+    trade_type: TradeType,
+) -> Result<ExpectedFill> {
+    let (sol_reserve, token_reserve) = protocol.get_reserves(rpc, &params.mint).await?;
+    let (reserve_in, reserve_out) = match trade_type {
+        TradeType::Sell => (token_reserve, sol_reserve),
+        _ => (sol_reserve, token_reserve),
+    };
+    expected_fill(params.amount, reserve_in, reserve_out, params.slippage_bps, params.reference_out)
+}
+
+fn expected_fill(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    slippage_bps: u16,
+    reference_out: Option<u64>,
+) -> Result<ExpectedFill> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(anyhow!("Pool has no liquidity"));
+    }
+    let expected_out = constant_product_amount_out(amount_in, reserve_in, reserve_out);
+    if expected_out == 0 {
+        return Err(anyhow!("Trade of {} returns nothing at the current reserves", amount_in));
+    }
+    if let Some(reference_out) = reference_out {
+        let worst_out = calculate_with_slippage_sell(reference_out, slippage_bps as u64);
+        if expected_out < worst_out {
+            return Err(anyhow!(
+                "Slippage exceeded: expected {} now, {} when decided, tolerance {} bps",
+                expected_out,
+                reference_out,
+                slippage_bps
+            ));
+        }
+    }
+
+    let spot_out = amount_in as u128 * reserve_out as u128 / reserve_in as u128;
+    let price_impact_bps = (spot_out - expected_out as u128) * 10_000 / spot_out;
+    Ok(ExpectedFill {
+        expected_out,
+        min_out: calculate_with_slippage_sell(expected_out, slippage_bps as u64),
+        price_impact_bps: price_impact_bps as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_fill_rejects_moved_price() {
+        // Adding 1_000 to a 99_000 reserve costs 1% against the spot price
+        let fill = expected_fill(1_000, 99_000, 99_000, 100, None).unwrap();
+        assert_eq!(fill.expected_out, 990);
+        assert_eq!(fill.min_out, 981);
+        assert_eq!(fill.price_impact_bps, 100);
+
+        assert!(expected_fill(1_000, 99_000, 99_000, 100, Some(1_000)).is_ok());
+        assert!(expected_fill(1_000, 99_000, 99_000, 100, Some(1_001)).is_err());
+        assert!(expected_fill(1_000, 0, 99_000, 100, None).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use solana_sdk::signature::Signature;
+
+use crate::swqos::common::SwqosError;
+use crate::swqos::observer::TradeObserver;
+
+/// How many lamports `TradeBuilder` tips the provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TipStrategy {
+    /// The same tip for every trade
+    Fixed(u64),
+    /// Basis points of the SOL side of the trade: the amount of a buy, the `reference_out` of a
+    /// sell (no tip when it is unset)
+    PercentOfTrade(u16),
+    /// Starts at `min`, grows after each missed bundle and shrinks after each landed one, never
+    /// leaving `[min, max]`
+    Adaptive { min: u64, max: u64 },
+}
+
+/// A `TipStrategy` with the landing history the adaptive strategy scales from.
+///
+/// Share one per provider between builders and attach it to that provider's client with
+/// `with_observer`, e.g. `JitoClient::with_observer(scaler.clone())`: as a `TradeObserver` it
+/// records every confirmed send as landed and every dropped or timed out one as missed. Outcomes
+/// can also be reported by hand with `record_landed` / `record_missed`; the fixed and percentage
+/// strategies ignore them.
+#[derive(Debug)]
+pub struct TipScaler {
+    strategy: TipStrategy,
+    current: AtomicU64,
+}
+
+impl TipScaler {
+    pub fn new(strategy: TipStrategy) -> Self {
+        let current = match strategy {
+            TipStrategy::Adaptive { min, .. } => min,
+            _ => 0,
+        };
+        Self { strategy, current: AtomicU64::new(current) }
+    }
+
+    pub fn strategy(&self) -> TipStrategy {
+        self.strategy
+    }
+
+    /// Tip for a trade moving `trade_lamports` of SOL
+    pub fn tip_lamports(&self, trade_lamports: u64) -> u64 {
+        match self.strategy {
+            TipStrategy::Fixed(lamports) => lamports,
+            TipStrategy::PercentOfTrade(bps) => {
+                (trade_lamports as u128 * bps as u128 / 10_000) as u64
+            }
+            TipStrategy::Adaptive { .. } => self.current.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Current adaptive tip, `None` for the other strategies
+    pub fn current_adaptive(&self) -> Option<u64> {
+        match self.strategy {
+            TipStrategy::Adaptive { .. } => Some(self.current.load(Ordering::Relaxed)),
+            _ => None,
+        }
+    }
+
+    /// A tipped transaction landed: lower the adaptive tip by a tenth
+    pub fn record_landed(&self) {
+        self.scale(|current| current - current / 10);
+    }
+
+    /// A tipped transaction did not land: raise the adaptive tip by half
+    pub fn record_missed(&self) {
+        self.scale(|current| current.saturating_add((current / 2).max(1)));
+    }
+
+    fn scale(&self, step: impl Fn(u64) -> u64) {
+        if let TipStrategy::Adaptive { min, max } = self.strategy {
+            // Always Ok, the closure never declines the update
+            let _ = self.current.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                Some(step(current).clamp(min, max))
+            });
+        }
+    }
+}
+
+/// Feeds the adaptive tip from the sends of the client it is attached to. Only confirmations that
+/// were waited for are seen; errors other than a drop or timeout say nothing about the tip.
+impl TradeObserver for TipScaler {
+    fn on_confirmed(&self, _signature: &Signature, _elapsed: Duration) {
+        self.record_landed();
+    }
+
+    fn on_failed(&self, _signature: &Signature, error: &SwqosError, _elapsed: Duration) {
+        if matches!(
+            error,
+            SwqosError::TransactionDropped { .. } | SwqosError::ConfirmationTimeout { .. }
+        ) {
+            self.record_missed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adaptive_tip_stays_in_bounds() {
+        let scaler = TipScaler::new(TipStrategy::Adaptive { min: 10_000, max: 40_000 });
+        assert_eq!(scaler.current_adaptive(), Some(10_000));
+
+        scaler.record_missed();
+        assert_eq!(scaler.tip_lamports(0), 15_000);
+        for _ in 0..10 {
+            scaler.record_missed();
+        }
+        assert_eq!(scaler.tip_lamports(0), 40_000);
+        scaler.record_landed();
+        assert_eq!(scaler.tip_lamports(0), 36_000);
+        for _ in 0..50 {
+            scaler.record_landed();
+        }
+        assert_eq!(scaler.tip_lamports(0), 10_000);
+
+        let percent = TipScaler::new(TipStrategy::PercentOfTrade(50));
+        assert_eq!(percent.tip_lamports(1_000_000_000), 5_000_000);
+        assert_eq!(percent.current_adaptive(), None);
+    }
+
+    #[tokio::test]
+    async fn test_attached_scaler_follows_send_outcomes() {
+        use crate::common::rpc_provider::MockRpc;
+        use crate::swqos::solana_rpc::SolRpcClient;
+        use crate::swqos::{SwqosClientTrait, TradeType};
+        use solana_sdk::message::{Message, VersionedMessage};
+        use solana_sdk::signer::{keypair::Keypair, Signer};
+        use solana_sdk::transaction::VersionedTransaction;
+        use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+        use std::sync::Arc;
+
+        let sign = |payer: Keypair| {
+            let message = VersionedMessage::Legacy(Message::new(&[], Some(&payer.pubkey())));
+            VersionedTransaction::try_new(message, &[&payer]).unwrap()
+        };
+        let (dropped, confirmed) = (sign(Keypair::new()), sign(Keypair::new()));
+        let scaler = Arc::new(TipScaler::new(TipStrategy::Adaptive { min: 10_000, max: 40_000 }));
+        let mock = Arc::new(MockRpc::new());
+        let client = SolRpcClient::new(mock.clone())
+            .with_confirmation_timeout(Duration::from_millis(50))
+            .with_observer(scaler.clone());
+
+        // Never seen on-chain: dropped, the tip grows
+        mock.push_signature_status(dropped.signatures[0], None);
+        assert!(client.send_transaction(TradeType::Buy, &dropped, true).await.is_err());
+        assert_eq!(scaler.current_adaptive(), Some(15_000));
+
+        mock.push_signature_status(
+            confirmed.signatures[0],
+            Some(TransactionStatus {
+                slot: 1,
+                confirmations: Some(1),
+                status: Ok(()),
+                err: None,
+                confirmation_status: Some(TransactionConfirmationStatus::Confirmed),
+            }),
+        );
+        client.send_transaction(TradeType::Buy, &confirmed, true).await.unwrap();
+        assert_eq!(scaler.current_adaptive(), Some(13_500));
+    }
+}
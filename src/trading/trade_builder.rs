@@ -0,0 +1,386 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use futures::future::join_all;
+use solana_hash::Hash;
+use solana_sdk::{
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    transaction::VersionedTransaction,
+};
+use solana_system_interface::instruction::transfer;
+
+use crate::common::blockhash_cache::BlockhashCache;
+use crate::common::SolanaRpcClient;
+use crate::swqos::common::{validate_size, LAMPORTS_PER_SIGNATURE};
+use crate::swqos::{SwqosClient, TradeType};
+use crate::trading::common::check_affordability;
+use crate::trading::common::compute_budget_manager::{build_compute_budget, MAX_COMPUTE_UNIT_LIMIT};
+use crate::trading::core::params::TradeParams;
+use crate::trading::core::traits::DexProtocol;
+use crate::trading::tip_strategy::{TipScaler, TipStrategy};
+
+/// Assembles a signed trade transaction in landing order: compute budget, tip transfer, extra
+/// pre-instructions, swap, extra post-instructions
+///
+/// The tip goes to the account reported by the selected provider's `get_tip_account`, so the
+/// result can be handed straight to that provider's `send_transaction`. With `with_fee_payer` a
+/// separate wallet, e.g. a relayer, pays the fees and tip while `params.payer` owns the tokens.
+#[derive(Clone)]
+pub struct TradeBuilder {
+    protocol: Arc<dyn DexProtocol>,
+    params: TradeParams,
+    trade_type: TradeType,
+    units_limit: Option<u32>,
+    micro_lamports: u64,
+    tip: Option<(Arc<SwqosClient>, Arc<TipScaler>)>,
+    recent_blockhash: Option<Hash>,
+    blockhash_cache: Option<Arc<BlockhashCache>>,
+    fee_payer: Option<Arc<Keypair>>,
+    extra_pre_instructions: Vec<Instruction>,
+    extra_post_instructions: Vec<Instruction>,
+    affordability_check: Option<bool>,
+}
+
+impl TradeBuilder {
+    /// Buy `params.mint` for `params.amount` lamports
+    pub fn buy(protocol: Arc<dyn DexProtocol>, params: TradeParams) -> Self {
+        Self::new(protocol, params, TradeType::Buy)
+    }
+
+    /// Sell `params.amount` tokens of `params.mint`
+    pub fn sell(protocol: Arc<dyn DexProtocol>, params: TradeParams) -> Self {
+        Self::new(protocol, params, TradeType::Sell)
+    }
+
+    fn new(protocol: Arc<dyn DexProtocol>, params: TradeParams, trade_type: TradeType) -> Self {
+        Self {
+            protocol,
+            params,
+            trade_type,
+            units_limit: None,
+            micro_lamports: 0,
+            tip: None,
+            recent_blockhash: None,
+            blockhash_cache: None,
+            fee_payer: None,
+            extra_pre_instructions: Vec::new(),
+            extra_post_instructions: Vec::new(),
+            affordability_check: None,
+        }
+    }
+
+    /// Compute unit limit (`None` for the maximum) and price in micro-lamports
+    pub fn with_compute_budget(mut self, units_limit: Option<u32>, micro_lamports: u64) -> Self {
+        self.units_limit = units_limit;
+        self.micro_lamports = micro_lamports;
+        self
+    }
+
+    /// Tip `tip_lamports` to the provider the transaction will be sent through
+    pub fn with_tip(self, swqos_client: Arc<SwqosClient>, tip_lamports: u64) -> Self {
+        let scaler = Arc::new(TipScaler::new(TipStrategy::Fixed(tip_lamports)));
+        self.with_tip_strategy(swqos_client, scaler)
+    }
+
+    /// Tip the provider the transaction will be sent through whatever `scaler` asks for at build
+    /// time
+    pub fn with_tip_strategy(
+        mut self,
+        swqos_client: Arc<SwqosClient>,
+        scaler: Arc<TipScaler>,
+    ) -> Self {
+        self.tip = Some((swqos_client, scaler));
+        self
+    }
+
+    /// Use this blockhash instead of fetching the latest one
+    pub fn with_recent_blockhash(mut self, recent_blockhash: Hash) -> Self {
+        self.recent_blockhash = Some(recent_blockhash);
+        self
+    }
+
+    /// Take the blockhash from `cache` when no fixed one is set, falling back to fetching if the
+    /// cache is still empty
+    pub fn with_blockhash_cache(mut self, cache: Arc<BlockhashCache>) -> Self {
+        self.blockhash_cache = Some(cache);
+        self
+    }
+
+    /// Pay the transaction fees and tip from `fee_payer` instead of `params.payer`; both sign
+    pub fn with_fee_payer(mut self, fee_payer: Arc<Keypair>) -> Self {
+        self.fee_payer = Some(fee_payer);
+        self
+    }
+
+    /// Instructions spliced in right before the swap, e.g. a memo or a referral transfer
+    pub fn with_pre_instructions(mut self, instructions: Vec<Instruction>) -> Self {
+        self.extra_pre_instructions = instructions;
+        self
+    }
+
+    /// Instructions spliced in right after the swap
+    pub fn with_post_instructions(mut self, instructions: Vec<Instruction>) -> Self {
+        self.extra_post_instructions = instructions;
+        self
+    }
+
+    /// Before signing a buy, check that the wallets can cover the amount, fees, tip and, when
+    /// `needs_ata`, the token account rent, see `check_affordability`
+    pub fn with_affordability_check(mut self, needs_ata: bool) -> Self {
+        self.affordability_check = Some(needs_ata);
+        self
+    }
+
+    /// Trade for `payer` instead of `params.payer`
+    pub fn with_payer(mut self, payer: Arc<Keypair>) -> Self {
+        self.params.payer = payer;
+        self
+    }
+
+    pub fn params(&self) -> &TradeParams {
+        &self.params
+    }
+
+    pub fn trade_type(&self) -> TradeType {
+        self.trade_type
+    }
+
+    /// Build the swap and return the transaction signed by `params.payer` and the fee payer.
+    ///
+    /// Fails with `SwqosError::TransactionTooLarge` if the extra instructions push the transaction
+    /// past the packet size limit.
+    pub async fn build(&self, rpc: &SolanaRpcClient) -> Result<VersionedTransaction> {
+        let swap = match self.trade_type {
+            TradeType::Sell => self.protocol.build_sell(rpc, &self.params).await?,
+            _ => self.protocol.build_buy(rpc, &self.params).await?,
+        };
+
+        let owner = self.params.payer.as_ref();
+        let payer = self.fee_payer.as_deref().unwrap_or(owner);
+        let mut instructions: Vec<Instruction> = Vec::with_capacity(
+            swap.len() + self.extra_pre_instructions.len() + self.extra_post_instructions.len() + 3,
+        );
+        instructions.extend(build_compute_budget(self.units_limit, self.micro_lamports));
+        let tip_lamports = self.tip_lamports();
+        if let Some((swqos_client, _)) = &self.tip {
+            if tip_lamports > 0 {
+                let tip_account: Pubkey = swqos_client.get_tip_account()?.parse().map_err(|e| {
+                    anyhow!("Invalid tip account from {:?}: {}", swqos_client.get_swqos_type(), e)
+                })?;
+                instructions.push(transfer(&payer.pubkey(), &tip_account, tip_lamports));
+            }
+        }
+        instructions.extend(self.extra_pre_instructions.iter().cloned());
+        instructions.extend(swap);
+        instructions.extend(self.extra_post_instructions.iter().cloned());
+
+        if let (Some(needs_ata), TradeType::Buy | TradeType::CreateAndBuy) =
+            (self.affordability_check, self.trade_type)
+        {
+            if payer.pubkey() == owner.pubkey() {
+                let estimated_fee = self.estimated_fee(1);
+                check_affordability(rpc, &owner.pubkey(), self.params.amount, estimated_fee, needs_ata)
+                    .await?;
+            } else {
+                // The owner pays the swap and the token account, the fee payer everything else
+                let estimated_fee = self.estimated_fee(2);
+                check_affordability(rpc, &owner.pubkey(), self.params.amount, 0, needs_ata).await?;
+                check_affordability(rpc, &payer.pubkey(), 0, estimated_fee, false).await?;
+            }
+        }
+
+        let cached = self.blockhash_cache.as_ref().and_then(|cache| cache.get());
+        let blockhash = match (self.recent_blockhash, cached) {
+            (Some(blockhash), _) | (None, Some((blockhash, _))) => blockhash,
+            (None, None) => rpc.get_latest_blockhash().await?,
+        };
+        // The fee payer is compiled as account_keys[0], so it signs first
+        let message = v0::Message::try_compile(&payer.pubkey(), &instructions, &[], blockhash)?;
+        let signers: Vec<&Keypair> =
+            if payer.pubkey() == owner.pubkey() { vec![payer] } else { vec![payer, owner] };
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &signers)?;
+        validate_size(&transaction)?;
+        Ok(transaction)
+    }
+
+    /// Build the same trade once per wallet, concurrently. Each wallet trades from its own token
+    /// accounts and, unless a fixed blockhash is set, gets the blockhash current at its build.
+    /// Results are in the order of `wallets`.
+    pub async fn build_for_wallets(
+        &self,
+        rpc: &SolanaRpcClient,
+        wallets: &[Arc<Keypair>],
+    ) -> Vec<Result<VersionedTransaction>> {
+        join_all(wallets.iter().map(|wallet| async move {
+            self.clone().with_payer(wallet.clone()).build(rpc).await
+        }))
+        .await
+    }
+
+    /// Base fee of `signatures`, priority fee at the compute unit limit and tip, in lamports
+    fn estimated_fee(&self, signatures: u64) -> u64 {
+        let units = self.units_limit.unwrap_or(MAX_COMPUTE_UNIT_LIMIT) as u128;
+        let priority_fee = (units * self.micro_lamports as u128).div_ceil(1_000_000) as u64;
+        signatures * LAMPORTS_PER_SIGNATURE + priority_fee + self.tip_lamports()
+    }
+
+    fn tip_lamports(&self) -> u64 {
+        let Some((_, scaler)) = &self.tip else {
+            return 0;
+        };
+        let trade_lamports = match self.trade_type {
+            TradeType::Sell => self.params.reference_out.unwrap_or(0),
+            _ => self.params.amount,
+        };
+        scaler.tip_lamports(trade_lamports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swqos::dryrun::DryRunClient;
+    use crate::trading::factory::DexType;
+    use solana_sdk::instruction::AccountMeta;
+
+    struct StubProtocol;
+
+    #[async_trait::async_trait]
+    impl DexProtocol for StubProtocol {
+        fn venue(&self) -> DexType {
+            DexType::PumpFun
+        }
+
+        async fn build_buy(&self, _: &SolanaRpcClient, params: &TradeParams) -> Result<Vec<Instruction>> {
+            let owner = AccountMeta::new(params.payer.pubkey(), true);
+            Ok(vec![Instruction::new_with_bytes(params.mint, &[1], vec![owner])])
+        }
+
+        async fn build_sell(&self, _: &SolanaRpcClient, params: &TradeParams) -> Result<Vec<Instruction>> {
+            Ok(vec![Instruction::new_with_bytes(params.mint, &[2], vec![])])
+        }
+
+        async fn get_price(&self, _: &SolanaRpcClient, _: &Pubkey) -> Result<f64> {
+            Ok(0.0)
+        }
+
+        async fn get_reserves(&self, _: &SolanaRpcClient, _: &Pubkey) -> Result<(u64, u64)> {
+            Ok((0, 0))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_orders_compute_budget_tip_swap() {
+        let rpc = SolanaRpcClient::new("http://127.0.0.1:8899".to_string());
+        let mint = Pubkey::new_unique();
+        let params = TradeParams::new(mint, 1_000_000, 100, Arc::new(Keypair::new()));
+        let transaction = TradeBuilder::buy(Arc::new(StubProtocol), params)
+            .with_compute_budget(Some(200_000), 10_000)
+            .with_tip(Arc::new(DryRunClient::new()), 100_000)
+            .with_recent_blockhash(Hash::new_unique())
+            .build(&rpc)
+            .await
+            .unwrap();
+
+        let keys = transaction.message.static_account_keys();
+        let programs: Vec<Pubkey> = transaction
+            .message
+            .instructions()
+            .iter()
+            .map(|instruction| keys[instruction.program_id_index as usize])
+            .collect();
+        assert_eq!(
+            programs,
+            vec![
+                solana_compute_budget_interface::id(),
+                solana_compute_budget_interface::id(),
+                solana_system_interface::program::id(),
+                mint,
+            ]
+        );
+        assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+    }
+
+    #[tokio::test]
+    async fn test_fee_payer_signs_first() {
+        let rpc = SolanaRpcClient::new("http://127.0.0.1:8899".to_string());
+        let owner = Arc::new(Keypair::new());
+        let relayer = Arc::new(Keypair::new());
+        let params = TradeParams::new(Pubkey::new_unique(), 1_000_000, 100, owner.clone());
+        let transaction = TradeBuilder::buy(Arc::new(StubProtocol), params)
+            .with_fee_payer(relayer.clone())
+            .with_recent_blockhash(Hash::new_unique())
+            .build(&rpc)
+            .await
+            .unwrap();
+
+        let keys = transaction.message.static_account_keys();
+        assert_eq!(keys[0], relayer.pubkey());
+        assert_eq!(keys[1], owner.pubkey());
+        assert_eq!(transaction.signatures.len(), 2);
+        assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+    }
+
+    #[tokio::test]
+    async fn test_build_for_wallets_signs_per_wallet() {
+        let rpc = SolanaRpcClient::new("http://127.0.0.1:8899".to_string());
+        let wallets: Vec<Arc<Keypair>> = (0..3).map(|_| Arc::new(Keypair::new())).collect();
+        let params = TradeParams::new(Pubkey::new_unique(), 1_000_000, 100, wallets[0].clone());
+        let transactions = TradeBuilder::buy(Arc::new(StubProtocol), params)
+            .with_recent_blockhash(Hash::new_unique())
+            .build_for_wallets(&rpc, &wallets)
+            .await;
+
+        assert_eq!(transactions.len(), wallets.len());
+        for (transaction, wallet) in transactions.iter().zip(&wallets) {
+            let transaction = transaction.as_ref().unwrap();
+            assert_eq!(transaction.message.static_account_keys()[0], wallet.pubkey());
+            assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+        }
+    }
+
+    #[test]
+    fn test_estimated_fee_counts_signatures_priority_and_tip() {
+        let params = TradeParams::new(Pubkey::new_unique(), 1_000_000, 100, Arc::new(Keypair::new()));
+        let builder = TradeBuilder::buy(Arc::new(StubProtocol), params)
+            .with_compute_budget(Some(200_000), 10_000)
+            .with_tip(Arc::new(DryRunClient::new()), 100_000);
+        // 200k units at 10k micro-lamports each is 2_000 lamports of priority fee
+        assert_eq!(builder.estimated_fee(2), 10_000 + 2_000 + 100_000);
+    }
+
+    #[tokio::test]
+    async fn test_extra_instructions_wrap_swap() {
+        let rpc = SolanaRpcClient::new("http://127.0.0.1:8899".to_string());
+        let mint = Pubkey::new_unique();
+        let memo = Pubkey::new_unique();
+        let logger = Pubkey::new_unique();
+        let params = TradeParams::new(mint, 1_000_000, 100, Arc::new(Keypair::new()));
+        let builder = TradeBuilder::buy(Arc::new(StubProtocol), params)
+            .with_compute_budget(Some(200_000), 10_000)
+            .with_pre_instructions(vec![Instruction::new_with_bytes(memo, b"memo", vec![])])
+            .with_post_instructions(vec![Instruction::new_with_bytes(logger, &[0], vec![])])
+            .with_recent_blockhash(Hash::new_unique());
+        let transaction = builder.build(&rpc).await.unwrap();
+
+        let keys = transaction.message.static_account_keys();
+        let programs: Vec<Pubkey> = transaction
+            .message
+            .instructions()
+            .iter()
+            .map(|instruction| keys[instruction.program_id_index as usize])
+            .collect();
+        assert_eq!(programs[2..], [memo, mint, logger]);
+
+        let oversized = builder.with_post_instructions(vec![Instruction::new_with_bytes(logger, &[0; 1232], vec![])]);
+        let err = oversized.build(&rpc).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<crate::swqos::common::SwqosError>(),
+            Some(crate::swqos::common::SwqosError::TransactionTooLarge { .. })
+        ));
+    }
+}
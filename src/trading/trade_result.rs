@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use futures::future::join_all;
+use solana_commitment_config::CommitmentConfig;
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signature::Signature,
+    signer::Signer,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
+    UiMessage, UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+
+use crate::common::SolanaRpcClient;
+use crate::events::pumpfun::parse_pumpfun_trades;
+use crate::swqos::SwqosClient;
+use crate::trading::trade_builder::TradeBuilder;
+
+/// Decimals of Pump.fun tokens, used to price fills read from the trade event
+const PUMPFUN_TOKEN_DECIMALS: u8 = 6;
+
+/// What a confirmed trade actually filled at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeResult {
+    pub signature: Signature,
+    /// Slot the transaction was confirmed in
+    pub slot: u64,
+    /// Lamports spent on a buy or received from a sell, fees excluded
+    pub actual_sol: u64,
+    /// Raw token amount bought or sold
+    pub actual_tokens: u64,
+    /// Fill price in SOL per whole token
+    pub price: f64,
+    /// Transaction fee in lamports, base and priority fee
+    pub fee_paid: u64,
+}
+
+/// Build the trade, send it through `swqos_client`, wait for confirmation and read the fill from
+/// the confirmed transaction, see `fetch_trade_result`
+pub async fn execute_trade(
+    rpc: &SolanaRpcClient,
+    swqos_client: &SwqosClient,
+    builder: &TradeBuilder,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<TradeResult> {
+    let transaction = builder.build(rpc).await?;
+    let signature = swqos_client.send_transaction(builder.trade_type(), &transaction, true).await?;
+    fetch_trade_result(rpc, &signature, owner, mint).await
+}
+
+/// Execute the same trade from every wallet in `wallets`, e.g. to join a launch with a fleet.
+///
+/// All transactions are built first, see `TradeBuilder::build_for_wallets`, then submitted
+/// through `swqos_client` at once so they land as close together as possible. Each wallet gets
+/// its own result, in the order of `wallets`; one wallet failing does not stop the others.
+pub async fn execute_multi_wallet(
+    rpc: &SolanaRpcClient,
+    swqos_client: &SwqosClient,
+    builder: &TradeBuilder,
+    wallets: &[Arc<Keypair>],
+) -> Vec<Result<TradeResult>> {
+    let transactions = builder.build_for_wallets(rpc, wallets).await;
+    let mint = builder.params().mint;
+    join_all(transactions.into_iter().zip(wallets).map(|(transaction, wallet)| async move {
+        let transaction = transaction?;
+        let signature =
+            swqos_client.send_transaction(builder.trade_type(), &transaction, true).await?;
+        fetch_trade_result(rpc, &signature, &wallet.pubkey(), &mint).await
+    }))
+    .await
+}
+
+/// Fetch a confirmed trade of `owner` in `mint` and extract its fill.
+///
+/// The amounts come from the Pump.fun trade events when the transaction has any, otherwise from
+/// the owner's token and SOL balance changes; those SOL amounts then also include tips and rent
+/// of accounts created or closed by the transaction.
+pub async fn fetch_trade_result(
+    rpc: &SolanaRpcClient,
+    signature: &Signature,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<TradeResult> {
+    let transaction = rpc
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                max_supported_transaction_version: Some(0),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await?;
+    trade_result(*signature, &transaction, owner, mint)
+}
+
+fn trade_result(
+    signature: Signature,
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<TradeResult> {
+    let meta = transaction
+        .transaction
+        .meta
+        .as_ref()
+        .ok_or_else(|| anyhow!("Transaction {} has no status metadata", signature))?;
+    if let Some(err) = &meta.err {
+        return Err(anyhow!("Transaction {} failed: {}", signature, err));
+    }
+
+    let logs = match &meta.log_messages {
+        OptionSerializer::Some(logs) => logs.as_slice(),
+        _ => &[],
+    };
+    let fills: Vec<_> = parse_pumpfun_trades(logs)
+        .into_iter()
+        .filter(|event| event.user == *owner && event.mint == *mint)
+        .collect();
+    let (actual_sol, actual_tokens, decimals) = if fills.is_empty() {
+        balance_changes(transaction, meta, owner, mint)?
+    } else {
+        let sol = fills.iter().map(|event| event.sol_amount).sum();
+        let tokens = fills.iter().map(|event| event.token_amount).sum();
+        (sol, tokens, PUMPFUN_TOKEN_DECIMALS)
+    };
+
+    let whole_tokens = actual_tokens as f64 / 10f64.powi(decimals as i32);
+    let price = if whole_tokens == 0.0 { 0.0 } else { actual_sol as f64 / LAMPORTS_PER_SOL as f64 / whole_tokens };
+    Ok(TradeResult { signature, slot: transaction.slot, actual_sol, actual_tokens, price, fee_paid: meta.fee })
+}
+
+/// `(lamports, tokens, decimals)` the owner's balances moved by, fee excluded
+fn balance_changes(
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+    meta: &UiTransactionStatusMeta,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<(u64, u64, u8)> {
+    let token_balance = |balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>| {
+        let OptionSerializer::Some(balances) = balances else {
+            return (0, None);
+        };
+        balances
+            .iter()
+            .filter(|balance| {
+                balance.mint == mint.to_string()
+                    && matches!(&balance.owner, OptionSerializer::Some(o) if *o == owner.to_string())
+            })
+            .fold((0u64, None), |(amount, decimals), balance| {
+                let raw = balance.ui_token_amount.amount.parse::<u64>().unwrap_or(0);
+                (amount + raw, decimals.or(Some(balance.ui_token_amount.decimals)))
+            })
+    };
+    let (pre_tokens, pre_decimals) = token_balance(&meta.pre_token_balances);
+    let (post_tokens, post_decimals) = token_balance(&meta.post_token_balances);
+    let decimals = post_decimals
+        .or(pre_decimals)
+        .ok_or_else(|| anyhow!("Transaction did not move {}'s balance of {}", owner, mint))?;
+
+    let account_keys = match &transaction.transaction.transaction {
+        EncodedTransaction::Json(ui_transaction) => match &ui_transaction.message {
+            UiMessage::Raw(message) => message.account_keys.clone(),
+            UiMessage::Parsed(message) => {
+                message.account_keys.iter().map(|key| key.pubkey.clone()).collect()
+            }
+        },
+        _ => return Err(anyhow!("Transaction is not JSON encoded")),
+    };
+    let index = account_keys
+        .iter()
+        .position(|key| *key == owner.to_string())
+        .ok_or_else(|| anyhow!("{} is not an account of the transaction", owner))?;
+    let pre_lamports = meta.pre_balances.get(index).copied().unwrap_or(0) as i128;
+    let post_lamports = meta.post_balances.get(index).copied().unwrap_or(0) as i128;
+    // The fee is only taken from account_keys[0]
+    let fee = if index == 0 { meta.fee as i128 } else { 0 };
+    let lamports = (post_lamports - pre_lamports + fee).unsigned_abs() as u64;
+
+    Ok((lamports, pre_tokens.abs_diff(post_tokens), decimals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_trade_result_from_balance_changes() {
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let token_balance = |amount: &str| {
+            json!([{
+                "accountIndex": 1,
+                "mint": mint.to_string(),
+                "owner": owner.to_string(),
+                "programId": crate::constants::TOKEN_PROGRAM.to_string(),
+                "uiTokenAmount": { "uiAmount": null, "decimals": 6, "amount": amount, "uiAmountString": "" },
+            }])
+        };
+        let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(json!({
+            "slot": 42,
+            "blockTime": null,
+            // The transaction and its meta are flattened into the top level
+            "transaction": {
+                "signatures": [Signature::default().to_string()],
+                "message": {
+                    "header": { "numRequiredSignatures": 1, "numReadonlySignedAccounts": 0, "numReadonlyUnsignedAccounts": 0 },
+                    "accountKeys": [owner.to_string(), Pubkey::new_unique().to_string()],
+                    "recentBlockhash": solana_hash::Hash::default().to_string(),
+                    "instructions": [],
+                },
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 10_000,
+                // 0.5 SOL spent on the swap on top of the fee
+                "preBalances": [2_000_000_000u64, 0],
+                "postBalances": [1_499_990_000u64, 0],
+                "preTokenBalances": token_balance("0"),
+                "postTokenBalances": token_balance("2000000"),
+                "logMessages": [],
+            },
+        }))
+        .unwrap();
+
+        let result = trade_result(Signature::default(), &transaction, &owner, &mint).unwrap();
+        assert_eq!(result.slot, 42);
+        assert_eq!(result.actual_sol, 500_000_000);
+        assert_eq!(result.actual_tokens, 2_000_000);
+        assert_eq!(result.price, 0.25);
+        assert_eq!(result.fee_paid, 10_000);
+    }
+}
@@ -65,3 +65,82 @@ pub const fn calculate_with_slippage_sell(amount: u64, basis_points: u64) -> u64
         amount - (amount * basis_points / 10000)
     }
 }
+
+/// Constant-product output for `amount_in`, before pool fees
+///
+/// `amount_out = reserve_out * amount_in / (reserve_in + amount_in)`, rounded down.
+#[inline(always)]
+pub const fn constant_product_amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64) -> u64 {
+    let denominator = reserve_in as u128 + amount_in as u128;
+    if denominator == 0 {
+        return 0;
+    }
+    (reserve_out as u128 * amount_in as u128 / denominator) as u64
+}
+
+/// Constant-product input needed to receive `amount_out`, before pool fees
+///
+/// `amount_in = reserve_in * amount_out / (reserve_out - amount_out)`, rounded up. Returns
+/// `u64::MAX` when `amount_out` would drain the pool.
+#[inline(always)]
+pub const fn constant_product_amount_in(amount_out: u64, reserve_in: u64, reserve_out: u64) -> u64 {
+    if amount_out >= reserve_out {
+        return u64::MAX;
+    }
+    let amount_in = ceil_div(reserve_in as u128 * amount_out as u128, (reserve_out - amount_out) as u128);
+    if amount_in > u64::MAX as u128 {
+        u64::MAX
+    } else {
+        amount_in as u64
+    }
+}
+
+/// Worst-case output of swapping `amount_in`, as enforced by the sell builders
+///
+/// The constant-product quote reduced by `slippage_bps` with `calculate_with_slippage_sell`.
+/// Protocol fees are not deducted, quote through the protocol's own calculator to include them.
+#[inline(always)]
+pub const fn calc_min_out(amount_in: u64, reserve_in: u64, reserve_out: u64, slippage_bps: u16) -> u64 {
+    calculate_with_slippage_sell(
+        constant_product_amount_out(amount_in, reserve_in, reserve_out),
+        slippage_bps as u64,
+    )
+}
+
+/// Worst-case input to receive `amount_out`, as enforced by the buy builders
+///
+/// The constant-product quote raised by `slippage_bps` with `calculate_with_slippage_buy`.
+/// Protocol fees are not added, quote through the protocol's own calculator to include them.
+#[inline(always)]
+pub const fn calc_max_in(amount_out: u64, reserve_in: u64, reserve_out: u64, slippage_bps: u16) -> u64 {
+    let amount_in = constant_product_amount_in(amount_out, reserve_in, reserve_out);
+    if amount_in == u64::MAX {
+        return u64::MAX;
+    }
+    calculate_with_slippage_buy(amount_in, slippage_bps as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calc_min_out_and_max_in() {
+        // 1 SOL into a 30 SOL / 1.073B token curve
+        let tokens = constant_product_amount_out(1_000_000_000, 30_000_000_000, 1_073_000_000_000_000);
+        assert_eq!(tokens, 34_612_903_225_806);
+        assert_eq!(
+            calc_min_out(1_000_000_000, 30_000_000_000, 1_073_000_000_000_000, 100),
+            calculate_with_slippage_sell(tokens, 100)
+        );
+
+        // Buying those tokens back costs at most the SOL spent, plus slippage
+        let sol = constant_product_amount_in(tokens, 30_000_000_000, 1_073_000_000_000_000);
+        assert!(sol <= 1_000_000_000 && sol > 999_999_000);
+        assert_eq!(
+            calc_max_in(tokens, 30_000_000_000, 1_073_000_000_000_000, 100),
+            calculate_with_slippage_buy(sol, 100)
+        );
+        assert_eq!(calc_max_in(10, 10, 10, 100), u64::MAX);
+    }
+}